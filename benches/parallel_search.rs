@@ -0,0 +1,64 @@
+//! Node rate of the alpha-beta search for 1, 2, 4... threads up to the core count.
+//!
+//! cargo bench --bench parallel_search
+
+use std::time::Duration;
+use reversi::board::Board;
+use reversi::engine::Player;
+use reversi::engine::greedy::Greedy;
+use reversi::engine::search::{SearchLimits, Searcher};
+
+const OPENING_MOVES: usize = 20;
+const TIME_PER_POSITION: Duration = Duration::from_secs(2);
+
+fn main() {
+
+    // middle game positions, reached by letting a seeded random player open the game
+    let positions: Vec<Board> = (0..4).map(|seed| {
+        let mut board = Board::new();
+        let mut player = Greedy::new(1.0, seed);
+
+        for _ in 0..OPENING_MOVES {
+            match player.choose_move(&board) {
+                Some((x, y)) => { board.play(x, y); },
+                None => board.pass(),
+            }
+        }
+
+        board
+    }).collect();
+
+    let limits = SearchLimits {
+        depth: 60,
+        time: Some(TIME_PER_POSITION),
+        soft_time: None,
+    };
+
+    let max_threads = std::thread::available_parallelism().map_or(1, |threads| threads.get());
+    let mut single_thread_rate = 0.0;
+    let mut threads = 1;
+
+    println!("{:>8} {:>12} {:>12} {:>8}", "threads", "nodes", "nodes/s", "speedup");
+
+    while threads <= max_threads {
+        let mut searcher = Searcher::new(threads);
+        let mut nodes = 0;
+        let mut seconds = 0.0;
+
+        for board in &positions {
+            searcher.clear();
+            let result = searcher.search(board, &limits);
+            nodes += result.nodes;
+            seconds += result.elapsed.as_secs_f64();
+        }
+
+        let rate = nodes as f64 / seconds;
+        if threads == 1 {
+            single_thread_rate = rate;
+        }
+
+        println!("{:>8} {:>12} {:>12.0} {:>7.2}x", threads, nodes, rate, rate / single_thread_rate);
+
+        threads = if threads * 2 > max_threads && threads < max_threads { max_threads } else { threads * 2 };
+    }
+}
@@ -0,0 +1,488 @@
+use std::io::{self, BufRead, Read, Write};
+use std::process;
+use std::time::Duration;
+use reversi::board::{move_name, parse_move, PlayerTurn};
+use reversi::game::Game;
+use reversi::engine::eval::DISC;
+use reversi::engine::search::{SearchLimits, Searcher};
+use reversi::mode::{GameMode, Opponent};
+use reversi::nboard::NBoardEngine;
+use reversi::net::protocol::{with_default_port, Connection, Message, Offer, DEFAULT_PORT, VERSION};
+use reversi::net::session::{NetEvent, Session};
+use reversi::notation::{ascii, board_string, ggf, parse_board_string, parse_ggf, parse_transcript, transcript};
+use reversi::profile::{ProfileStats, Profiles};
+use reversi::rating::{leaderboard, RatingStore};
+use reversi::settings::Settings;
+
+
+const USAGE: &str = "\
+usage:
+  reversi-cli play [--engine NAME] [--color black|white] [--black NAME] [--white NAME]
+      plays a game in the terminal, against the engine when one is named,
+      the finished game is rated under the players' names
+      engines: greedy1, greedy2, greedy3, mcts, alphabeta
+  reversi-cli analyze POSITION [--depth N] [--time SECONDS]
+      searches a position, given as a board string (64 squares of X, O and -
+      followed by the side to move) or as a transcript of moves from the start
+  reversi-cli convert [FILE] [--to transcript|ggf] [--black NAME] [--white NAME]
+      converts a game between a transcript (\"f5d6c3...\") and GGF, reads
+      standard input without a FILE
+  reversi-cli nboard [--depth N]
+      runs as an engine for NBoard compatible GUIs over standard input and output
+  reversi-cli host [--port N] [--name NAME]
+      waits for an opponent to join over the network and plays Black against it
+  reversi-cli join ADDRESS[:PORT] [--name NAME]
+      joins a hosted game, the host assigns the color, or a reversi-server that
+      pairs it with another player. On its move a player can also type
+      \"say TEXT\", \"draw\", \"takeback\" or \"resign\"
+  reversi-cli watch ADDRESS[:PORT] [GAME] [--name NAME]
+      lists the games of a reversi-server, or follows the numbered one
+  reversi-cli ratings [--limit N]
+      lists the Elo ratings of the players and engines of every recorded game
+  reversi-cli profile NAME
+      keeps NAME as a player profile to choose in the game window
+  reversi-cli stats [NAME...]
+      shows the record of the named players, of every profile without names
+common options:
+  --threads N    search threads, all cores by default
+";
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+
+    let result = match args.first().map(String::as_str) {
+        Some("play") => play(&args[1..]),
+        Some("analyze") => analyze(&args[1..]),
+        Some("convert") => convert(&args[1..]),
+        Some("nboard") => nboard(&args[1..]),
+        Some("host") => host(&args[1..]),
+        Some("join") => join(&args[1..]),
+        Some("watch") => watch(&args[1..]),
+        Some("ratings") => ratings(&args[1..]),
+        Some("profile") => profile(&args[1..]),
+        Some("stats") => stats(&args[1..]),
+        Some("help" | "--help" | "-h") => {
+            print!("{}", USAGE);
+            Ok(())
+        },
+        _ => Err(USAGE.to_string()),
+    };
+
+    if let Err(err) = result {
+        eprintln!("{}", err);
+        process::exit(2);
+    }
+}
+
+// value following `--name`
+fn option<'a>(args: &'a [String], name: &str) -> Option<&'a str> {
+    args.iter()
+        .position(|arg| arg == name)
+        .and_then(|idx| args.get(idx + 1))
+        .map(String::as_str)
+}
+
+// arguments that are neither options nor their values, board strings may start with "--" too
+fn positional(args: &[String]) -> Vec<&str> {
+    let mut out = Vec::new();
+    let mut idx = 0;
+
+    while idx < args.len() {
+        if args[idx].strip_prefix("--").is_some_and(|name| name.starts_with(|chr: char| chr.is_ascii_alphabetic())) {
+            idx += 2;
+        } else {
+            out.push(args[idx].as_str());
+            idx += 1;
+        }
+    }
+
+    out
+}
+
+fn parse_option<T: std::str::FromStr>(args: &[String], name: &str) -> Result<Option<T>, String> {
+    option(args, name)
+        .map(|value| value.parse().map_err(|_| format!("{} takes a number, got '{}'", name, value)))
+        .transpose()
+}
+
+fn settings(args: &[String]) -> Result<Settings, String> {
+    let mut settings = Settings::default();
+
+    if let Some(threads) = parse_option(args, "--threads")? {
+        settings.threads = threads;
+    }
+
+    Ok(settings)
+}
+
+
+fn play(args: &[String]) -> Result<(), String> {
+    let mut settings = settings(args)?;
+
+    if let Some(name) = option(args, "--black") {
+        settings.black_name = name.to_string();
+    }
+    if let Some(name) = option(args, "--white") {
+        settings.white_name = name.to_string();
+    }
+
+    let mode = match option(args, "--engine") {
+        Some(name) => GameMode::PvE(Opponent::from_name(name).ok_or_else(|| format!("unknown engine '{}', pick one of {}", name, Opponent::NAMES.join(", ")))?),
+        None => GameMode::PvP,
+    };
+
+    let mut game = Game::new(mode, &settings);
+
+    game.computer_side = match option(args, "--color") {
+        None | Some("black") => PlayerTurn::White,
+        Some("white") => PlayerTurn::Black,
+        Some(color) => return Err(format!("unknown color '{}'", color)),
+    };
+
+    let stdin = io::stdin();
+    let mut lines = stdin.lock().lines();
+
+    while game.outcome().is_none() {
+        println!("{}", ascii(&game.board));
+        let side = game.board.player_turn;
+        let plies = game.history.len();
+
+        if let Some((x, y)) = game.computer_move() {
+            println!("{} plays {}\n", side.name(), move_name(x, y));
+        } else {
+            print!("{} to move (e.g. d3, quit): ", side.name());
+            io::stdout().flush().map_err(|err| err.to_string())?;
+
+            let line = match lines.next() {
+                Some(line) => line.map_err(|err| err.to_string())?,
+                None => return Ok(()),
+            };
+            let line = line.trim();
+
+            if line == "quit" {
+                return Ok(());
+            }
+
+            if !parse_move(line).is_some_and(|(x, y)| game.play(x, y)) {
+                println!("'{}' is no legal move\n", line);
+                continue;
+            }
+        }
+
+        // the move may have left the other side without one
+        if game.history.len() > plies + 1 {
+            println!("{} has to pass\n", game.board.player_turn.opposite().name());
+        }
+    }
+
+    println!("{}", ascii(&game.board));
+    println!("{}", game.outcome().map(|outcome| outcome.describe()).unwrap_or_default());
+    println!("{}", transcript(&game.history));
+
+    if let Some(entry) = game.rating_entry(&settings) {
+        RatingStore::open_default().record(entry).map_err(|err| format!("failed to record the game: {}", err))?;
+    }
+
+    Ok(())
+}
+
+
+fn analyze(args: &[String]) -> Result<(), String> {
+    let settings = settings(args)?;
+    let text = positional(args).join("");
+
+    let board = match parse_board_string(&text) {
+        Ok(board) => board,
+        Err(err) => match parse_transcript(&text) {
+            Ok(history) => history.positions().last().copied().unwrap_or_default(),
+            Err(_) => return Err(err),
+        },
+    };
+
+    let limits = SearchLimits {
+        depth: parse_option(args, "--depth")?.unwrap_or(board.empties() as u8),
+        time: Some(Duration::from_secs_f64(parse_option(args, "--time")?.unwrap_or(5.))),
+        soft_time: None,
+    };
+
+    println!("{}", ascii(&board));
+    println!("{}", board_string(&board));
+
+    let mut searcher = Searcher::new(settings.threads);
+    let result = searcher.search(&board, &limits);
+
+    let best_move = match result.best_move {
+        Some((x, y)) => move_name(x, y),
+        None if board.is_over() => "none, the game is over".to_string(),
+        None => "pass".to_string(),
+    };
+    let pv: Vec<String> = result.pv.iter().map(|(x, y)| move_name(*x, *y)).collect();
+
+    println!("best move: {}", best_move);
+    println!("score:     {:+.2} discs", result.score as f64 / DISC as f64);
+    println!("depth:     {}", result.depth);
+    println!("pv:        {}", pv.join(" "));
+    println!("nodes:     {} ({:.0} nodes/s)", result.nodes, result.nodes_per_second());
+
+    if board.has_valid_move() {
+        let move_limits = SearchLimits {
+            depth: result.depth.saturating_sub(1).max(1),
+            time: limits.time.map(|time| time / board.valid_moves().len() as u32),
+            soft_time: None,
+        };
+
+        let mut scores = searcher.score_moves(&board, &move_limits);
+        scores.sort_by_key(|(_, score)| -score);
+
+        println!("moves:");
+        for ((x, y), score) in scores {
+            println!("  {} {:+.2}", move_name(x, y), score as f64 / DISC as f64);
+        }
+    }
+
+    Ok(())
+}
+
+
+fn convert(args: &[String]) -> Result<(), String> {
+    let mut text = String::new();
+
+    match positional(args).first() {
+        None | Some(&"-") => io::stdin().read_to_string(&mut text).map(|_| ()),
+        Some(path) => std::fs::read_to_string(path).map(|contents| text = contents),
+    }.map_err(|err| err.to_string())?;
+
+    let text = text.trim();
+    let is_ggf = text.starts_with("(;");
+
+    let (history, black, white) = if is_ggf {
+        let game = parse_ggf(text)?;
+        (game.history, game.black, game.white)
+    } else {
+        (parse_transcript(text)?, String::new(), String::new())
+    };
+
+    let black = option(args, "--black").map_or(black, str::to_string);
+    let white = option(args, "--white").map_or(white, str::to_string);
+
+    // the other format unless told otherwise
+    let to = option(args, "--to").unwrap_or(if is_ggf { "transcript" } else { "ggf" });
+
+    match to {
+        "transcript" => println!("{}", transcript(&history)),
+        "ggf" => println!("{}", ggf(&history, &black, &white)),
+        _ => return Err(format!("unknown format '{}'", to)),
+    }
+
+    Ok(())
+}
+
+
+fn nboard(args: &[String]) -> Result<(), String> {
+    let settings = settings(args)?;
+    let mut engine = NBoardEngine::new(settings.threads);
+
+    if let Some(depth) = parse_option::<u8>(args, "--depth")? {
+        engine.handle(&format!("set depth {}", depth), &mut io::sink()).map_err(|err| err.to_string())?;
+    }
+
+    engine.run(io::stdin().lock(), io::stdout().lock()).map_err(|err| err.to_string())
+}
+
+
+fn host(args: &[String]) -> Result<(), String> {
+    let port = parse_option(args, "--port")?.unwrap_or(DEFAULT_PORT);
+    let name = option(args, "--name").unwrap_or("host");
+    let session = Session::host(port, name).map_err(|err| format!("failed to listen on port {}: {}", port, err))?;
+
+    network(session)
+}
+
+fn join(args: &[String]) -> Result<(), String> {
+    let address = address(args)?;
+    let name = option(args, "--name").unwrap_or("guest");
+    let session = Session::join(&address, name).map_err(|err| format!("failed to join {}: {}", address, err))?;
+
+    network(session)
+}
+
+fn watch(args: &[String]) -> Result<(), String> {
+    let address = address(args)?;
+    // names are unique on a server, spectators needn't pick one
+    let name = option(args, "--name").map_or_else(|| format!("spectator {}", process::id()), str::to_string);
+
+    let Some(game) = positional(args).get(1).copied() else {
+        return list_games(&address, &name);
+    };
+    let game = game.parse().map_err(|_| format!("'{}' is no game number", game))?;
+    let session = Session::watch(&address, &name, game).map_err(|err| format!("failed to join {}: {}", address, err))?;
+
+    network(session)
+}
+
+// first positional argument, `Session` adds the default port when it has none
+fn address(args: &[String]) -> Result<String, String> {
+    positional(args).first().map(|address| address.to_string()).ok_or_else(|| "the host's address is missing".to_string())
+}
+
+fn list_games(address: &str, name: &str) -> Result<(), String> {
+    const WAIT: Duration = Duration::from_secs(2);
+
+    let stream = std::net::TcpStream::connect(with_default_port(address)).map_err(|err| format!("failed to join {}: {}", address, err))?;
+    let mut connection = Connection::new(stream).map_err(|err| err.to_string())?;
+    connection.send(&Message::Hello { version: VERSION, token: None, name: name.to_string() }).map_err(|err| err.to_string())?;
+    connection.send(&Message::List).map_err(|err| err.to_string())?;
+
+    let started = std::time::Instant::now();
+    let mut expected = None;
+    let mut listed = 0;
+
+    while started.elapsed() < WAIT && expected != Some(listed) {
+        for message in connection.receive().map_err(|err| err.to_string())? {
+            match message? {
+                Message::Games(count) => expected = Some(count),
+                Message::Game { id, text } => {
+                    println!("{:>4}  {}", id, text);
+                    listed += 1;
+                },
+                Message::Error(text) => return Err(text),
+                _ => {},
+            }
+        }
+
+        std::thread::sleep(Duration::from_millis(20));
+    }
+
+    if expected == Some(0) {
+        println!("no games");
+    }
+
+    connection.send(&Message::Bye).map_err(|err| err.to_string())
+}
+
+// plays or watches a networked game until it is over, the opponent's moves are waited for
+fn network(mut session: Session) -> Result<(), String> {
+    const POLL: Duration = Duration::from_millis(100);
+
+    let stdin = io::stdin();
+    let mut lines = stdin.lock().lines();
+    let mut status = String::new();
+
+    loop {
+        for event in session.poll() {
+            match event {
+                NetEvent::Joined(name) if session.is_watching() => println!("watching {}", name),
+                NetEvent::Joined(name) => println!("{} joined", name),
+                // the board is shown with the prompt when it's our move
+                NetEvent::Updated if !session.is_my_turn() => println!("{}", ascii(&session.game.board)),
+                NetEvent::Updated => {},
+                NetEvent::Left => println!("connection lost"),
+                NetEvent::Rejected(text) | NetEvent::Info(text) | NetEvent::Chat(text) => println!("{}", text),
+                // asked below
+                NetEvent::Offered(_) => {},
+                NetEvent::Answered(offer, accepted) => {
+                    println!("the {} offer was {}", offer.name(), if accepted { "accepted" } else { "declined" });
+                },
+            }
+        }
+
+        if session.status() != status {
+            status = session.status();
+            println!("{}", status);
+        }
+
+        if let Some(outcome) = session.game.outcome() {
+            println!("{}", outcome.describe());
+            println!("{}", transcript(&session.game.history));
+            return Ok(());
+        }
+
+        if let Some(offer) = session.offer {
+            let peer = session.peer_name.clone().unwrap_or_default();
+            print!("{} offers a {}, accept? (y/n): ", peer, offer.name());
+            io::stdout().flush().map_err(|err| err.to_string())?;
+
+            let Some(line) = lines.next() else {
+                return Ok(());
+            };
+            let accept = line.map_err(|err| err.to_string())?.trim().starts_with('y');
+
+            if let Err(err) = session.answer(accept) {
+                println!("{}", err);
+            }
+            continue;
+        }
+
+        // an offer of ours waits for the answer before the next move
+        if !session.is_my_turn() || session.offered.is_some() {
+            std::thread::sleep(POLL);
+            continue;
+        }
+
+        println!("{}", ascii(&session.game.board));
+        print!("{} to move (e.g. d3, say TEXT, draw, takeback, resign, quit): ", session.side.name());
+        io::stdout().flush().map_err(|err| err.to_string())?;
+
+        let line = match lines.next() {
+            Some(line) => line.map_err(|err| err.to_string())?,
+            None => return Ok(()),
+        };
+        let line = line.trim();
+
+        let result = match line {
+            "quit" => return Ok(()),
+            "resign" => session.resign(),
+            "draw" => session.propose(Offer::Draw),
+            "takeback" => session.propose(Offer::Takeback),
+            _ => match (line.strip_prefix("say "), parse_move(line)) {
+                (Some(text), _) => session.say(text),
+                (None, Some((x, y))) => session.play(x, y),
+                (None, None) => Err(format!("'{}' is no square", line)),
+            },
+        };
+
+        if let Err(err) = result {
+            println!("{}", err);
+        }
+    }
+}
+
+
+fn ratings(args: &[String]) -> Result<(), String> {
+    let limit = parse_option(args, "--limit")?.unwrap_or(usize::MAX);
+    let store = RatingStore::open_default();
+
+    println!("{}", leaderboard(&store.standings(), limit));
+    Ok(())
+}
+
+
+fn profile(args: &[String]) -> Result<(), String> {
+    let name = args.first().ok_or_else(|| USAGE.to_string())?;
+    let mut profiles = Profiles::open_default();
+
+    match profiles.add(name).map_err(|err| format!("failed to save the profile: {}", err))? {
+        true => println!("added {}", name.trim()),
+        false => println!("{} is a profile already", name.trim()),
+    }
+    Ok(())
+}
+
+fn stats(args: &[String]) -> Result<(), String> {
+    let names = match args.is_empty() {
+        true => Profiles::open_default().names,
+        false => args.to_vec(),
+    };
+
+    if names.is_empty() {
+        return Err("no profiles yet, add one with \"reversi-cli profile NAME\"".to_string());
+    }
+
+    let store = RatingStore::open_default();
+    let reports: Vec<String> = names.iter()
+        .map(|name| ProfileStats::from_games(name, &store.games).report())
+        .collect();
+
+    println!("{}", reports.join("\n\n"));
+    Ok(())
+}
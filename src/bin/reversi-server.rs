@@ -0,0 +1,59 @@
+use std::path::PathBuf;
+use std::process;
+use std::thread;
+use std::time::Duration;
+use reversi::net::protocol::DEFAULT_PORT;
+use reversi::net::server::Server;
+
+
+const USAGE: &str = "\
+usage:
+  reversi-server [--port N] [--archive FILE]
+      runs games for the app and reversi-cli: clients that join are paired into games,
+      `reversi-cli watch` lists and follows them
+      --archive    appends every finished game to FILE in GGF, reversi-server.ggf by default
+";
+
+// pause between polls of the clients
+const POLL: Duration = Duration::from_millis(20);
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+
+    if args.iter().any(|arg| matches!(arg.as_str(), "help" | "--help" | "-h")) {
+        print!("{}", USAGE);
+        return;
+    }
+
+    if let Err(err) = run(&args) {
+        eprintln!("{}", err);
+        process::exit(2);
+    }
+}
+
+// value following `--name`
+fn option<'a>(args: &'a [String], name: &str) -> Option<&'a str> {
+    args.iter()
+        .position(|arg| arg == name)
+        .and_then(|idx| args.get(idx + 1))
+        .map(String::as_str)
+}
+
+fn run(args: &[String]) -> Result<(), String> {
+    let port = match option(args, "--port") {
+        Some(port) => port.parse().map_err(|_| format!("--port takes a number, got '{}'", port))?,
+        None => DEFAULT_PORT,
+    };
+    let archive = PathBuf::from(option(args, "--archive").unwrap_or("reversi-server.ggf"));
+
+    let mut server = Server::bind(port, Some(archive.clone())).map_err(|err| format!("failed to listen on port {}: {}", port, err))?;
+    eprintln!("listening on port {}, archiving to {}", server.port(), archive.display());
+
+    loop {
+        for line in server.poll() {
+            eprintln!("{}", line);
+        }
+
+        thread::sleep(POLL);
+    }
+}
@@ -0,0 +1,112 @@
+use std::fs;
+use std::process;
+use reversi::mode::Opponent;
+use reversi::settings::Settings;
+use reversi::tournament::{Entrant, Format, Tournament, OPENINGS};
+
+
+const USAGE: &str = "\
+usage:
+  reversi-tournament [--format round-robin|gauntlet] [--engine NAME]... [--external NAME=COMMAND]...
+                     [--openings N] [--archive FILE] [--threads N]
+      plays every pairing from each opening twice, once with either color, and prints
+      the standings and pairings with win rates and Elo differences (95% margins)
+      engines: greedy1, greedy2, greedy3, mcts, alphabeta
+      external engines speak the NBoard protocol, e.g. --external mine=\"./engine --level 5\"
+      a gauntlet plays the first entrant against all others
+      --openings    how many of the 8 balanced openings to use, all by default
+      --archive     writes every game to FILE in GGF, one per line
+";
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+
+    if args.iter().any(|arg| matches!(arg.as_str(), "help" | "--help" | "-h")) {
+        print!("{}", USAGE);
+        return;
+    }
+
+    if let Err(err) = run(&args) {
+        eprintln!("{}", err);
+        process::exit(2);
+    }
+}
+
+// every value following `--name`, in order
+fn options<'a>(args: &'a [String], name: &str) -> Vec<&'a str> {
+    args.iter()
+        .enumerate()
+        .filter(|(_, arg)| *arg == name)
+        .filter_map(|(idx, _)| args.get(idx + 1))
+        .map(String::as_str)
+        .collect()
+}
+
+fn parse_option<T: std::str::FromStr>(args: &[String], name: &str) -> Result<Option<T>, String> {
+    options(args, name).last()
+        .map(|value| value.parse().map_err(|_| format!("{} takes a number, got '{}'", name, value)))
+        .transpose()
+}
+
+// entrants in the order their options were given
+fn entrants(args: &[String]) -> Result<Vec<Entrant>, String> {
+    let mut entrants = Vec::new();
+
+    for (idx, arg) in args.iter().enumerate() {
+        let Some(value) = args.get(idx + 1) else {
+            break;
+        };
+
+        match arg.as_str() {
+            "--engine" => {
+                if Opponent::from_name(value).is_none() {
+                    return Err(format!("unknown engine '{}', pick one of {}", value, Opponent::NAMES.join(", ")));
+                }
+                entrants.push(Entrant::Builtin(value.clone()));
+            },
+            "--external" => {
+                let (name, command) = value.split_once('=')
+                    .ok_or_else(|| format!("--external takes NAME=COMMAND, got '{}'", value))?;
+                entrants.push(Entrant::External { name: name.to_string(), command: command.to_string() });
+            },
+            _ => {},
+        }
+    }
+
+    if entrants.len() < 2 {
+        return Err(format!("a tournament needs two entrants or more\n{}", USAGE));
+    }
+
+    Ok(entrants)
+}
+
+fn run(args: &[String]) -> Result<(), String> {
+    let format = match options(args, "--format").last().copied() {
+        None | Some("round-robin") => Format::RoundRobin,
+        Some("gauntlet") => Format::Gauntlet,
+        Some(other) => return Err(format!("unknown format '{}', pick round-robin or gauntlet", other)),
+    };
+
+    let mut settings = Settings::default();
+    if let Some(threads) = parse_option(args, "--threads")? {
+        settings.threads = threads;
+    }
+
+    let openings = parse_option(args, "--openings")?.unwrap_or(OPENINGS.len());
+    let mut tournament = Tournament::new(entrants(args)?, format, openings, settings);
+    let total = tournament.pairings().len() * tournament.openings * 2;
+
+    tournament.run(|tournament, result| {
+        let (black, white) = (tournament.entrants[result.black].name(), tournament.entrants[result.white].name());
+        eprintln!("game {}/{}: {} - {} from {}, {:+} discs in {} moves",
+            tournament.results.len() + 1, total, black, white, OPENINGS[result.opening], result.disc_difference, result.history.len());
+    })?;
+
+    print!("{}", tournament.report());
+
+    if let Some(path) = options(args, "--archive").last() {
+        fs::write(path, tournament.archive()).map_err(|err| format!("failed to write {}: {}", path, err))?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,231 @@
+use std::io;
+use crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Paragraph};
+use ratatui::{DefaultTerminal, Frame};
+use reversi::board::{move_name, Cell, PlayerTurn, REVERSI_FIELD_HEIGHT, REVERSI_FIELD_WIDTH};
+use reversi::game::Game;
+use reversi::mode::GameMode;
+use reversi::rating::RatingStore;
+use reversi::settings::Settings;
+
+
+const HELP: &str = "\
+arrows   move the cursor
+enter    play at the cursor
+a1..h8   play a square by name
+m        next mode
+r        restart
+q        quit";
+
+fn main() -> io::Result<()> {
+    let mut terminal = ratatui::init();
+    let result = App::new().run(&mut terminal);
+    ratatui::restore();
+    result
+}
+
+/// Full screen terminal front end with the modes of the side panel
+struct App {
+    game: Game,
+    settings: Settings,
+    // index into `GameMode::PRESETS`
+    mode: usize,
+    cursor: (usize, usize),
+    // column letter typed while waiting for the row
+    typed: Option<char>,
+    message: String,
+    quit: bool,
+}
+
+impl App {
+    fn new() -> Self {
+        let settings = Settings::default();
+
+        Self {
+            game: Game::new(GameMode::PvP, &settings),
+            settings,
+            mode: 0,
+            cursor: (3, 2),
+            typed: None,
+            message: String::new(),
+            quit: false,
+        }
+    }
+
+    fn run(&mut self, terminal: &mut DefaultTerminal) -> io::Result<()> {
+        while !self.quit {
+            terminal.draw(|frame| self.draw(frame))?;
+
+            if self.game.is_computer_turn() {
+                self.message = "thinking...".to_string();
+                terminal.draw(|frame| self.draw(frame))?;
+
+                if let Some((x, y)) = self.game.computer_move() {
+                    self.message = format!("computer plays {}", move_name(x, y));
+                    self.record();
+                }
+                continue;
+            }
+
+            if let Event::Key(key) = event::read()? {
+                if key.kind == KeyEventKind::Press {
+                    self.key(key.code);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn restart(&mut self) {
+        let (_, mode) = &GameMode::PRESETS[self.mode];
+        self.game = Game::new(mode.clone(), &self.settings);
+        self.typed = None;
+        self.message = String::new();
+    }
+
+    fn key(&mut self, code: KeyCode) {
+        let (x, y) = self.cursor;
+
+        match code {
+            KeyCode::Char('q') | KeyCode::Esc => self.quit = true,
+            KeyCode::Char('r') => self.restart(),
+            KeyCode::Char('m') => {
+                self.mode = (self.mode + 1) % GameMode::PRESETS.len();
+                self.restart();
+            },
+            KeyCode::Left => self.cursor = (x.saturating_sub(1), y),
+            KeyCode::Right => self.cursor = ((x + 1).min(REVERSI_FIELD_WIDTH - 1), y),
+            KeyCode::Up => self.cursor = (x, y.saturating_sub(1)),
+            KeyCode::Down => self.cursor = (x, (y + 1).min(REVERSI_FIELD_HEIGHT - 1)),
+            KeyCode::Enter | KeyCode::Char(' ') => self.play(x, y),
+            KeyCode::Char(chr @ 'a'..='h') => self.typed = Some(chr),
+            KeyCode::Char(chr @ '1'..='8') => {
+                if let Some(column) = self.typed.take() {
+                    let cell = ((column as u8 - b'a') as usize, chr as usize - '1' as usize);
+                    self.cursor = cell;
+                    self.play(cell.0, cell.1);
+                }
+            },
+            _ => {},
+        }
+    }
+
+    fn play(&mut self, x: usize, y: usize) {
+        if self.game.outcome().is_some() {
+            return;
+        }
+
+        let side = self.game.board.player_turn;
+
+        self.message = if self.game.play(x, y) {
+            format!("{} plays {}", side.name(), move_name(x, y))
+        } else {
+            format!("{} is no legal move", move_name(x, y))
+        };
+
+        self.record();
+    }
+
+    // keeps the game in the rating store once it is over
+    fn record(&mut self) {
+        if let Some(entry) = self.game.rating_entry(&self.settings) {
+            if let Err(err) = RatingStore::open_default().record(entry) {
+                self.message = format!("failed to record the game: {}", err);
+            }
+        }
+    }
+
+    fn draw(&self, frame: &mut Frame) {
+        let [board_area, side_area] = Layout::horizontal([
+            Constraint::Length(4 + 3 * REVERSI_FIELD_WIDTH as u16),
+            Constraint::Min(30),
+        ]).areas(frame.area());
+
+        self.draw_board(frame, board_area);
+        self.draw_side(frame, side_area);
+    }
+
+    fn draw_board(&self, frame: &mut Frame, area: Rect) {
+        let board = &self.game.board;
+        let last_move = self.game.history.last_move().and_then(|played| played.cell);
+
+        let mut lines = vec![Line::from(
+            (0..REVERSI_FIELD_WIDTH).map(|x| format!(" {} ", (b'a' + x as u8) as char)).collect::<String>()
+        ).style(Style::new().fg(Color::Gray)).centered()];
+
+        for y in 0..REVERSI_FIELD_HEIGHT {
+            let mut spans = vec![Span::styled(format!("{} ", y + 1), Style::new().fg(Color::Gray))];
+
+            for x in 0..REVERSI_FIELD_WIDTH {
+                let (text, fg) = match board.field[y * REVERSI_FIELD_WIDTH + x] {
+                    Cell::Black(_) => (" ● ", Color::Black),
+                    Cell::White(_) => (" ● ", Color::White),
+                    Cell::Free if board.is_valid_cell(x, y) => (" · ", Color::Yellow),
+                    Cell::Free => ("   ", Color::Green),
+                };
+
+                let mut style = Style::new().fg(fg).bg(Color::Green);
+                if last_move == Some((x, y)) {
+                    style = style.bg(Color::LightGreen);
+                }
+                if self.cursor == (x, y) {
+                    style = style.bg(Color::Yellow).add_modifier(Modifier::BOLD);
+                }
+
+                spans.push(Span::styled(text, style));
+            }
+
+            lines.push(Line::from(spans));
+        }
+
+        let (black_score, white_score) = board.score();
+        lines.push(Line::default());
+        lines.push(Line::from(format!("Black: {}  White: {}", black_score, white_score)));
+
+        frame.render_widget(Paragraph::new(lines).block(Block::bordered().title(" REVERSI ")), area);
+    }
+
+    fn draw_side(&self, frame: &mut Frame, area: Rect) {
+        let [status_area, moves_area, help_area] = Layout::vertical([
+            Constraint::Length(6),
+            Constraint::Min(4),
+            Constraint::Length(9),
+        ]).areas(area);
+
+        let (label, _) = &GameMode::PRESETS[self.mode];
+        let status = match self.game.outcome() {
+            Some(outcome) => outcome.describe(),
+            None => format!("{} to move", self.game.board.player_turn.name()),
+        };
+
+        let mut lines = vec![
+            Line::from(format!("mode: {}", label)),
+            Line::from(status),
+            Line::from(self.message.clone()),
+        ];
+        if let Some(name) = self.game.opponent_name() {
+            lines.insert(1, Line::from(format!("computer: {}", name)));
+        }
+        frame.render_widget(Paragraph::new(lines).block(Block::bordered().title(" game ")), status_area);
+
+        // the latest moves that fit, oldest first
+        let shown = moves_area.height.saturating_sub(2) as usize;
+        let moves = &self.game.history.moves;
+        let lines: Vec<Line> = moves.iter()
+            .enumerate()
+            .skip(moves.len().saturating_sub(shown))
+            .map(|(ply, played)| {
+                let name = played.cell.map_or("pass".to_string(), |(x, y)| move_name(x, y));
+                let marker = if played.side == PlayerTurn::Black { "●" } else { "○" };
+                Line::from(format!("{:>3}. {} {}", ply + 1, marker, name))
+            })
+            .collect();
+        frame.render_widget(Paragraph::new(lines).block(Block::bordered().title(" moves ")), moves_area);
+
+        frame.render_widget(Paragraph::new(HELP).block(Block::bordered().title(" keys ")), help_area);
+    }
+}
@@ -0,0 +1,300 @@
+use crate::board::Cell::{Free, Black, White};
+
+pub const REVERSI_FIELD_WIDTH: usize = 8;
+pub const REVERSI_FIELD_HEIGHT: usize = 8;
+pub const REVERSI_FIELD_SIZE: usize = REVERSI_FIELD_WIDTH * REVERSI_FIELD_HEIGHT;
+pub type Field = [Cell ; REVERSI_FIELD_SIZE];
+
+pub const DIRECTIONS: [(isize, isize); 8] = [(1_isize, 0_isize),
+    (0_isize, 1_isize),
+    (-1_isize, 0_isize),
+    (0_isize, -1_isize),
+    (1_isize, 1_isize),
+    (1_isize, -1_isize),
+    (-1_isize, 1_isize),
+    (-1_isize, -1_isize)];
+
+
+/// Rules-level snapshot of a game: the field and the side to move, without any of the UI state
+/// `Reversi` carries. Engines search over copies of it.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Board {
+    pub field: Field,
+    pub player_turn: PlayerTurn,
+}
+
+impl Board {
+    pub fn new() -> Self {
+
+        let mut initial_field: Field = [Cell::Free ; REVERSI_FIELD_SIZE];
+        let top_left_center: (usize, usize) = ((REVERSI_FIELD_WIDTH / 2) - 1, (REVERSI_FIELD_HEIGHT / 2) - 1);
+
+        initial_field[top_left_center.1 * REVERSI_FIELD_WIDTH + top_left_center.0] = Cell::White(0);
+        initial_field[(top_left_center.1 + 1) * REVERSI_FIELD_WIDTH + top_left_center.0] = Cell::Black(0);
+        initial_field[top_left_center.1 * REVERSI_FIELD_WIDTH + top_left_center.0 + 1] = Cell::Black(0);
+        initial_field[(top_left_center.1 + 1) * REVERSI_FIELD_WIDTH + top_left_center.0 + 1] = Cell::White(0);
+
+        Self {
+            field: initial_field,
+            player_turn: PlayerTurn::Black,
+        }
+    }
+
+    // number of opponent discs a move at (x, y) encloses in the given direction
+    fn run_length(&self, x: isize, y: isize, (x_coef, y_coef): (isize, isize)) -> usize {
+        let mut factor = 1_isize;
+
+        while (0..REVERSI_FIELD_HEIGHT as isize).contains(&(y + y_coef * factor))
+            && (0..REVERSI_FIELD_WIDTH as isize).contains(&(x + x_coef * factor))
+            && self.player_turn.is_reverse_of(&self.field[(y + y_coef * factor) as usize * REVERSI_FIELD_WIDTH + (x + x_coef * factor) as usize]) {
+
+            factor += 1;
+        }
+
+        if factor > 1 && (0..REVERSI_FIELD_HEIGHT as isize).contains(&(y + y_coef * factor))
+            && (0..REVERSI_FIELD_WIDTH as isize).contains(&(x + x_coef * factor))
+            && self.player_turn == self.field[(y + y_coef * factor) as usize * REVERSI_FIELD_WIDTH + (x + x_coef * factor) as usize] {
+
+            return (factor - 1) as usize;
+        }
+
+        0
+    }
+
+    /// indices of the discs a move of the side to move at (x, y) would turn over,
+    /// empty when the move is not legal
+    pub fn flips(&self, x: usize, y: usize) -> Vec<usize> {
+        let mut flipped: Vec<usize> = Vec::new();
+
+        if !self.is_valid_cell(x, y) {
+            return flipped;
+        }
+
+        let (x, y): (isize, isize) = (x as isize, y as isize);
+
+        for (x_coef, y_coef) in DIRECTIONS {
+            for factor in 1..=self.run_length(x, y, (x_coef, y_coef)) as isize {
+                flipped.push((y + y_coef * factor) as usize * REVERSI_FIELD_WIDTH + (x + x_coef * factor) as usize);
+            }
+        }
+
+        flipped
+    }
+
+    pub fn is_valid_cell(&self, x: usize, y: usize) -> bool {
+        if x >= REVERSI_FIELD_WIDTH || y >= REVERSI_FIELD_HEIGHT || self.field[y * REVERSI_FIELD_WIDTH + x] != Cell::Free {
+            return false;
+        }
+
+        DIRECTIONS.iter().any(|dir| self.run_length(x as isize, y as isize, *dir) > 0)
+    }
+
+    pub fn valid_moves(&self) -> Vec<(usize, usize)> {
+        (0..REVERSI_FIELD_SIZE)
+            .map(|idx| (idx % REVERSI_FIELD_WIDTH, idx / REVERSI_FIELD_WIDTH))
+            .filter(|(x, y)| self.is_valid_cell(*x, *y))
+            .collect()
+    }
+
+    pub fn has_valid_move(&self) -> bool {
+        (0..REVERSI_FIELD_SIZE).any(|idx| self.is_valid_cell(idx % REVERSI_FIELD_WIDTH, idx / REVERSI_FIELD_WIDTH))
+    }
+
+    /// places a disc of the side to move at (x, y), turns the captured discs over and hands the
+    /// turn to the opponent. Returns the turned indices, an illegal move leaves the board untouched
+    pub fn play(&mut self, x: usize, y: usize) -> Vec<usize> {
+        let flipped = self.flips(x, y);
+
+        if !flipped.is_empty() {
+            self.field[y * REVERSI_FIELD_WIDTH + x] = self.player_turn.produce();
+
+            for idx in &flipped {
+                self.field[*idx].inverse();
+            }
+
+            self.pass();
+        }
+
+        flipped
+    }
+
+    /// hands the turn to the opponent without placing a disc
+    pub fn pass(&mut self) {
+        self.player_turn = self.player_turn.opposite();
+    }
+
+    /// neither side has a move left
+    pub fn is_over(&self) -> bool {
+        if self.has_valid_move() {
+            return false;
+        }
+
+        let mut opponent = *self;
+        opponent.pass();
+        !opponent.has_valid_move()
+    }
+
+    /// (black, white) disc counts
+    pub fn score(&self) -> (u32, u32) {
+        let mut black_score = 0;
+        let mut white_score = 0;
+
+        for cell in self.field {
+            if let Cell::White(_) = cell {
+                white_score += 1;
+            }
+
+            if let Cell::Black(_) = cell {
+                black_score += 1;
+            }
+        }
+
+        (black_score, white_score)
+    }
+
+    /// side with more discs, `None` on a tie
+    pub fn winner(&self) -> Option<PlayerTurn> {
+        let (black_score, white_score) = self.score();
+
+        if black_score > white_score {
+            Some(PlayerTurn::Black)
+        } else if white_score > black_score {
+            Some(PlayerTurn::White)
+        } else {
+            None
+        }
+    }
+
+    pub fn empties(&self) -> usize {
+        self.field.iter().filter(|cell| **cell == Cell::Free).count()
+    }
+}
+
+/// the usual name of a square: column letter a-h from the left, row number 1-8 from the top
+pub fn move_name(x: usize, y: usize) -> String {
+    format!("{}{}", (b'a' + x as u8) as char, y + 1)
+}
+
+/// square of a name like "d3", upper case columns are accepted as well
+pub fn parse_move(name: &str) -> Option<(usize, usize)> {
+    let mut chars = name.trim().chars();
+    let column = chars.next()?.to_ascii_lowercase();
+    let row: usize = chars.as_str().parse().ok()?;
+
+    if !column.is_ascii_lowercase() || row == 0 {
+        return None;
+    }
+
+    let (x, y) = ((column as u8 - b'a') as usize, row - 1);
+
+    if x < REVERSI_FIELD_WIDTH && y < REVERSI_FIELD_HEIGHT {
+        Some((x, y))
+    } else {
+        None
+    }
+}
+
+impl Default for Board {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+
+#[derive(Clone, Copy, Debug)]
+pub enum Cell {
+    Black(usize),
+    White(usize),
+    Free
+}
+
+impl PartialEq<Cell> for Cell {
+    fn eq(&self, other: &Cell) -> bool {
+
+        if let (Cell::White(_), Cell::White(_)) = (*self, *other) {
+            return true;
+        }
+
+        if let (Cell::Black(_), Cell::Black(_)) = (*self, *other) {
+            return true;
+        }
+
+        if let (Cell::Free, Cell::Free) = (*self, *other) {
+            return true;
+        }
+
+        false
+    }
+}
+
+impl Cell {
+    pub fn inverse(&mut self) {
+        *self = match self {
+            Black(f) => White(*f),
+            White(f) => Black(*f),
+            Free => Free,
+        }
+    }
+}
+
+impl PartialEq<PlayerTurn> for Cell {
+    fn eq(&self, other: &PlayerTurn) -> bool {
+        (*self == Cell::White(0) && *other == PlayerTurn::White) || (*self == Cell::Black(0) && *other == PlayerTurn::Black)
+    }
+}
+
+
+
+
+#[repr(u8)]
+#[derive(PartialEq, Clone, Copy, Debug)]
+#[cfg_attr(feature = "gui", derive(druid::Data))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum PlayerTurn {
+    Black,
+    White,
+}
+
+impl PartialEq<Cell> for PlayerTurn {
+    fn eq(&self, other: &Cell) -> bool {
+        (*self == PlayerTurn::White && *other == Cell::White(0)) || (*self == PlayerTurn::Black && *other == Cell::Black(0))
+    }
+}
+
+
+impl PlayerTurn {
+    const BLACK_NAME: &'static str = "Black";
+    const WHITE_NAME: &'static str = "White";
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            PlayerTurn::Black => Self::BLACK_NAME,
+            PlayerTurn::White => Self::WHITE_NAME,
+        }
+
+    }
+
+    pub fn produce(&self) -> Cell {
+        match self {
+            PlayerTurn::Black => {
+                Cell::Black(0)
+            }
+            PlayerTurn::White => {
+                Cell::White(0)
+            }
+        }
+    }
+
+    pub fn is_reverse_of(&self, cell: &Cell) -> bool {
+        (*self == PlayerTurn::White && *cell == Cell::Black(0)) ||
+            (*self == PlayerTurn::Black && *cell == Cell::White(0))
+    }
+
+    pub fn opposite(&self) -> PlayerTurn {
+        match self {
+            PlayerTurn::Black => PlayerTurn::White,
+            PlayerTurn::White => PlayerTurn::Black,
+        }
+    }
+}
@@ -0,0 +1,311 @@
+use std::time::{Duration, Instant};
+use crate::board::PlayerTurn;
+use crate::engine::time::ClockState;
+
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum TimeControl {
+    // the whole game has to be played within `base`
+    SuddenDeath {
+        #[cfg_attr(feature = "serde", serde(with = "crate::serialize::millis"))]
+        base: Duration,
+    },
+    // `increment` is added after every move
+    Fischer {
+        #[cfg_attr(feature = "serde", serde(with = "crate::serialize::millis"))]
+        base: Duration,
+        #[cfg_attr(feature = "serde", serde(with = "crate::serialize::millis"))]
+        increment: Duration,
+    },
+    // once `base` is used up every move has to be made within `period`, running over a period
+    // uses it up, running out of periods loses
+    ByoYomi {
+        #[cfg_attr(feature = "serde", serde(with = "crate::serialize::millis"))]
+        base: Duration,
+        #[cfg_attr(feature = "serde", serde(with = "crate::serialize::millis"))]
+        period: Duration,
+        periods: u32,
+    },
+}
+
+impl TimeControl {
+    pub const PRESETS: [TimeControl; 3] = [
+        TimeControl::SuddenDeath { base: Duration::from_secs(5 * 60) },
+        TimeControl::Fischer { base: Duration::from_secs(3 * 60), increment: Duration::from_secs(2) },
+        TimeControl::ByoYomi { base: Duration::from_secs(5 * 60), period: Duration::from_secs(30), periods: 5 },
+    ];
+
+    pub fn base(&self) -> Duration {
+        match *self {
+            TimeControl::SuddenDeath { base } => base,
+            TimeControl::Fischer { base, .. } => base,
+            TimeControl::ByoYomi { base, .. } => base,
+        }
+    }
+
+    pub fn name(&self) -> String {
+        match *self {
+            TimeControl::SuddenDeath { base } => format!("{} min", base.as_secs() / 60),
+            TimeControl::Fischer { base, increment } => format!("{} min + {} s", base.as_secs() / 60, increment.as_secs()),
+            TimeControl::ByoYomi { base, period, periods } => format!("{} min + {}x{} s", base.as_secs() / 60, periods, period.as_secs()),
+        }
+    }
+}
+
+
+/// The clock of one side
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Clock {
+    pub control: TimeControl,
+    // main time left
+    #[cfg_attr(feature = "serde", serde(with = "crate::serialize::millis"))]
+    pub remaining: Duration,
+    // time left in the current byo-yomi period
+    #[cfg_attr(feature = "serde", serde(with = "crate::serialize::millis"))]
+    pub period_remaining: Duration,
+    // byo-yomi periods left, the current one included
+    pub periods: u32,
+    pub flagged: bool,
+}
+
+impl Clock {
+    pub fn new(control: TimeControl) -> Self {
+        let (period_remaining, periods) = match control {
+            TimeControl::ByoYomi { period, periods, .. } => (period, periods),
+            _ => (Duration::ZERO, 0),
+        };
+
+        Self {
+            control,
+            remaining: control.base(),
+            period_remaining,
+            periods,
+            flagged: false,
+        }
+    }
+
+    /// takes the time the side spent thinking off the clock, false once its flag fell
+    pub fn tick(&mut self, elapsed: Duration) -> bool {
+        if self.flagged {
+            return false;
+        }
+
+        if elapsed < self.remaining {
+            self.remaining -= elapsed;
+            return true;
+        }
+
+        let mut elapsed = elapsed - self.remaining;
+        self.remaining = Duration::ZERO;
+
+        if let TimeControl::ByoYomi { period, .. } = self.control {
+            while self.periods > 0 {
+                if elapsed < self.period_remaining {
+                    self.period_remaining -= elapsed;
+                    return true;
+                }
+
+                elapsed -= self.period_remaining;
+                self.periods -= 1;
+                self.period_remaining = if self.periods > 0 { period } else { Duration::ZERO };
+            }
+        }
+
+        self.flagged = true;
+        false
+    }
+
+    /// the side finished its move: adds the increment or starts a fresh byo-yomi period
+    pub fn finish_move(&mut self) {
+        match self.control {
+            TimeControl::Fischer { increment, .. } => self.remaining += increment,
+            TimeControl::ByoYomi { period, .. } if self.periods > 0 => self.period_remaining = period,
+            _ => {},
+        }
+    }
+
+    /// what the engine's time manager needs to know
+    pub fn state(&self) -> ClockState {
+        let increment = match self.control {
+            TimeControl::Fischer { increment, .. } => increment,
+            _ => Duration::ZERO,
+        };
+
+        ClockState {
+            remaining: self.remaining,
+            increment,
+            period: if self.periods > 0 { self.period_remaining } else { Duration::ZERO },
+        }
+    }
+
+    /// m:ss, tenths of a second in the last ten seconds, byo-yomi periods in brackets
+    pub fn display(&self) -> String {
+        let in_periods = self.remaining.is_zero() && self.periods > 0;
+        let shown = if in_periods { self.period_remaining } else { self.remaining };
+
+        let mut text = if shown < Duration::from_secs(10) {
+            format!("0:{:02}.{}", shown.as_secs(), shown.subsec_millis() / 100)
+        } else {
+            format!("{}:{:02}", shown.as_secs() / 60, shown.as_secs() % 60)
+        };
+
+        if let TimeControl::ByoYomi { .. } = self.control {
+            text.push_str(&format!(" ({})", self.periods));
+        }
+
+        text
+    }
+}
+
+
+/// Both clocks of a game, the side to move's one is running
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct GameClocks {
+    pub black: Clock,
+    pub white: Clock,
+    // when the game and its clocks were set up
+    pub started: Instant,
+    last_tick: Instant,
+}
+
+impl GameClocks {
+    pub fn new(control: TimeControl) -> Self {
+        let now = Instant::now();
+
+        Self {
+            black: Clock::new(control),
+            white: Clock::new(control),
+            started: now,
+            last_tick: now,
+        }
+    }
+
+    /// clocks that stopped with a saved game, running again from now on
+    pub fn resume(black: Clock, white: Clock) -> Self {
+        let now = Instant::now();
+
+        Self {
+            black,
+            white,
+            started: now,
+            last_tick: now,
+        }
+    }
+
+    pub fn clock(&self, side: PlayerTurn) -> &Clock {
+        match side {
+            PlayerTurn::Black => &self.black,
+            PlayerTurn::White => &self.white,
+        }
+    }
+
+    pub fn clock_mut(&mut self, side: PlayerTurn) -> &mut Clock {
+        match side {
+            PlayerTurn::Black => &mut self.black,
+            PlayerTurn::White => &mut self.white,
+        }
+    }
+
+    /// charges the side to move for the time since the last tick, false once its flag fell
+    pub fn tick(&mut self, side: PlayerTurn) -> bool {
+        let now = Instant::now();
+        let elapsed = now - self.last_tick;
+        self.last_tick = now;
+
+        self.clock_mut(side).tick(elapsed)
+    }
+
+    /// the side to move completed its move, false when it did so too late
+    pub fn press(&mut self, side: PlayerTurn) -> bool {
+        let in_time = self.tick(side);
+
+        if in_time {
+            self.clock_mut(side).finish_move();
+        }
+
+        in_time
+    }
+
+    /// the side's clock as it stands right now
+    pub fn state(&self, side: PlayerTurn) -> ClockState {
+        let mut clock = *self.clock(side);
+        clock.tick(self.last_tick.elapsed());
+        clock.state()
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SECOND: Duration = Duration::from_secs(1);
+
+    #[test]
+    fn sudden_death_flags_when_the_time_is_gone() {
+        let mut clock = Clock::new(TimeControl::SuddenDeath { base: 10 * SECOND });
+
+        assert!(clock.tick(4 * SECOND));
+        clock.finish_move();
+        assert_eq!(clock.remaining, 6 * SECOND);
+
+        assert!(!clock.tick(6 * SECOND));
+        assert!(clock.flagged);
+        // a fallen flag stays down
+        assert!(!clock.tick(Duration::ZERO));
+    }
+
+    #[test]
+    fn fischer_adds_the_increment_after_the_move() {
+        let mut clock = Clock::new(TimeControl::Fischer { base: 10 * SECOND, increment: 2 * SECOND });
+
+        assert!(clock.tick(3 * SECOND));
+        clock.finish_move();
+        assert_eq!(clock.remaining, 9 * SECOND);
+        assert_eq!(clock.state().increment, 2 * SECOND);
+    }
+
+    #[test]
+    fn byo_yomi_uses_up_periods_that_run_over() {
+        let mut clock = Clock::new(TimeControl::ByoYomi { base: 10 * SECOND, period: 5 * SECOND, periods: 3 });
+
+        // the main time and 2 s of the first period
+        assert!(clock.tick(12 * SECOND));
+        assert_eq!((clock.remaining, clock.period_remaining, clock.periods), (Duration::ZERO, 3 * SECOND, 3));
+        clock.finish_move();
+        assert_eq!(clock.period_remaining, 5 * SECOND);
+
+        // over the first period into the second one
+        assert!(clock.tick(7 * SECOND));
+        assert_eq!((clock.period_remaining, clock.periods), (3 * SECOND, 2));
+
+        assert!(!clock.tick(8 * SECOND));
+        assert_eq!(clock.periods, 0);
+        assert!(clock.flagged);
+    }
+
+    #[test]
+    fn the_display_shows_tenths_at_the_end() {
+        let mut clock = Clock::new(TimeControl::SuddenDeath { base: 5 * 60 * SECOND });
+        assert_eq!(clock.display(), "5:00");
+
+        clock.remaining = Duration::from_millis(9_450);
+        assert_eq!(clock.display(), "0:09.4");
+
+        let byo_yomi = Clock::new(TimeControl::PRESETS[2]);
+        assert_eq!(byo_yomi.display(), "5:00 (5)");
+    }
+
+    #[test]
+    fn a_late_press_adds_nothing() {
+        let mut clocks = GameClocks::new(TimeControl::Fischer { base: Duration::ZERO, increment: SECOND });
+
+        assert!(!clocks.press(PlayerTurn::Black));
+        assert_eq!(clocks.black.remaining, Duration::ZERO);
+        assert!(clocks.black.flagged);
+        assert!(!clocks.white.flagged);
+    }
+}
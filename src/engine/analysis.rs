@@ -0,0 +1,260 @@
+use std::time::Duration;
+use crate::board::{Board, PlayerTurn};
+use crate::engine::eval::DISC;
+use crate::engine::search::{SearchLimits, Searcher};
+use crate::history::History;
+
+
+// assessments run after every turn, so they have to stay cheap
+pub(crate) const ASSESS_DEPTH: u8 = 6;
+const ASSESS_TIME: Duration = Duration::from_millis(100);
+
+/// Engine assessment of a position from Black's point of view, see `eval::DISC`. The searcher
+/// is meant to be kept for the whole game, its table carries over from one position to the next.
+pub fn assess(searcher: &mut Searcher, board: &Board) -> i32 {
+    let limits = SearchLimits {
+        depth: ASSESS_DEPTH,
+        time: Some(ASSESS_TIME),
+        soft_time: None,
+    };
+
+    let score = searcher.search(board, &limits).score;
+
+    match board.player_turn {
+        PlayerTurn::Black => score,
+        PlayerTurn::White => -score,
+    }
+}
+
+
+// post-game analysis searches every position deeper than the running assessment to find the
+// engine's choice
+const ANALYSIS_DEPTH: u8 = 8;
+const ANALYSIS_TIME: Duration = Duration::from_millis(150);
+// from this many empty squares on every move is searched to the end of the game
+const SOLVE_EMPTIES: usize = 10;
+// earlier on the engine plays the game out after a move at this depth, its final disc count is
+// what the move is worth
+const PLAYOUT_DEPTH: u8 = 3;
+
+/// How a move compares to the engine's best one, by the discs it gave away: the final disc margin
+/// after the best move less the one after the move played. Near the end both are exact, earlier
+/// they are the margins the engine reaches playing the game out from either move.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Classification {
+    Best,
+    Inaccuracy,
+    Mistake,
+    Blunder,
+}
+
+impl Classification {
+    pub const ALL: [Classification; 4] = [
+        Classification::Best,
+        Classification::Inaccuracy,
+        Classification::Mistake,
+        Classification::Blunder,
+    ];
+
+    // smallest disc loss of each class but the best one, see `eval::DISC`
+    const INACCURACY: i32 = 2 * DISC;
+    const MISTAKE: i32 = 6 * DISC;
+    const BLUNDER: i32 = 12 * DISC;
+
+    pub fn from_loss(loss: i32) -> Self {
+        if loss >= Self::BLUNDER {
+            Classification::Blunder
+        } else if loss >= Self::MISTAKE {
+            Classification::Mistake
+        } else if loss >= Self::INACCURACY {
+            Classification::Inaccuracy
+        } else {
+            Classification::Best
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Classification::Best => "best",
+            Classification::Inaccuracy => "inaccuracy",
+            Classification::Mistake => "mistake",
+            Classification::Blunder => "blunder",
+        }
+    }
+}
+
+/// One move of the game held against the engine's choice in the same position
+#[derive(Clone, Debug, PartialEq)]
+pub struct MoveAnalysis {
+    // turns played before it, passes included
+    pub ply: usize,
+    pub side: PlayerTurn,
+    pub played: (usize, usize),
+    pub best: (usize, usize),
+    // final disc margins from the mover's point of view, see `eval::DISC`
+    pub played_score: i32,
+    pub best_score: i32,
+    pub class: Classification,
+}
+
+impl MoveAnalysis {
+    /// discs given away compared to the best move, see `Classification`
+    pub fn loss(&self) -> i32 {
+        self.best_score - self.played_score
+    }
+}
+
+/// Every move of a finished game with its classification, passes are left out
+#[derive(Clone, Debug, PartialEq, Default)]
+pub struct GameAnalysis {
+    pub moves: Vec<MoveAnalysis>,
+}
+
+impl GameAnalysis {
+    /// `progress` hears of every move analysed with the number of moves in all and stops the
+    /// analysis by returning false, None then
+    pub fn run(history: &History, threads: usize, mut progress: impl FnMut(usize, usize) -> bool) -> Option<Self> {
+        let mut searcher = Searcher::new(threads);
+        let positions = history.positions();
+        let total = history.moves.iter().filter(|played| played.cell.is_some()).count();
+        let mut moves = Vec::new();
+
+        for (ply, (played, board)) in history.moves.iter().zip(positions.iter()).enumerate() {
+            let cell = match played.cell {
+                Some(cell) => cell,
+                None => continue,
+            };
+
+            if let Some((best, best_score, played_score)) = Self::disc_scores(&mut searcher, board, cell) {
+                moves.push(MoveAnalysis {
+                    ply,
+                    side: played.side,
+                    played: cell,
+                    best,
+                    played_score,
+                    best_score,
+                    class: Classification::from_loss(best_score - played_score),
+                });
+            }
+
+            if !progress(moves.len(), total) {
+                return None;
+            }
+        }
+
+        Some(Self {
+            moves,
+        })
+    }
+
+    // the engine's choice, its final disc margin and the played move's
+    fn disc_scores(searcher: &mut Searcher, board: &Board, cell: (usize, usize)) -> Option<((usize, usize), i32, i32)> {
+        if board.empties() <= SOLVE_EMPTIES {
+            let limits = SearchLimits {
+                depth: board.empties() as u8,
+                time: None,
+                soft_time: None,
+            };
+            let scores = searcher.score_moves(board, &limits);
+
+            let (best, best_score) = *scores.iter().max_by_key(|(_, score)| *score)?;
+            let played_score = scores.iter()
+                .find(|(candidate, _)| *candidate == cell)
+                .map_or(best_score, |(_, score)| *score);
+
+            return Some((best, best_score, played_score));
+        }
+
+        let limits = SearchLimits {
+            depth: ANALYSIS_DEPTH,
+            time: Some(ANALYSIS_TIME / board.valid_moves().len().max(1) as u32),
+            soft_time: None,
+        };
+        let (best, _) = *searcher.score_moves(board, &limits).iter().max_by_key(|(_, score)| *score)?;
+
+        let played_score = Self::play_out(searcher, board, cell);
+        if best == cell {
+            return Some((best, played_score, played_score));
+        }
+
+        // the engine's move doesn't always play out better, the move played is the best then
+        let best_score = Self::play_out(searcher, board, best).max(played_score);
+        Some((best, best_score, played_score))
+    }
+
+    // final disc margin for the side to move when it plays the move and the engine both sides
+    // after it
+    fn play_out(searcher: &mut Searcher, board: &Board, (x, y): (usize, usize)) -> i32 {
+        let side = board.player_turn;
+        let mut board = *board;
+        board.play(x, y);
+
+        let limits = SearchLimits {
+            depth: PLAYOUT_DEPTH,
+            time: None,
+            soft_time: None,
+        };
+
+        while !board.is_over() {
+            match searcher.search(&board, &limits).best_move {
+                Some((x, y)) => { board.play(x, y); },
+                None => board.pass(),
+            }
+        }
+
+        let (black_score, white_score) = board.score();
+        let margin = DISC * (black_score as i32 - white_score as i32);
+        if side == PlayerTurn::Black { margin } else { -margin }
+    }
+
+    /// how many moves of the side fell into the class
+    pub fn count(&self, side: PlayerTurn, class: Classification) -> usize {
+        self.moves.iter().filter(|analysed| analysed.side == side && analysed.class == class).count()
+    }
+
+    /// the moves that lost the most, worst first
+    pub fn worst(&self, limit: usize) -> Vec<&MoveAnalysis> {
+        let mut worst: Vec<&MoveAnalysis> = self.moves.iter()
+            .filter(|analysed| analysed.class != Classification::Best)
+            .collect();
+
+        worst.sort_by_key(|analysed| -analysed.loss());
+        worst.truncate(limit);
+        worst
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn losses_are_counted_in_discs() {
+        assert_eq!(Classification::from_loss(0), Classification::Best);
+        assert_eq!(Classification::from_loss(DISC), Classification::Best);
+        assert_eq!(Classification::from_loss(2 * DISC), Classification::Inaccuracy);
+        assert_eq!(Classification::from_loss(6 * DISC), Classification::Mistake);
+        assert_eq!(Classification::from_loss(12 * DISC), Classification::Blunder);
+    }
+
+    #[test]
+    fn the_analysis_stops_when_asked() {
+        let mut board = Board::new();
+        let mut history = History::new();
+        for (x, y) in [(5, 4), (5, 5)] {
+            let side = board.player_turn;
+            let flipped = board.play(x, y);
+            history.push(side, (x, y), flipped);
+        }
+
+        let mut heard = Vec::new();
+        let analysis = GameAnalysis::run(&history, 1, |done, total| {
+            heard.push((done, total));
+            false
+        });
+
+        assert!(analysis.is_none());
+        assert_eq!(heard, vec![(1, 2)]);
+    }
+}
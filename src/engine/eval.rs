@@ -0,0 +1,58 @@
+use crate::board::{Board, Cell, PlayerTurn, REVERSI_FIELD_SIZE};
+
+/// Engine scores are in hundredths of a disc: a finished game scores exactly
+/// `DISC * (own discs - opponent discs)`.
+pub const DISC: i32 = 100;
+
+// how much a disc on each square is worth during the game, corners are stable, the squares
+// next to them hand corners to the opponent
+const WEIGHTS: [i32; REVERSI_FIELD_SIZE] = [
+     400, -120,  40,  20,  20,  40, -120,  400,
+    -120, -200, -10, -10, -10, -10, -200, -120,
+      40,  -10,   5,   2,   2,   5,  -10,   40,
+      20,  -10,   2,   1,   1,   2,  -10,   20,
+      20,  -10,   2,   1,   1,   2,  -10,   20,
+      40,  -10,   5,   2,   2,   5,  -10,   40,
+    -120, -200, -10, -10, -10, -10, -200, -120,
+     400, -120,  40,  20,  20,  40, -120,  400,
+];
+
+const MOBILITY: i32 = 30;
+
+/// positional worth of a square, also used to order moves in the search
+pub fn square_weight(idx: usize) -> i32 {
+    WEIGHTS[idx]
+}
+
+/// exact result of a finished game from the side to move's point of view
+pub fn final_score(board: &Board) -> i32 {
+    let (black_score, white_score) = board.score();
+    let diff = black_score as i32 - white_score as i32;
+
+    DISC * if board.player_turn == PlayerTurn::Black { diff } else { -diff }
+}
+
+/// Static evaluation from the side to move's point of view
+pub fn evaluate(board: &Board) -> i32 {
+    if board.is_over() {
+        return final_score(board);
+    }
+
+    let own = board.player_turn.produce();
+    let mut positional = 0;
+
+    for (idx, cell) in board.field.iter().enumerate() {
+        if *cell == Cell::Free {
+            continue;
+        }
+
+        positional += if *cell == own { WEIGHTS[idx] } else { -WEIGHTS[idx] };
+    }
+
+    let own_moves = board.valid_moves().len() as i32;
+    let mut opponent = *board;
+    opponent.pass();
+    let opponent_moves = opponent.valid_moves().len() as i32;
+
+    positional + MOBILITY * (own_moves - opponent_moves)
+}
@@ -0,0 +1,239 @@
+use std::io::{self, BufRead, BufReader, Write};
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::thread;
+use std::time::{Duration, Instant};
+use crate::board::{parse_move, Board};
+use crate::engine::Player;
+use crate::notation::ggf_position;
+
+
+/// Engine running in its own process, driven over the NBoard protocol: the position is sent as
+/// a GGF game before every move and the engine's "===" reply is played. An engine that doesn't
+/// answer within its timeout fails the request instead of keeping the caller waiting.
+pub struct ExternalEngine {
+    command: String,
+    name: String,
+    child: Child,
+    input: ChildStdin,
+    // lines the engine wrote, read on a thread of their own so that waiting for them can time out
+    output: Receiver<String>,
+    ping: u32,
+    // longest wait for a move or for the engine to start up
+    timeout: Duration,
+}
+
+impl ExternalEngine {
+    pub const DEFAULT_DEPTH: u8 = 8;
+    pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+
+    /// starts the engine, `command` is the program followed by its arguments
+    pub fn launch(command: &str, depth: u8) -> io::Result<Self> {
+        let mut parts = command.split_whitespace();
+        let program = parts.next().ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "no engine command"))?;
+
+        let mut child = Command::new(program)
+            .args(parts)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()?;
+
+        let input = child.stdin.take().expect("stdin is piped");
+        let stdout = BufReader::new(child.stdout.take().expect("stdout is piped"));
+
+        // ends with the engine's output, when the process exits
+        let (lines, output) = mpsc::channel();
+        thread::spawn(move || {
+            for line in stdout.lines().map_while(Result::ok) {
+                if lines.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+
+        let mut engine = Self {
+            command: command.to_string(),
+            name: command.to_string(),
+            child,
+            input,
+            output,
+            ping: 0,
+            timeout: Self::DEFAULT_TIMEOUT,
+        };
+
+        engine.send("nboard 2")?;
+        engine.send(&format!("set depth {}", depth))?;
+        engine.sync(Instant::now() + engine.timeout)?;
+
+        Ok(engine)
+    }
+
+    pub fn command(&self) -> &str {
+        &self.command
+    }
+
+    pub fn set_timeout(&mut self, timeout: Duration) {
+        self.timeout = timeout;
+    }
+
+    fn send(&mut self, line: &str) -> io::Result<()> {
+        writeln!(self.input, "{}", line)?;
+        self.input.flush()
+    }
+
+    fn read_line(&mut self, deadline: Instant) -> io::Result<String> {
+        let line = match self.output.recv_timeout(deadline.saturating_duration_since(Instant::now())) {
+            Ok(line) => line.trim().to_string(),
+            Err(RecvTimeoutError::Timeout) => {
+                return Err(io::Error::new(io::ErrorKind::TimedOut, "the engine didn't answer in time"));
+            },
+            Err(RecvTimeoutError::Disconnected) => {
+                return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "the engine exited"));
+            },
+        };
+
+        // the engine may introduce itself at any time
+        if let Some(name) = line.strip_prefix("set myname ") {
+            self.name = name.to_string();
+        }
+
+        Ok(line)
+    }
+
+    // waits until the engine has worked through everything sent so far
+    fn sync(&mut self, deadline: Instant) -> io::Result<()> {
+        self.ping += 1;
+        let pong = format!("pong {}", self.ping);
+        self.send(&format!("ping {}", self.ping))?;
+
+        while self.read_line(deadline)? != pong {}
+        Ok(())
+    }
+
+    /// the engine's move for the side to move, None when it passes. Errors when the engine
+    /// exited, played an illegal move or took longer than its timeout; it is best replaced then,
+    /// a late answer would still be on its way.
+    pub fn request_move(&mut self, board: &Board) -> io::Result<Option<(usize, usize)>> {
+        let deadline = Instant::now() + self.timeout;
+
+        self.send(&format!("set game {}", ggf_position(board)))?;
+        self.sync(deadline)?;
+        self.send("go")?;
+
+        loop {
+            let line = self.read_line(deadline)?;
+
+            if let Some(reply) = line.strip_prefix("===") {
+                let name = reply.trim().split('/').next().unwrap_or_default();
+
+                if name.eq_ignore_ascii_case("PA") {
+                    return Ok(None);
+                }
+
+                return match parse_move(name) {
+                    Some((x, y)) if board.is_valid_cell(x, y) => Ok(Some((x, y))),
+                    _ => Err(io::Error::new(io::ErrorKind::InvalidData, format!("illegal move '{}'", name))),
+                };
+            }
+        }
+    }
+}
+
+impl Player for ExternalEngine {
+
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn choose_move(&mut self, board: &Board) -> Option<(usize, usize)> {
+        match self.request_move(board) {
+            Ok(choice) => choice,
+            Err(err) => {
+                // the side is left to the player at the board
+                eprintln!("external engine {}: {}", self.name, err);
+                None
+            },
+        }
+    }
+}
+
+impl Drop for ExternalEngine {
+    fn drop(&mut self) {
+        let _ = self.send("quit");
+
+        if let Ok(None) = self.child.try_wait() {
+            let _ = self.child.kill();
+        }
+        let _ = self.child.wait();
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::path::PathBuf;
+
+    // an engine that answers pings and replies to "go" as `go` says, run by sh
+    fn scripted(name: &str, go: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("reversi-engine-{}-{}.sh", std::process::id(), name));
+        let script = format!(
+            "while read cmd arg rest; do\n  case \"$cmd\" in\n    ping) echo \"pong $arg\" ;;\n    go) {} ;;\n  esac\ndone\n",
+            go,
+        );
+        fs::write(&path, script).unwrap();
+        path
+    }
+
+    fn launch(name: &str, go: &str) -> (ExternalEngine, PathBuf) {
+        let path = scripted(name, go);
+        let engine = ExternalEngine::launch(&format!("sh {}", path.display()), ExternalEngine::DEFAULT_DEPTH).unwrap();
+        (engine, path)
+    }
+
+    #[test]
+    fn the_engine_reply_is_played() {
+        let (mut engine, path) = launch("reply", "echo 'set myname Scripted'; echo '=== F5/0.50/0.1'");
+
+        assert_eq!(engine.request_move(&Board::new()).unwrap(), Some((5, 4)));
+        assert_eq!(engine.name(), "Scripted");
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn a_malformed_reply_fails_the_request() {
+        let (mut engine, path) = launch("malformed", "echo '=== Z9'");
+
+        let err = engine.request_move(&Board::new()).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn an_engine_that_exits_fails_the_request() {
+        let (mut engine, path) = launch("exits", "exit 0");
+
+        let err = engine.request_move(&Board::new()).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn a_silent_engine_times_out() {
+        let (mut engine, path) = launch("silent", "true");
+        engine.set_timeout(Duration::from_millis(200));
+
+        let start = Instant::now();
+        let err = engine.request_move(&Board::new()).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::TimedOut);
+        assert!(start.elapsed() < Duration::from_secs(5));
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn an_engine_that_never_starts_fails_to_launch() {
+        assert!(ExternalEngine::launch("sh -c exit", ExternalEngine::DEFAULT_DEPTH).is_err());
+        assert!(ExternalEngine::launch("", ExternalEngine::DEFAULT_DEPTH).is_err());
+    }
+}
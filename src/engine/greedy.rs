@@ -0,0 +1,96 @@
+use std::cmp::Reverse;
+use rand::{Rng, SeedableRng};
+use rand::rngs::StdRng;
+use crate::board::Board;
+use crate::engine::Player;
+
+/// The original computer opponent: ranks moves by the number of discs they turn over and,
+/// depending on `error_chance`, plays a random one instead.
+pub struct Greedy {
+    error_chance: f64,
+    rng: StdRng,
+}
+
+impl Greedy {
+    pub fn new(error_chance: f64, seed: u64) -> Self {
+        Self {
+            error_chance,
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+}
+
+impl Player for Greedy {
+
+    fn name(&self) -> String {
+        format!("Greedy ({})", self.error_chance)
+    }
+
+    fn choose_move(&mut self, board: &Board) -> Option<(usize, usize)> {
+
+        let mut moves: Vec<(usize, usize, usize)> = board.valid_moves()
+            .into_iter()
+            .map(|(x, y)| (x, y, board.flips(x, y).len()))
+            .collect();
+
+        if moves.is_empty() {
+            return None;
+        }
+
+        // most flips first, the first of equal ones in board order
+        moves.sort_by_key(|(_, _, cost)| Reverse(*cost));
+
+        let (x, y, _) = if self.rng.gen::<f64>() < self.error_chance {
+            moves[self.rng.gen_range(0..moves.len())]
+        } else {
+            moves[0]
+        };
+
+        Some((x, y))
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::{Cell, PlayerTurn, REVERSI_FIELD_SIZE};
+
+    #[test]
+    fn without_errors_the_most_flipping_move_is_played() {
+        let mut board = Board::new();
+        for (x, y) in [(5, 4), (5, 5), (4, 5)] {
+            board.play(x, y);
+        }
+
+        let most = board.valid_moves().into_iter().map(|(x, y)| board.flips(x, y).len()).max().unwrap();
+        let (x, y) = Greedy::new(0., 1).choose_move(&board).unwrap();
+        assert_eq!(board.flips(x, y).len(), most);
+        assert!(most > 1);
+    }
+
+    #[test]
+    fn errors_are_legal_moves_too() {
+        let mut player = Greedy::new(1., 7);
+        let mut board = Board::new();
+
+        while let Some((x, y)) = player.choose_move(&board) {
+            assert!(board.is_valid_cell(x, y), "({}, {})", x, y);
+            board.play(x, y);
+            if !board.has_valid_move() {
+                board.pass();
+            }
+        }
+
+        assert!(board.is_over());
+    }
+
+    #[test]
+    fn a_side_without_moves_passes() {
+        let mut board = Board { field: [Cell::Free; REVERSI_FIELD_SIZE], player_turn: PlayerTurn::Black };
+        board.field[0] = Cell::White(0);
+        board.field[1] = Cell::Black(0);
+
+        assert_eq!(Greedy::new(0., 1).choose_move(&board), None);
+    }
+}
@@ -0,0 +1,88 @@
+use std::time::Duration;
+use crate::board::Board;
+use crate::engine::eval::DISC;
+use crate::engine::search::{SearchLimits, Searcher};
+
+
+/// The engine's advice for the side to move
+#[derive(Clone, Debug, PartialEq)]
+pub struct Hint {
+    pub best_move: (usize, usize),
+    // the moves expected to follow, starting with `best_move`
+    pub pv: Vec<(usize, usize)>,
+    // from the side to move's point of view, see `eval::DISC`
+    pub score: i32,
+}
+
+impl Hint {
+    // how long the player is kept waiting for a hint
+    pub const TIME: Duration = Duration::from_secs(1);
+    // longer variations are mostly noise to someone learning the game
+    pub const PV_LEN: usize = 5;
+
+    /// None when the side to move has to pass. The searcher may be kept from one hint to the
+    /// next, what it learned about earlier positions of the game still helps
+    pub fn search(searcher: &mut Searcher, board: &Board) -> Option<Self> {
+        let limits = SearchLimits {
+            depth: board.empties() as u8,
+            time: Some(Self::TIME),
+            soft_time: Some(Self::TIME / 2),
+        };
+
+        let result = searcher.search(board, &limits);
+        let best_move = result.best_move?;
+
+        let mut pv = result.pv;
+        // the table can lose the root entry to another position, the best move always leads
+        if pv.first() != Some(&best_move) {
+            pv = vec![best_move];
+        }
+        pv.truncate(Self::PV_LEN);
+
+        Some(Self {
+            best_move,
+            pv,
+            score: result.score,
+        })
+    }
+}
+
+
+/// Engine scores of every legal move for the analysis overlay
+#[derive(Clone, Debug, PartialEq, Default)]
+pub struct MoveScores {
+    // from the side to move's point of view, see `eval::DISC`
+    pub scores: Vec<((usize, usize), i32)>,
+}
+
+impl MoveScores {
+    // the overlay is refreshed after every move, so the whole analysis has to stay short
+    pub const TIME: Duration = Duration::from_millis(500);
+    pub const DEPTH: u8 = 6;
+
+    pub fn search(searcher: &mut Searcher, board: &Board) -> Self {
+        let moves = board.valid_moves().len().max(1) as u32;
+        let limits = SearchLimits {
+            depth: Self::DEPTH,
+            time: Some(Self::TIME / moves),
+            soft_time: None,
+        };
+
+        Self {
+            scores: searcher.score_moves(board, &limits),
+        }
+    }
+
+    pub fn score(&self, x: usize, y: usize) -> Option<i32> {
+        self.scores.iter().find(|(cell, _)| *cell == (x, y)).map(|(_, score)| *score)
+    }
+
+    pub fn best(&self) -> Option<i32> {
+        self.scores.iter().map(|(_, score)| *score).max()
+    }
+
+    /// score in discs with its sign, the way it is printed on the board
+    pub fn format(score: i32) -> String {
+        format!("{:+.1}", score as f64 / DISC as f64)
+    }
+}
@@ -0,0 +1,402 @@
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+use rand::{Rng, SeedableRng};
+use rand::rngs::StdRng;
+use crate::board::{Board, PlayerTurn, REVERSI_FIELD_WIDTH, REVERSI_FIELD_HEIGHT};
+use crate::engine::time::{ClockState, TimeManager};
+use crate::engine::{Player, DEFAULT_SEED};
+
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Playout {
+    // every legal move is equally likely
+    Random,
+    // corners are preferred and the squares next to them avoided
+    Light,
+}
+
+#[derive(Clone, Debug)]
+pub struct MctsConfig {
+    // search stops after this many playouts...
+    pub iterations: Option<u32>,
+    // ...or once this much time went by, whichever comes first. At least one has to be set.
+    // Only the playout count keeps the replies the same from machine to machine
+    pub time_limit: Option<Duration>,
+    // the UCT exploration constant
+    pub exploration: f64,
+    pub playout: Playout,
+    pub seed: u64,
+}
+
+impl Default for MctsConfig {
+    fn default() -> Self {
+        Self {
+            iterations: Some(10_000),
+            time_limit: None,
+            exploration: std::f64::consts::SQRT_2,
+            playout: Playout::Light,
+            seed: DEFAULT_SEED,
+        }
+    }
+}
+
+
+#[derive(Clone)]
+struct Node {
+    board: Board,
+    // cell index of the move leading here, None for a pass
+    mv: Option<usize>,
+    // side that made that move, the one `wins` are counted for
+    mover: PlayerTurn,
+    parent: Option<usize>,
+    children: Vec<usize>,
+    untried: Vec<Option<usize>>,
+    visits: u32,
+    wins: f64,
+}
+
+impl Node {
+    fn new(board: Board, mv: Option<usize>, parent: Option<usize>) -> Self {
+
+        let untried: Vec<Option<usize>> = if board.is_over() {
+            Vec::new()
+        } else {
+            let moves: Vec<Option<usize>> = board.valid_moves()
+                .into_iter()
+                .map(|(x, y)| Some(y * REVERSI_FIELD_WIDTH + x))
+                .collect();
+
+            if moves.is_empty() { vec![None] } else { moves }
+        };
+
+        Self {
+            board,
+            mv,
+            mover: board.player_turn.opposite(),
+            parent,
+            children: Vec::new(),
+            untried,
+            visits: 0,
+            wins: 0.0,
+        }
+    }
+}
+
+
+/// Monte Carlo Tree Search player (UCT). The tree of the previous search is kept and, when the
+/// position it is asked about was reached from it, searched further instead of starting over.
+pub struct Mcts {
+    config: MctsConfig,
+    rng: StdRng,
+    nodes: Vec<Node>,
+    root: usize,
+    time_manager: TimeManager,
+    clock: Option<ClockState>,
+}
+
+impl Mcts {
+    const MAX_NODES: usize = 250_000;
+
+    pub fn new(config: MctsConfig) -> Self {
+        Self {
+            rng: StdRng::seed_from_u64(config.seed),
+            config,
+            nodes: Vec::new(),
+            root: 0,
+            time_manager: TimeManager::default(),
+            clock: None,
+        }
+    }
+
+    /// playouts that went through the current root so far
+    pub fn root_visits(&self) -> u32 {
+        self.nodes.get(self.root).map_or(0, |node| node.visits)
+    }
+
+    fn set_root(&mut self, board: &Board) {
+
+        let mut found: Option<usize> = None;
+
+        if let Some(root) = self.nodes.get(self.root) {
+            // the position is usually our own last move followed by the opponent's reply
+            let mut candidates: Vec<usize> = vec![self.root];
+            for child in &root.children {
+                candidates.push(*child);
+                candidates.extend(self.nodes[*child].children.iter());
+            }
+
+            found = candidates.into_iter().find(|idx| self.nodes[*idx].board == *board);
+        }
+
+        match found {
+            Some(idx) => self.compact(idx),
+            None => {
+                self.nodes.clear();
+                self.nodes.push(Node::new(*board, None, None));
+            }
+        }
+
+        self.root = 0;
+    }
+
+    // rebuilds the arena from the subtree at `new_root` so discarded branches don't pile up
+    fn compact(&mut self, new_root: usize) {
+        let old = std::mem::take(&mut self.nodes);
+        let mut queue: VecDeque<(usize, Option<usize>)> = VecDeque::from([(new_root, None)]);
+
+        while let Some((old_idx, parent)) = queue.pop_front() {
+            let new_idx = self.nodes.len();
+
+            let mut node = old[old_idx].clone();
+            node.parent = parent;
+            node.children.clear();
+            self.nodes.push(node);
+
+            if let Some(parent) = parent {
+                self.nodes[parent].children.push(new_idx);
+            }
+
+            for child in &old[old_idx].children {
+                queue.push_back((*child, Some(new_idx)));
+            }
+        }
+    }
+
+    fn select_child(&self, idx: usize) -> usize {
+        let parent_visits = (self.nodes[idx].visits.max(1) as f64).ln();
+
+        let uct = |child: &usize| {
+            let node = &self.nodes[*child];
+            node.wins / node.visits as f64 + self.config.exploration * (parent_visits / node.visits as f64).sqrt()
+        };
+
+        *self.nodes[idx].children
+            .iter()
+            .max_by(|a, b| uct(a).total_cmp(&uct(b)))
+            .unwrap()
+    }
+
+    fn expand(&mut self, idx: usize) -> usize {
+        let pick = self.rng.gen_range(0..self.nodes[idx].untried.len());
+        let mv = self.nodes[idx].untried.swap_remove(pick);
+
+        let mut board = self.nodes[idx].board;
+        match mv {
+            Some(cell) => { board.play(cell % REVERSI_FIELD_WIDTH, cell / REVERSI_FIELD_WIDTH); },
+            None => board.pass(),
+        }
+
+        let child = self.nodes.len();
+        self.nodes.push(Node::new(board, mv, Some(idx)));
+        self.nodes[idx].children.push(child);
+
+        child
+    }
+
+    fn light_weight(x: usize, y: usize) -> u32 {
+        let edge_x = x == 0 || x == REVERSI_FIELD_WIDTH - 1;
+        let edge_y = y == 0 || y == REVERSI_FIELD_HEIGHT - 1;
+        let near_x = x == 1 || x == REVERSI_FIELD_WIDTH - 2;
+        let near_y = y == 1 || y == REVERSI_FIELD_HEIGHT - 2;
+
+        if edge_x && edge_y {
+            16
+        } else if (edge_x || near_x) && (edge_y || near_y) {
+            1
+        } else if edge_x || edge_y {
+            4
+        } else {
+            2
+        }
+    }
+
+    fn playout(&mut self, mut board: Board) -> Option<PlayerTurn> {
+        loop {
+            let moves = board.valid_moves();
+
+            if moves.is_empty() {
+                board.pass();
+                if !board.has_valid_move() {
+                    break;
+                }
+                continue;
+            }
+
+            let (x, y) = match self.config.playout {
+                Playout::Random => moves[self.rng.gen_range(0..moves.len())],
+                Playout::Light => {
+                    let total: u32 = moves.iter().map(|(x, y)| Self::light_weight(*x, *y)).sum();
+                    let mut pick = self.rng.gen_range(0..total);
+
+                    *moves.iter().find(|(x, y)| {
+                        let weight = Self::light_weight(*x, *y);
+                        if pick < weight { true } else { pick -= weight; false }
+                    }).unwrap()
+                }
+            };
+
+            board.play(x, y);
+        }
+
+        board.winner()
+    }
+
+    fn iterate(&mut self) {
+        let mut idx = self.root;
+
+        while self.nodes[idx].untried.is_empty() && !self.nodes[idx].children.is_empty() {
+            idx = self.select_child(idx);
+        }
+
+        if !self.nodes[idx].untried.is_empty() && self.nodes.len() < Self::MAX_NODES {
+            idx = self.expand(idx);
+        }
+
+        let winner = self.playout(self.nodes[idx].board);
+
+        let mut current = Some(idx);
+        while let Some(idx) = current {
+            let node = &mut self.nodes[idx];
+            node.visits += 1;
+            node.wins += match winner {
+                Some(side) if side == node.mover => 1.0,
+                Some(_) => 0.0,
+                None => 0.5,
+            };
+            current = node.parent;
+        }
+    }
+}
+
+impl Player for Mcts {
+
+    fn name(&self) -> String {
+        "MCTS".to_string()
+    }
+
+    fn choose_move(&mut self, board: &Board) -> Option<(usize, usize)> {
+
+        if !board.has_valid_move() {
+            return None;
+        }
+
+        self.set_root(board);
+
+        // a clock replaces the configured time limit, playouts can stop any time so the soft
+        // budget is all a move gets
+        let time_limit = match self.clock {
+            Some(clock) => Some(self.time_manager.budget(&clock, board.empties()).soft),
+            None => self.config.time_limit,
+        };
+
+        let start = Instant::now();
+        let mut iterations: u32 = 0;
+
+        loop {
+            if self.config.iterations.is_some_and(|limit| iterations >= limit)
+                || time_limit.is_some_and(|limit| start.elapsed() >= limit) {
+                break;
+            }
+
+            self.iterate();
+            iterations += 1;
+        }
+
+        let best = self.nodes[self.root].children
+            .iter()
+            .max_by_key(|child| self.nodes[**child].visits)
+            .and_then(|child| self.nodes[*child].mv);
+
+        // an untouched root (zero budget) still has to answer with something legal
+        let cell = best.unwrap_or_else(|| {
+            let (x, y) = board.valid_moves()[0];
+            y * REVERSI_FIELD_WIDTH + x
+        });
+
+        Some((cell % REVERSI_FIELD_WIDTH, cell / REVERSI_FIELD_WIDTH))
+    }
+
+    fn set_clock(&mut self, clock: Option<ClockState>) {
+        self.clock = clock;
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::{Cell, REVERSI_FIELD_SIZE};
+
+    fn mcts(iterations: u32) -> Mcts {
+        Mcts::new(MctsConfig { iterations: Some(iterations), ..MctsConfig::default() })
+    }
+
+    // Black to move without a legal move while White can still play c1
+    fn black_must_pass() -> Board {
+        let mut board = Board { field: [Cell::Free; REVERSI_FIELD_SIZE], player_turn: PlayerTurn::Black };
+        board.field[0] = Cell::White(0);
+        board.field[1] = Cell::Black(0);
+        board
+    }
+
+    #[test]
+    fn moves_are_legal() {
+        let mut player = mcts(200);
+        let mut board = Board::new();
+
+        for _ in 0..6 {
+            let (x, y) = player.choose_move(&board).unwrap();
+            assert!(board.is_valid_cell(x, y), "({}, {})", x, y);
+            board.play(x, y);
+        }
+    }
+
+    #[test]
+    fn a_side_without_moves_passes() {
+        assert_eq!(mcts(200).choose_move(&black_must_pass()), None);
+    }
+
+    #[test]
+    fn the_playout_limit_is_kept() {
+        let mut player = mcts(500);
+        player.choose_move(&Board::new());
+
+        assert_eq!(player.root_visits(), 500);
+    }
+
+    #[test]
+    fn the_tree_is_kept_after_the_reply() {
+        let mut player = mcts(2_000);
+        let mut board = Board::new();
+
+        let (x, y) = player.choose_move(&board).unwrap();
+        board.play(x, y);
+        let (x, y) = board.valid_moves()[0];
+        board.play(x, y);
+
+        // the playouts through the reply count towards the new root
+        player.choose_move(&board);
+        assert!(player.root_visits() > 2_000, "{} visits", player.root_visits());
+    }
+
+    #[test]
+    fn the_same_seed_plays_the_same_game() {
+        let play = || {
+            let mut player = mcts(300);
+            let mut board = Board::new();
+            let mut moves = Vec::new();
+
+            while let Some((x, y)) = player.choose_move(&board) {
+                moves.push((x, y));
+                board.play(x, y);
+                if moves.len() == 10 {
+                    break;
+                }
+            }
+
+            moves
+        };
+
+        assert_eq!(play(), play());
+    }
+}
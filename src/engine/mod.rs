@@ -0,0 +1,30 @@
+pub mod analysis;
+pub mod eval;
+pub mod external;
+pub mod greedy;
+pub mod hint;
+pub mod mcts;
+pub mod search;
+pub mod time;
+pub mod tt;
+
+use crate::board::Board;
+use crate::engine::time::ClockState;
+
+/// Seed the computer players start from unless told otherwise, so a game against them can be
+/// replayed move for move.
+pub const DEFAULT_SEED: u64 = 0x5eed;
+
+/// Anything that can pick moves for one side of a game: the computer opponents of
+/// `GameMode::PvE` implement it.
+pub trait Player {
+
+    fn name(&self) -> String;
+
+    // picks a move for the side to move on the board, None when that side has to pass
+    fn choose_move(&mut self, board: &Board) -> Option<(usize, usize)>;
+
+    // tells the player what its clock looks like before its next move, None for untimed games.
+    // Players that don't budget their time ignore it
+    fn set_clock(&mut self, _clock: Option<ClockState>) {}
+}
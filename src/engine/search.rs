@@ -0,0 +1,491 @@
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+use crate::board::{Board, REVERSI_FIELD_WIDTH};
+use crate::engine::eval::{evaluate, final_score, square_weight};
+use crate::engine::time::{ClockState, TimeManager};
+use crate::engine::tt::{hash, Bound, Entry, TranspositionTable};
+use crate::engine::Player;
+
+
+const INF: i32 = 1_000_000;
+
+#[derive(Clone, Debug)]
+pub struct SearchLimits {
+    // deepest iteration, in moves
+    pub depth: u8,
+    // wall clock budget, the last finished iteration is played when it runs out
+    pub time: Option<Duration>,
+    // no new iteration is started past this, and the search may end well before it when the
+    // best move keeps coming out the same
+    pub soft_time: Option<Duration>,
+}
+
+impl Default for SearchLimits {
+    fn default() -> Self {
+        Self {
+            depth: 8,
+            time: None,
+            soft_time: None,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct SearchResult {
+    // None when the side to move has to pass
+    pub best_move: Option<(usize, usize)>,
+    // from the side to move's point of view, see `eval::DISC`
+    pub score: i32,
+    // last iteration that finished
+    pub depth: u8,
+    // positions visited by all threads together
+    pub nodes: u64,
+    // principal variation starting with `best_move`
+    pub pv: Vec<(usize, usize)>,
+    pub elapsed: Duration,
+}
+
+impl SearchResult {
+    pub fn nodes_per_second(&self) -> f64 {
+        self.nodes as f64 / self.elapsed.as_secs_f64().max(1e-9)
+    }
+}
+
+
+/// Iterative deepening alpha-beta search. With more than one thread it runs lazy SMP: every
+/// thread searches the same root and they share their results through the transposition table,
+/// the main thread's answer is the one reported.
+pub struct Searcher {
+    tt: TranspositionTable,
+    threads: usize,
+}
+
+impl Searcher {
+    pub fn new(threads: usize) -> Self {
+        Self {
+            tt: TranspositionTable::new(TranspositionTable::DEFAULT_SIZE_MB),
+            threads: threads.max(1),
+        }
+    }
+
+    pub fn threads(&self) -> usize {
+        self.threads
+    }
+
+    pub fn set_threads(&mut self, threads: usize) {
+        self.threads = threads.max(1);
+    }
+
+    /// forgets everything learned in previous searches
+    pub fn clear(&mut self) {
+        self.tt.clear();
+    }
+
+    pub fn search(&mut self, board: &Board, limits: &SearchLimits) -> SearchResult {
+        let start = Instant::now();
+
+        if !board.has_valid_move() {
+            return SearchResult {
+                score: evaluate(board),
+                elapsed: start.elapsed(),
+                ..SearchResult::default()
+            };
+        }
+
+        // searching deeper than the number of empty squares can't change anything
+        let max_depth = limits.depth.max(1).min(board.empties() as u8);
+        let stop = AtomicBool::new(false);
+        let nodes = AtomicU64::new(0);
+        let deadline = limits.time.map(|time| start + time);
+
+        let (best_move, score, depth) = thread::scope(|scope| {
+            for id in 1..self.threads {
+                let mut helper = Worker::new(id, &self.tt, &stop, &nodes, start, deadline, None);
+                scope.spawn(move || helper.iterate(board, max_depth));
+            }
+
+            let result = Worker::new(0, &self.tt, &stop, &nodes, start, deadline, limits.soft_time).iterate(board, max_depth);
+            stop.store(true, Ordering::Relaxed);
+            result.expect("the first iteration of the main thread always finishes")
+        });
+
+        SearchResult {
+            best_move: best_move.map(|cell| (cell % REVERSI_FIELD_WIDTH, cell / REVERSI_FIELD_WIDTH)),
+            score,
+            depth,
+            nodes: nodes.load(Ordering::Relaxed),
+            pv: self.principal_variation(board, depth),
+            elapsed: start.elapsed(),
+        }
+    }
+
+    /// every legal move with its score from the side to move's point of view, each move's reply
+    /// is searched within `limits`
+    pub fn score_moves(&mut self, board: &Board, limits: &SearchLimits) -> Vec<((usize, usize), i32)> {
+        board.valid_moves()
+            .into_iter()
+            .map(|(x, y)| {
+                let mut child = *board;
+                child.play(x, y);
+
+                let score = if child.has_valid_move() {
+                    -self.search(&child, limits).score
+                } else {
+                    let mut passed = child;
+                    passed.pass();
+
+                    if passed.has_valid_move() {
+                        self.search(&passed, limits).score
+                    } else {
+                        -final_score(&child)
+                    }
+                };
+
+                ((x, y), score)
+            })
+            .collect()
+    }
+
+    // follows the best moves stored in the table from the root
+    fn principal_variation(&self, board: &Board, depth: u8) -> Vec<(usize, usize)> {
+        let mut pv = Vec::new();
+        let mut board = *board;
+
+        while pv.len() < depth as usize {
+            if !board.has_valid_move() {
+                board.pass();
+                if !board.has_valid_move() {
+                    break;
+                }
+            }
+
+            let cell = match self.tt.probe(hash(&board)).and_then(|entry| entry.best_move) {
+                Some(cell) => cell,
+                None => break,
+            };
+
+            let (x, y) = (cell % REVERSI_FIELD_WIDTH, cell / REVERSI_FIELD_WIDTH);
+            if board.play(x, y).is_empty() {
+                break;
+            }
+
+            pv.push((x, y));
+        }
+
+        pv
+    }
+}
+
+
+struct Worker<'a> {
+    id: usize,
+    tt: &'a TranspositionTable,
+    stop: &'a AtomicBool,
+    nodes: &'a AtomicU64,
+    start: Instant,
+    deadline: Option<Instant>,
+    soft_time: Option<Duration>,
+    local_nodes: u64,
+    root_depth: u8,
+}
+
+impl<'a> Worker<'a> {
+    // nodes between looks at the clock
+    const CHECK_INTERVAL: u64 = 1024;
+    // iterations in a row agreeing on the best move after which it is trusted
+    const STABLE_ITERATIONS: u32 = 3;
+
+    fn new(id: usize, tt: &'a TranspositionTable, stop: &'a AtomicBool, nodes: &'a AtomicU64,
+           start: Instant, deadline: Option<Instant>, soft_time: Option<Duration>) -> Self {
+        Self {
+            id,
+            tt,
+            stop,
+            nodes,
+            start,
+            deadline,
+            soft_time,
+            local_nodes: 0,
+            root_depth: 0,
+        }
+    }
+
+    // best move, score and depth of the last finished iteration
+    fn iterate(&mut self, board: &Board, max_depth: u8) -> Option<(Option<usize>, i32, u8)> {
+        let mut best: Option<(Option<usize>, i32, u8)> = None;
+        let mut stable: u32 = 0;
+
+        for depth in 1..=max_depth {
+            // helpers run one ply ahead every other thread so they don't walk the tree in lock step
+            let depth = (depth + (self.id % 2) as u8).min(max_depth);
+            self.root_depth = depth;
+
+            match self.root(board, depth) {
+                Some((best_move, score)) => {
+                    stable = if best.is_some_and(|(previous, _, _)| previous == best_move) { stable + 1 } else { 0 };
+                    best = Some((best_move, score, depth));
+                },
+                None => break,
+            }
+
+            if self.stop.load(Ordering::Relaxed) || self.soft_time_used(stable) {
+                break;
+            }
+        }
+
+        self.nodes.fetch_add(self.local_nodes % Self::CHECK_INTERVAL, Ordering::Relaxed);
+        best
+    }
+
+    fn soft_time_used(&self, stable: u32) -> bool {
+        let soft_time = match self.soft_time {
+            Some(soft_time) => soft_time,
+            None => return false,
+        };
+        let elapsed = self.start.elapsed();
+
+        // the next iteration takes a few times longer than the last one, don't start what won't finish
+        elapsed * 2 >= soft_time || (stable >= Self::STABLE_ITERATIONS && elapsed * 8 >= soft_time)
+    }
+
+    fn stopped(&mut self) -> bool {
+        // the main thread's first iteration is what gets played when time is short
+        if self.id == 0 && self.root_depth <= 1 {
+            return false;
+        }
+
+        if self.stop.load(Ordering::Relaxed) {
+            return true;
+        }
+
+        if self.local_nodes.is_multiple_of(Self::CHECK_INTERVAL) && self.deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+            self.stop.store(true, Ordering::Relaxed);
+            return true;
+        }
+
+        false
+    }
+
+    fn visit(&mut self) {
+        self.local_nodes += 1;
+
+        if self.local_nodes.is_multiple_of(Self::CHECK_INTERVAL) {
+            self.nodes.fetch_add(Self::CHECK_INTERVAL, Ordering::Relaxed);
+        }
+    }
+
+    // legal moves as cell indices, the remembered best one first and the rest by square worth
+    fn ordered_moves(board: &Board, tt_move: Option<usize>) -> Vec<usize> {
+        let mut moves: Vec<usize> = board.valid_moves()
+            .into_iter()
+            .map(|(x, y)| y * REVERSI_FIELD_WIDTH + x)
+            .collect();
+
+        moves.sort_by_key(|cell| if Some(*cell) == tt_move { -INF } else { -square_weight(*cell) });
+        moves
+    }
+
+    fn root(&mut self, board: &Board, depth: u8) -> Option<(Option<usize>, i32)> {
+        let key = hash(board);
+        let tt_move = self.tt.probe(key).and_then(|entry| entry.best_move);
+
+        let mut alpha = -INF;
+        let mut best_move = None;
+
+        for cell in Self::ordered_moves(board, tt_move) {
+            let mut child = *board;
+            child.play(cell % REVERSI_FIELD_WIDTH, cell / REVERSI_FIELD_WIDTH);
+
+            let score = -self.negamax(&child, depth - 1, -INF, -alpha)?;
+
+            if score > alpha || best_move.is_none() {
+                alpha = score;
+                best_move = Some(cell);
+            }
+        }
+
+        self.tt.store(key, Entry { score: alpha, depth, bound: Bound::Exact, best_move });
+        Some((best_move, alpha))
+    }
+
+    fn negamax(&mut self, board: &Board, depth: u8, mut alpha: i32, beta: i32) -> Option<i32> {
+        self.visit();
+
+        if self.stopped() {
+            return None;
+        }
+
+        if !board.has_valid_move() {
+            let mut passed = *board;
+            passed.pass();
+
+            if !passed.has_valid_move() {
+                return Some(final_score(board));
+            }
+
+            return self.negamax(&passed, depth, -beta, -alpha).map(|score| -score);
+        }
+
+        if depth == 0 {
+            return Some(evaluate(board));
+        }
+
+        let key = hash(board);
+        let mut tt_move = None;
+
+        if let Some(entry) = self.tt.probe(key) {
+            tt_move = entry.best_move;
+
+            if entry.depth >= depth {
+                match entry.bound {
+                    Bound::Exact => return Some(entry.score),
+                    Bound::Lower if entry.score >= beta => return Some(entry.score),
+                    Bound::Upper if entry.score <= alpha => return Some(entry.score),
+                    _ => {},
+                }
+            }
+        }
+
+        let alpha_orig = alpha;
+        let mut best_score = -INF;
+        let mut best_move = None;
+
+        for cell in Self::ordered_moves(board, tt_move) {
+            let mut child = *board;
+            child.play(cell % REVERSI_FIELD_WIDTH, cell / REVERSI_FIELD_WIDTH);
+
+            let score = -self.negamax(&child, depth - 1, -beta, -alpha)?;
+
+            if score > best_score {
+                best_score = score;
+                best_move = Some(cell);
+            }
+
+            alpha = alpha.max(score);
+            if alpha >= beta {
+                break;
+            }
+        }
+
+        let bound = if best_score <= alpha_orig {
+            Bound::Upper
+        } else if best_score >= beta {
+            Bound::Lower
+        } else {
+            Bound::Exact
+        };
+
+        self.tt.store(key, Entry { score: best_score, depth, bound, best_move });
+        Some(best_score)
+    }
+}
+
+
+/// Computer player backed by the alpha-beta `Searcher`. In timed games the time manager decides
+/// how long each search may take instead of the fixed limits.
+pub struct AlphaBeta {
+    searcher: Searcher,
+    limits: SearchLimits,
+    time_manager: TimeManager,
+    clock: Option<ClockState>,
+}
+
+impl AlphaBeta {
+    pub fn new(limits: SearchLimits, threads: usize) -> Self {
+        Self {
+            searcher: Searcher::new(threads),
+            limits,
+            time_manager: TimeManager::default(),
+            clock: None,
+        }
+    }
+}
+
+impl Player for AlphaBeta {
+
+    fn name(&self) -> String {
+        format!("Alpha-beta (depth {})", self.limits.depth)
+    }
+
+    fn choose_move(&mut self, board: &Board) -> Option<(usize, usize)> {
+        let limits = match self.clock {
+            Some(clock) => {
+                let budget = self.time_manager.budget(&clock, board.empties());
+                SearchLimits {
+                    depth: self.limits.depth,
+                    time: Some(budget.hard),
+                    soft_time: Some(budget.soft),
+                }
+            },
+            None => self.limits.clone(),
+        };
+
+        self.searcher.search(board, &limits).best_move
+    }
+
+    fn set_clock(&mut self, clock: Option<ClockState>) {
+        self.clock = clock;
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::{Cell, PlayerTurn, REVERSI_FIELD_SIZE};
+    use crate::engine::eval::DISC;
+
+    // the first legal move every time until only `empties` squares are left
+    fn endgame(empties: usize) -> Board {
+        let mut board = Board::new();
+
+        while board.empties() > empties {
+            if !board.has_valid_move() {
+                board.pass();
+            }
+            let (x, y) = board.valid_moves()[0];
+            board.play(x, y);
+        }
+
+        board
+    }
+
+    #[test]
+    fn moves_are_legal() {
+        let mut player = AlphaBeta::new(SearchLimits { depth: 4, ..SearchLimits::default() }, 1);
+        let mut board = Board::new();
+
+        for _ in 0..6 {
+            let (x, y) = player.choose_move(&board).unwrap();
+            assert!(board.is_valid_cell(x, y), "({}, {})", x, y);
+            board.play(x, y);
+        }
+    }
+
+    #[test]
+    fn a_side_without_moves_passes() {
+        let mut board = Board { field: [Cell::Free; REVERSI_FIELD_SIZE], player_turn: PlayerTurn::Black };
+        board.field[0] = Cell::White(0);
+        board.field[1] = Cell::Black(0);
+
+        let mut player = AlphaBeta::new(SearchLimits::default(), 1);
+        assert_eq!(player.choose_move(&board), None);
+    }
+
+    #[test]
+    fn more_threads_find_the_same_score() {
+        // searched to the end, so entries the helpers leave behind from other depths can't
+        // tell a different story
+        let board = endgame(9);
+        let limits = SearchLimits { depth: 9, ..SearchLimits::default() };
+
+        let single = Searcher::new(1).search(&board, &limits);
+        let multi = Searcher::new(4).search(&board, &limits);
+
+        assert_eq!(single.depth, 9);
+        assert_eq!(multi.depth, 9);
+        assert_eq!(single.score, multi.score);
+        assert_eq!(single.score % DISC, 0);
+    }
+}
@@ -0,0 +1,133 @@
+use std::time::Duration;
+
+
+/// The clock of the side to move at the moment it has to move
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ClockState {
+    // main time left
+    pub remaining: Duration,
+    // added after every move (Fischer)
+    pub increment: Duration,
+    // byo-yomi period that is available once the main time ran out, zero when there is none
+    pub period: Duration,
+}
+
+/// How long a search may take: past `soft` no new iteration is started, `hard` is never crossed
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TimeBudget {
+    pub soft: Duration,
+    pub hard: Duration,
+}
+
+/// Splits the time left on a clock over the moves still to come
+#[derive(Clone, Debug)]
+pub struct TimeManager {
+    // kept back from every budget for move transmission and UI work
+    pub overhead: Duration,
+    // fewest moves the remaining time is spread over, so the end of the game is never starved
+    pub min_moves_to_go: u32,
+    // how many soft budgets a single move may use when the search is unstable
+    pub hard_factor: u32,
+}
+
+impl Default for TimeManager {
+    fn default() -> Self {
+        Self {
+            overhead: Duration::from_millis(50),
+            min_moves_to_go: 4,
+            hard_factor: 4,
+        }
+    }
+}
+
+impl TimeManager {
+
+    pub fn budget(&self, clock: &ClockState, empties: usize) -> TimeBudget {
+
+        // with the main time gone only the byo-yomi period is left, and it is renewed every move
+        if clock.remaining.is_zero() {
+            let usable = clock.period.saturating_sub(self.overhead);
+            return TimeBudget {
+                soft: usable / 2,
+                hard: usable,
+            };
+        }
+
+        // a move every other empty square is ours
+        let moves_to_go = (empties as u32).div_ceil(2).max(self.min_moves_to_go);
+        let usable = clock.remaining.saturating_sub(self.overhead);
+        // the most this move can take without losing on time, the increment only comes after it
+        let limit = (clock.remaining + clock.period).saturating_sub(self.overhead);
+
+        let soft = (usable / moves_to_go + clock.increment * 3 / 4 + clock.period / 2).min(limit);
+        let hard = (soft * self.hard_factor)
+            .min(usable / 3 + clock.increment + clock.period)
+            .max(soft)
+            .min(limit);
+
+        TimeBudget {
+            soft,
+            hard,
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SECOND: Duration = Duration::from_secs(1);
+
+    fn clock(remaining: Duration, increment: Duration, period: Duration) -> ClockState {
+        ClockState { remaining, increment, period }
+    }
+
+    #[test]
+    fn the_time_is_spread_over_our_moves_to_come() {
+        let manager = TimeManager::default();
+        let budget = manager.budget(&clock(60 * SECOND, Duration::ZERO, Duration::ZERO), 40);
+
+        // 20 moves of ours left
+        assert_eq!(budget.soft, (60 * SECOND - manager.overhead) / 20);
+        assert!(budget.soft <= budget.hard && budget.hard <= 60 * SECOND / 3);
+    }
+
+    #[test]
+    fn the_end_of_the_game_is_not_starved() {
+        let manager = TimeManager::default();
+        let budget = manager.budget(&clock(10 * SECOND, Duration::ZERO, Duration::ZERO), 2);
+
+        assert_eq!(budget.soft, (10 * SECOND - manager.overhead) / manager.min_moves_to_go);
+    }
+
+    #[test]
+    fn no_budget_loses_on_time() {
+        let manager = TimeManager::default();
+
+        for remaining in [Duration::ZERO, Duration::from_millis(30), SECOND, 10 * SECOND] {
+            for increment in [Duration::ZERO, 2 * SECOND] {
+                let budget = manager.budget(&clock(remaining, increment, Duration::ZERO), 30);
+                assert!(budget.hard <= remaining, "{:?} with {:?} left", budget, remaining);
+            }
+        }
+    }
+
+    #[test]
+    fn byo_yomi_periods_are_used_once_the_main_time_is_gone() {
+        let manager = TimeManager::default();
+        let budget = manager.budget(&clock(Duration::ZERO, Duration::ZERO, 30 * SECOND), 30);
+
+        assert_eq!(budget.hard, 30 * SECOND - manager.overhead);
+        assert_eq!(budget.soft, budget.hard / 2);
+    }
+
+    #[test]
+    fn the_increment_counts_towards_the_move() {
+        let manager = TimeManager::default();
+        let without = manager.budget(&clock(60 * SECOND, Duration::ZERO, Duration::ZERO), 40);
+        let with = manager.budget(&clock(60 * SECOND, 2 * SECOND, Duration::ZERO), 40);
+
+        assert_eq!(with.soft, without.soft + 2 * SECOND * 3 / 4);
+    }
+}
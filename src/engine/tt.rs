@@ -0,0 +1,193 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use crate::board::{Board, Cell, PlayerTurn, REVERSI_FIELD_SIZE};
+
+
+const fn splitmix(mut state: u64) -> u64 {
+    state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    state = (state ^ (state >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    state = (state ^ (state >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    state ^ (state >> 31)
+}
+
+const fn zobrist_keys() -> [[u64; 2]; REVERSI_FIELD_SIZE] {
+    let mut keys = [[0_u64; 2]; REVERSI_FIELD_SIZE];
+    let mut idx = 0;
+
+    while idx < REVERSI_FIELD_SIZE {
+        keys[idx][0] = splitmix(idx as u64 * 2 + 1);
+        keys[idx][1] = splitmix(idx as u64 * 2 + 2);
+        idx += 1;
+    }
+
+    keys
+}
+
+const ZOBRIST: [[u64; 2]; REVERSI_FIELD_SIZE] = zobrist_keys();
+const ZOBRIST_WHITE_TO_MOVE: u64 = splitmix(0);
+
+/// Zobrist key of a position, animation counters of the cells don't matter
+pub fn hash(board: &Board) -> u64 {
+    let mut key = if board.player_turn == PlayerTurn::White { ZOBRIST_WHITE_TO_MOVE } else { 0 };
+
+    for (idx, cell) in board.field.iter().enumerate() {
+        match cell {
+            Cell::Black(_) => key ^= ZOBRIST[idx][0],
+            Cell::White(_) => key ^= ZOBRIST[idx][1],
+            Cell::Free => {},
+        }
+    }
+
+    key
+}
+
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Bound {
+    Exact,
+    Lower,
+    Upper,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Entry {
+    pub score: i32,
+    pub depth: u8,
+    pub bound: Bound,
+    // cell index of the best move found, None for a pass or a fail low
+    pub best_move: Option<usize>,
+}
+
+impl Entry {
+    const NO_MOVE: u64 = 0xff;
+
+    fn pack(&self) -> u64 {
+        let bound = match self.bound {
+            Bound::Exact => 0_u64,
+            Bound::Lower => 1,
+            Bound::Upper => 2,
+        };
+        let best_move = self.best_move.map_or(Self::NO_MOVE, |mv| mv as u64);
+
+        (self.score as u32 as u64) | (self.depth as u64) << 32 | bound << 40 | best_move << 42
+    }
+
+    fn unpack(data: u64) -> Self {
+        let best_move = (data >> 42) & 0xff;
+
+        Self {
+            score: data as u32 as i32,
+            depth: (data >> 32) as u8,
+            bound: match (data >> 40) & 0b11 {
+                0 => Bound::Exact,
+                1 => Bound::Lower,
+                _ => Bound::Upper,
+            },
+            best_move: if best_move == Self::NO_MOVE { None } else { Some(best_move as usize) },
+        }
+    }
+}
+
+
+/// Transposition table shared by all search threads without locking. Every slot stores the key
+/// xor-ed with its data, so a slot torn by two threads writing at once just fails to match.
+pub struct TranspositionTable {
+    slots: Vec<[AtomicU64; 2]>,
+}
+
+impl TranspositionTable {
+    pub const DEFAULT_SIZE_MB: usize = 16;
+
+    pub fn new(size_mb: usize) -> Self {
+        let count = (size_mb.max(1) << 20) / std::mem::size_of::<[AtomicU64; 2]>();
+        // largest power of two that fits, so a key maps to a slot with a mask
+        let count = 1_usize << (usize::BITS - 1 - count.leading_zeros());
+
+        Self {
+            slots: (0..count).map(|_| [AtomicU64::new(0), AtomicU64::new(0)]).collect(),
+        }
+    }
+
+    fn slot(&self, key: u64) -> &[AtomicU64; 2] {
+        &self.slots[key as usize & (self.slots.len() - 1)]
+    }
+
+    pub fn probe(&self, key: u64) -> Option<Entry> {
+        let [checksum, data] = self.slot(key);
+        let data = data.load(Ordering::Relaxed);
+
+        if checksum.load(Ordering::Relaxed) ^ data == key && data != 0 {
+            Some(Entry::unpack(data))
+        } else {
+            None
+        }
+    }
+
+    pub fn store(&self, key: u64, entry: Entry) {
+        let [checksum, data] = self.slot(key);
+
+        // keep deeper results of the same position
+        if let Some(old) = self.probe(key) {
+            if old.depth > entry.depth && entry.bound != Bound::Exact {
+                return;
+            }
+        }
+
+        let packed = entry.pack();
+        checksum.store(key ^ packed, Ordering::Relaxed);
+        data.store(packed, Ordering::Relaxed);
+    }
+
+    pub fn clear(&self) {
+        for [checksum, data] in &self.slots {
+            checksum.store(0, Ordering::Relaxed);
+            data.store(0, Ordering::Relaxed);
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn entries_survive_packing() {
+        let entries = [
+            Entry { score: -6400, depth: 60, bound: Bound::Exact, best_move: Some(63) },
+            Entry { score: 1234, depth: 1, bound: Bound::Lower, best_move: Some(0) },
+            Entry { score: 0, depth: 0, bound: Bound::Upper, best_move: None },
+        ];
+
+        for entry in entries {
+            let unpacked = Entry::unpack(entry.pack());
+            assert_eq!(unpacked.score, entry.score);
+            assert_eq!(unpacked.depth, entry.depth);
+            assert_eq!(unpacked.bound, entry.bound);
+            assert_eq!(unpacked.best_move, entry.best_move);
+        }
+    }
+
+    #[test]
+    fn a_stored_entry_is_found_by_its_key_only() {
+        let table = TranspositionTable::new(1);
+        let key = hash(&Board::new());
+        table.store(key, Entry { score: 50, depth: 4, bound: Bound::Exact, best_move: Some(37) });
+
+        assert_eq!(table.probe(key).map(|entry| entry.score), Some(50));
+        // same slot, other position
+        assert!(table.probe(key ^ (1 << 40)).is_none());
+    }
+
+    #[test]
+    fn a_torn_write_is_rejected() {
+        let table = TranspositionTable::new(1);
+        let key = hash(&Board::new());
+        table.store(key, Entry { score: 50, depth: 4, bound: Bound::Exact, best_move: Some(37) });
+
+        // another thread got its data in, but not its checksum
+        let other = Entry { score: -300, depth: 9, bound: Bound::Lower, best_move: Some(19) };
+        table.slot(key)[1].store(other.pack(), Ordering::Relaxed);
+
+        assert!(table.probe(key).is_none());
+    }
+}
@@ -0,0 +1,139 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+use crate::board::{Board, PlayerTurn};
+use crate::engine::Player;
+use crate::history::History;
+use crate::mode::GameMode;
+use crate::outcome::Outcome;
+use crate::rating::GameEntry;
+use crate::settings::Settings;
+
+
+/// Turn order of a game without any front end attached: moves, the passes they force and the
+/// computer's replies in PvE. The terminal front ends play through it.
+pub struct Game {
+    pub board: Board,
+    pub history: History,
+    pub mode: GameMode,
+    // side the computer plays in PvE
+    pub computer_side: PlayerTurn,
+    opponent: Option<Rc<RefCell<dyn Player>>>,
+    // result settled off the board, by resignation or agreement
+    pub decided: Option<Outcome>,
+}
+
+impl Game {
+    pub fn new(mode: GameMode, settings: &Settings) -> Self {
+        let opponent = match &mode {
+            GameMode::PvE(opponent) => Some(opponent.player(settings)),
+            GameMode::PvP => None,
+        };
+
+        Self {
+            board: Board::new(),
+            history: History::new(),
+            mode,
+            computer_side: PlayerTurn::White,
+            opponent,
+            decided: None,
+        }
+    }
+
+    pub fn opponent_name(&self) -> Option<String> {
+        self.opponent.as_ref().map(|opponent| opponent.borrow().name())
+    }
+
+    /// name the side is rated under: the PvE opponent's or the player's from the settings
+    pub fn player_name(&self, side: PlayerTurn, settings: &Settings) -> String {
+        match &self.mode {
+            GameMode::PvE(opponent) if side == self.computer_side => opponent.name().to_string(),
+            _ => settings.player_name(side).to_string(),
+        }
+    }
+
+    pub fn is_computer_turn(&self) -> bool {
+        self.opponent.is_some() && self.outcome().is_none() && self.board.player_turn == self.computer_side
+    }
+
+    /// plays for the side to move, false when the move is illegal or the game was decided. A side
+    /// left without a move passes right away.
+    pub fn play(&mut self, x: usize, y: usize) -> bool {
+        if self.decided.is_some() {
+            return false;
+        }
+
+        let side = self.board.player_turn;
+        let flipped = self.board.play(x, y);
+
+        if flipped.is_empty() {
+            return false;
+        }
+
+        self.history.push(side, (x, y), flipped);
+
+        if !self.board.has_valid_move() && !self.board.is_over() {
+            self.history.push_pass(self.board.player_turn);
+            self.board.pass();
+        }
+
+        true
+    }
+
+    /// lets the computer make its move when it is its turn
+    pub fn computer_move(&mut self) -> Option<(usize, usize)> {
+        if !self.is_computer_turn() {
+            return None;
+        }
+
+        let choice = self.opponent.as_ref()?.borrow_mut().choose_move(&self.board)?;
+        self.play(choice.0, choice.1);
+        Some(choice)
+    }
+
+    /// None while the game goes on
+    pub fn outcome(&self) -> Option<Outcome> {
+        if self.decided.is_some() {
+            self.decided
+        } else if self.board.is_over() {
+            Some(Outcome::from_board(&self.board))
+        } else {
+            None
+        }
+    }
+
+    /// ends the game in favour of the other side
+    pub fn resign(&mut self, side: PlayerTurn) {
+        if self.outcome().is_none() {
+            self.decided = Some(Outcome::resigned(side));
+        }
+    }
+
+    pub fn agree_draw(&mut self) {
+        if self.outcome().is_none() {
+            self.decided = Some(Outcome::agreed_draw());
+        }
+    }
+
+    /// takes back the side's last move and whatever followed it, false when it has none or the
+    /// game is over
+    pub fn take_back(&mut self, side: PlayerTurn) -> bool {
+        let last = self.history.moves.iter().rposition(|played| played.side == side && played.cell.is_some());
+
+        match last {
+            Some(idx) if self.outcome().is_none() => {
+                self.history.moves.truncate(idx);
+                self.board = self.history.positions().last().copied().unwrap_or_default();
+                true
+            },
+            _ => false,
+        }
+    }
+
+    /// the finished game for the rating store, None while it goes on
+    pub fn rating_entry(&self, settings: &Settings) -> Option<GameEntry> {
+        let outcome = self.outcome()?;
+
+        Some(GameEntry::new(self.mode.label(), &self.player_name(PlayerTurn::Black, settings),
+                            &self.player_name(PlayerTurn::White, settings), &self.history, outcome))
+    }
+}
@@ -0,0 +1,88 @@
+use crate::board::{Board, PlayerTurn};
+
+
+/// One turn of a game
+#[derive(Clone, Debug, PartialEq)]
+pub struct Move {
+    pub side: PlayerTurn,
+    // None when the side had to pass
+    pub cell: Option<(usize, usize)>,
+    // field indices of the discs the move turned over
+    pub flipped: Vec<usize>,
+}
+
+/// Every turn played since the start position, passes included
+#[derive(Clone, Debug, PartialEq, Default)]
+pub struct History {
+    pub moves: Vec<Move>,
+}
+
+impl History {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.moves.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.moves.is_empty()
+    }
+
+    pub fn push(&mut self, side: PlayerTurn, cell: (usize, usize), flipped: Vec<usize>) {
+        self.moves.push(Move {
+            side,
+            cell: Some(cell),
+            flipped,
+        });
+    }
+
+    pub fn push_pass(&mut self, side: PlayerTurn) {
+        self.moves.push(Move {
+            side,
+            cell: None,
+            flipped: Vec::new(),
+        });
+    }
+
+    /// the latest turn that put a disc on the board
+    pub fn last_move(&self) -> Option<&Move> {
+        self.moves.iter().rev().find(|played| played.cell.is_some())
+    }
+
+    /// the board before every turn and after the last one
+    pub fn positions(&self) -> Vec<Board> {
+        let mut board = Board::new();
+        let mut positions = vec![board];
+
+        for played in &self.moves {
+            match played.cell {
+                Some((x, y)) => {
+                    board.play(x, y);
+                },
+                None => board.pass(),
+            }
+            positions.push(board);
+        }
+
+        positions
+    }
+}
+
+
+/// A finished game kept for the post-game screens
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GameRecord {
+    pub history: History,
+    // engine assessment of every position of `history`, from Black's point of view
+    pub evals: Vec<i32>,
+}
+
+impl GameRecord {
+    /// black and white discs on the board before every turn and after the last one
+    pub fn discs(&self) -> Vec<(u32, u32)> {
+        self.history.positions().iter().map(|board| board.score()).collect()
+    }
+}
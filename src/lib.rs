@@ -0,0 +1,21 @@
+pub mod board;
+pub mod clock;
+pub mod engine;
+pub mod game;
+pub mod history;
+pub mod mode;
+pub mod nboard;
+pub mod net;
+pub mod notation;
+pub mod outcome;
+pub mod profile;
+pub mod rating;
+pub mod report;
+pub mod save;
+#[cfg(feature = "serde")]
+pub mod serialize;
+pub mod settings;
+pub mod sound;
+#[cfg(feature = "gui")]
+pub mod theme;
+pub mod tournament;
@@ -1,99 +1,560 @@
 #![windows_subsystem = "windows"]
 
-use druid::{AppLauncher, BoxConstraints, Color, Env, Event, EventCtx, LayoutCtx, LifeCycle, LifeCycleCtx, PaintCtx, Size, UpdateCtx, Widget, WindowDesc, RenderContext, Point, AppDelegate, WindowId, DelegateCtx, ImageBuf, TimerToken, FontFamily};
-use druid::Data;
-use druid::widget::{Align, Flex, Label, Button, FlexParams, CrossAxisAlignment, Either};
-use crate::Cell::{Free, Black, White};
-use druid::kurbo::{Line, Rect};
+use druid::{AppLauncher, BoxConstraints, Color, Env, Event, EventCtx, LayoutCtx, LifeCycle, LifeCycleCtx, PaintCtx, Size, UpdateCtx, Widget, WindowDesc, RenderContext, Point, AppDelegate, WindowId, DelegateCtx, ImageBuf, TimerToken, FontFamily, FontDescriptor, Command, Target, Handled, FileDialogOptions, FileSpec, commands, ExtEventSink, Selector};
+use druid::{Data, Lens, WidgetExt, LinearGradient, RadialGradient, UnitPoint};
+use druid::widget::{Align, Flex, Label, Button, FlexParams, CrossAxisAlignment, Either, TextBox, Scroll, EnvScope, SizedBox};
+use reversi::board::{Board, Cell, Field, PlayerTurn, REVERSI_FIELD_WIDTH, REVERSI_FIELD_HEIGHT, REVERSI_FIELD_SIZE};
+use reversi::board::Cell::{Free, Black, White};
+use reversi::engine::Player;
+use reversi::engine::external::ExternalEngine;
+use reversi::mode::{GameMode, Opponent};
+use reversi::engine::hint::{Hint, MoveScores};
+use reversi::settings::{Renderer, Settings};
+use reversi::theme::Theme;
+use reversi::sound::{Sound, Sounds};
+use reversi::clock::{GameClocks, TimeControl};
+use reversi::history::{GameRecord, History};
+use reversi::engine::analysis::{assess, Classification, GameAnalysis};
+use reversi::engine::time::ClockState;
+use reversi::engine::search::Searcher;
+use reversi::engine::tt;
+use reversi::board::move_name;
+use reversi::report;
+use reversi::engine::eval::DISC;
+use reversi::outcome::{Outcome, Reason};
+use reversi::rating::{leaderboard, GameEntry, RatingStore};
+use reversi::profile::{ProfileStats, Profiles};
+use reversi::net::protocol::Offer;
+use reversi::save::SavedGame;
+use reversi::net::session::{NetEvent, Session};
+use druid::kurbo::{Circle, Ellipse, Line, Rect, Vec2};
 use std::mem::transmute_copy;
 use std::ops::Neg;
-use rand::{random, Rng, thread_rng};
-use rand::rngs::ThreadRng;
 use druid::image::{SubImage, GenericImageView, DynamicImage};
 use druid::piet::{InterpolationMode};
 use druid::piet::d2d::Bitmap;
 use druid::piet::Image;
 use druid::image::imageops::crop;
+use druid::image::imageops::FilterType;
 use druid::image::io::Reader as ICanRead;
 use std::io::Cursor;
-use std::borrow::Borrow;
-use std::time::Duration;
+use std::path::{Path, PathBuf};
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::sync::{Arc, Mutex, PoisonError};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::mpsc::{self, Sender};
+use std::thread;
+use std::time::{Duration, Instant};
 use druid::piet::{Text, TextLayoutBuilder, TextLayout};
-use druid::theme::WINDOW_BACKGROUND_COLOR;
+use druid::theme::{TEXT_SIZE_NORMAL, UI_FONT, WINDOW_BACKGROUND_COLOR};
 
-pub const REVERSI_FIELD_WIDTH: usize = 8;
-pub const REVERSI_FIELD_HEIGHT: usize = 8;
-pub const REVERSI_FIELD_SIZE: usize = REVERSI_FIELD_WIDTH * REVERSI_FIELD_HEIGHT;
-pub type Field = [Cell ; REVERSI_FIELD_SIZE];
-
-
-pub const WINDOW_WIDTH: f64 = 800_f64;
-pub const WINDOW_HEIGHT: f64 = 600_f64;
+// the board's side when nothing limits it, the smallest window it still fits in is
+// `Settings::MIN_WINDOW_SIZE`
+const BOARD_SIZE: f64 = 600_f64;
+const DIALOG_SIZE: (f64, f64) = (360_f64, 120_f64);
+const STATS_SIZE: (f64, f64) = (420_f64, 480_f64);
+const SETTINGS_SIZE: (f64, f64) = (320_f64, 360_f64);
 
 
 
 
 fn main() {
 
+    let settings = Settings::load_default();
+
     let wnd = WindowDesc::<Reversi>::new(root)
-        .window_size((WINDOW_WIDTH, WINDOW_HEIGHT))
-        .title("REVERSI")
-        .resizable(false);
+        .window_size(settings.window_size)
+        .with_min_size(Settings::MIN_WINDOW_SIZE)
+        .title("REVERSI");
 
-    AppLauncher::with_window(wnd)
+    let launcher = AppLauncher::with_window(wnd);
+    let sink = launcher.get_external_handle();
+
+    launcher
         .configure_env(|env, rev| {
-            env.set(WINDOW_BACKGROUND_COLOR, Color::rgba8(0,155,119, 255))
+            apply_theme(env, rev.theme())
         })
-        .launch(Reversi::new())
+        .delegate(Delegate)
+        .launch(Reversi::with_saved_game(settings, sink))
         .expect("failed to launch window");
 
 
 }
 
+// the theme's window background and font for the widgets
+fn apply_theme(env: &mut Env, theme: &Theme) {
+    env.set(WINDOW_BACKGROUND_COLOR, theme.background.clone());
+
+    if let Some(font) = &theme.font {
+        let size = env.get(TEXT_SIZE_NORMAL);
+        env.set(UI_FONT, FontDescriptor::new(FontFamily::new_unchecked(font.as_str())).with_size(size));
+    }
+}
+
+// the theme's font for text drawn on the board, `default` without one
+fn theme_font(theme: &Theme, default: FontFamily) -> FontFamily {
+    theme.font.as_deref().map_or(default, FontFamily::new_unchecked)
+}
+
 fn root() -> impl Widget<Reversi> {
 
+    // the environment follows the theme, so the window changes with it
+    EnvScope::new(|env, rev| apply_theme(env, rev.theme()),
+    // the board takes three quarters of the width it leaves, the side panel the rest
     Flex::<Reversi>::row()
-        .with_child(
+        .with_flex_child(
             Either::<Reversi>::new(|rev, env| rev.is_game,
                                    Grid::new(),
                                    VictoryScreen::new(),
-            ))
+            ),
+            3.)
+        .with_child(EvalBar)
         .with_flex_child(
             Align::centered(
+            Scroll::new(
             Flex::column()
                 .with_child(
                     Button::<Reversi>::new("Restart").on_click(
                         |ctx, rev, env| {
-                            *rev = Reversi::new();
+                            rev.restart(rev.settings.default_mode.clone());
                         }
                     )
                 )
-                .with_child(Button::<Reversi>::new("mode: PvP")
+                .with_child(mode_buttons())
+                .with_child(
+                    Flex::row()
+                        .with_child(Button::<Reversi>::new("-")
+                            .on_click(|ctx, rev, env| {
+                                rev.settings.threads = (rev.settings.threads - 1).max(1);
+                                rev.opponent = None;
+                                rev.save_settings();
+                            }))
+                        .with_child(Label::<Reversi>::dynamic(|rev, env| format!("threads: {}", rev.settings.threads)))
+                        .with_child(Button::<Reversi>::new("+")
+                            .on_click(|ctx, rev, env| {
+                                rev.settings.threads = (rev.settings.threads + 1).min(Settings::MAX_THREADS);
+                                rev.opponent = None;
+                                rev.save_settings();
+                            }))
+                )
+                .with_child(Button::<Reversi>::dynamic(|rev, env| {
+                    match rev.settings.time_control {
+                        Some(control) => format!("clock: {}", control.name()),
+                        None => "clock: off".to_string(),
+                    }
+                })
                     .on_click(|ctx, rev, env| {
-                        *rev = Reversi::new();
-                        rev.mode = GameMode::PvP;
-
+                        // off, then every preset in turn
+                        let presets = TimeControl::PRESETS;
+                        rev.settings.time_control = match rev.settings.time_control {
+                            None => Some(presets[0]),
+                            Some(control) => presets.iter()
+                                .position(|preset| *preset == control)
+                                .and_then(|idx| presets.get(idx + 1))
+                                .copied(),
+                        };
+                        rev.restart(rev.mode.clone());
+                    }))
+                .with_child(
+                    Flex::row()
+                        .with_child(Button::<Reversi>::new("Hint")
+                            .on_click(|ctx, rev, env| {
+                                rev.show_hint();
+                            }))
+                        .with_child(Button::<Reversi>::new("Settings")
+                            .on_click(|ctx, rev, env| {
+                                ctx.new_window(settings_dialog());
+                            }))
+                )
+                .with_child(TextBox::new()
+                    .with_placeholder("engine command")
+                    .lens(Reversi::settings.then(Settings::engine_command)))
+                .with_child(
+                    Flex::row()
+                        .with_child(Button::<Reversi>::dynamic(|rev, env| {
+                            format!("engine Black: {}", if rev.settings.external_black { "on" } else { "off" })
+                        })
+                            .on_click(|ctx, rev, env| {
+                                rev.settings.external_black = !rev.settings.external_black;
+                                rev.restart(rev.mode.clone());
+                            }))
+                        .with_child(Button::<Reversi>::dynamic(|rev, env| {
+                            format!("White: {}", if rev.settings.external_white { "on" } else { "off" })
+                        })
+                            .on_click(|ctx, rev, env| {
+                                rev.settings.external_white = !rev.settings.external_white;
+                                rev.restart(rev.mode.clone());
+                            }))
+                )
+                .with_child(
+                    Flex::row()
+                        .with_child(TextBox::new()
+                            .with_placeholder("host:port")
+                            .lens(Reversi::settings.then(Settings::net_address)))
+                        .with_child(Button::<Reversi>::new("Host")
+                            .on_click(|ctx, rev, env| {
+                                rev.host_game();
+                            }))
+                        .with_child(Button::<Reversi>::new("Join")
+                            .on_click(|ctx, rev, env| {
+                                rev.join_game();
+                            }))
+                        .with_child(Button::<Reversi>::new("Leave")
+                            .on_click(|ctx, rev, env| {
+                                rev.leave_network();
+                            }))
+                )
+                .with_child(Label::<Reversi>::dynamic(|rev, env| rev.net_status.clone()))
+                .with_child(Button::<Reversi>::new("Resign")
+                    .on_click(|ctx, rev, env| {
+                        if rev.is_game {
+                            ctx.new_window(confirm_dialog(Confirm::Resign));
+                        }
                     }))
-                .with_child(Button::<Reversi>::new("mode: PvE (1)")
+                .with_child(Either::<Reversi>::new(|rev, env| rev.network.is_some(),
+                    chat_pane(),
+                    Label::new(""),
+                ))
+                .with_child(
+                    Flex::row()
+                        .with_child(Button::<Reversi>::dynamic(|rev, env| match rev.analyzing {
+                                Some((done, total)) => format!("Analyzing {}/{}", done, total),
+                                None => "Analyze".to_string(),
+                            })
+                            .on_click(|ctx, rev, env| {
+                                rev.analyze();
+                            }))
+                        .with_child(Button::<Reversi>::new("Export")
+                            .on_click(|ctx, rev, env| {
+                                if rev.analysis.is_some() {
+                                    let options = FileDialogOptions::new()
+                                        .allowed_types(vec![FileSpec::HTML, Delegate::JSON])
+                                        .default_type(FileSpec::HTML)
+                                        .default_name("analysis.html");
+                                    ctx.submit_command(commands::SHOW_SAVE_PANEL.with(options));
+                                }
+                            }))
+                )
+                .with_child(
+                    Flex::row()
+                        .with_child(TextBox::new()
+                            .with_placeholder("Black name")
+                            .lens(Reversi::settings.then(Settings::black_name)))
+                        .with_child(TextBox::new()
+                            .with_placeholder("White name")
+                            .lens(Reversi::settings.then(Settings::white_name)))
+                )
+                .with_child(
+                    Flex::row()
+                        .with_child(Button::<Reversi>::new("Black profile")
+                            .on_click(|ctx, rev, env| {
+                                rev.next_profile(PlayerTurn::Black);
+                            }))
+                        .with_child(Button::<Reversi>::new("White profile")
+                            .on_click(|ctx, rev, env| {
+                                rev.next_profile(PlayerTurn::White);
+                            }))
+                )
+                .with_child(
+                    Flex::row()
+                        .with_child(Button::<Reversi>::new("Save profiles")
+                            .on_click(|ctx, rev, env| {
+                                rev.save_profiles();
+                            }))
+                        .with_child(Button::<Reversi>::new("Statistics")
+                            .on_click(|ctx, rev, env| {
+                                rev.refresh_stats();
+                                ctx.new_window(stats_window());
+                            }))
+                )
+                .with_child(Button::<Reversi>::dynamic(|rev, env| {
+                    format!("ratings: {}", if rev.show_leaderboard { "on" } else { "off" })
+                })
                     .on_click(|ctx, rev, env| {
-                        *rev = Reversi::new();
-                        rev.mode = GameMode::PvE(0.2);
+                        rev.show_leaderboard = !rev.show_leaderboard;
+                        rev.refresh_leaderboard();
+                    }))
+                .with_child(Either::<Reversi>::new(|rev, env| rev.show_leaderboard,
+                    Label::<Reversi>::dynamic(|rev, env| rev.leaderboard.clone())
+                        .with_font(FontDescriptor::new(FontFamily::MONOSPACE).with_size(11.)),
+                    Label::new(""),
+                ))
+            ).vertical()),
+            FlexParams::new(1., None)
+        )
+        .background(WINDOW_BACKGROUND_COLOR)
+    )
+}
 
+// offers and chat of a networked game
+fn chat_pane() -> impl Widget<Reversi> {
+    Flex::<Reversi>::column()
+        .with_child(
+            Flex::row()
+                .with_child(Button::<Reversi>::new("Offer draw")
+                    .on_click(|ctx, rev, env| {
+                        rev.propose(Offer::Draw);
                     }))
-                .with_child(Button::<Reversi>::new("mode: PvE (2)")
+                .with_child(Button::<Reversi>::new("Takeback")
                     .on_click(|ctx, rev, env| {
-                        *rev = Reversi::new();
-                        rev.mode = GameMode::PvE(0.4);
+                        rev.propose(Offer::Takeback);
+                    }))
+        )
+        .with_child(Label::<Reversi>::dynamic(|rev, env| rev.chat.clone()))
+        .with_child(
+            Flex::row()
+                .with_child(TextBox::new()
+                    .with_placeholder("chat")
+                    .lens(Reversi::chat_input))
+                .with_child(Button::<Reversi>::new("Send")
+                    .on_click(|ctx, rev, env| {
+                        rev.send_chat();
+                    }))
+        )
+}
 
+// preferences kept in the settings file, every change is saved at once
+fn settings_dialog() -> WindowDesc<Reversi> {
+    WindowDesc::<Reversi>::new(|| {
+        Align::centered(
+            Flex::<Reversi>::column()
+                .with_child(Button::<Reversi>::dynamic(|rev, env| format!("theme: {}", rev.settings.theme))
+                    .on_click(|ctx, rev, env| {
+                        let themes = rev.themes.clone();
+                        let idx = themes.iter().position(|theme| theme.name == rev.settings.theme).map_or(0, |idx| idx + 1);
+                        rev.settings.theme = themes[idx % themes.len()].name.clone();
+                        rev.save_settings();
                     }))
-                .with_child(Button::<Reversi>::new("mode: PvE (3)")
+                .with_child(Button::<Reversi>::dynamic(|rev, env| format!("board: {}", rev.settings.renderer.name()))
                     .on_click(|ctx, rev, env| {
-                        *rev = Reversi::new();
-                        rev.mode = GameMode::PvE(0.6);
+                        let renderers = Renderer::ALL;
+                        let idx = renderers.iter().position(|renderer| *renderer == rev.settings.renderer).map_or(0, |idx| idx + 1);
+                        rev.settings.renderer = renderers[idx % renderers.len()];
+                        rev.save_settings();
+                    }))
+                .with_child(Button::<Reversi>::dynamic(|rev, env| format!("animation: {}", rev.settings.animation_name()))
+                    .on_click(|ctx, rev, env| {
+                        let steps = Settings::ANIMATION_STEPS;
+                        let idx = steps.iter().position(|(_, step)| *step == rev.settings.animation_step).map_or(0, |idx| idx + 1);
+                        rev.settings.animation_step = steps[idx % steps.len()].1;
+                        rev.save_settings();
+                    }))
+                // nothing is heard without the "sound" feature, so there is nothing to switch
+                .with_child(Either::<Reversi>::new(|rev, env| cfg!(feature = "sound"),
+                    Button::<Reversi>::dynamic(|rev, env| {
+                        format!("sounds: {}", if rev.settings.sounds { "on" } else { "off" })
+                    })
+                        .on_click(|ctx, rev, env| {
+                            rev.settings.sounds = !rev.settings.sounds;
+                            rev.save_settings();
+                        }),
+                    SizedBox::empty(),
+                ))
+                .with_child(Button::<Reversi>::dynamic(|rev, env| format!("start in: {}", rev.settings.default_mode.label()))
+                    .on_click(|ctx, rev, env| {
+                        let presets = GameMode::PRESETS;
+                        let idx = presets.iter().position(|(_, mode)| *mode == rev.settings.default_mode).map_or(0, |idx| idx + 1);
+                        rev.settings.default_mode = presets[idx % presets.len()].1.clone();
+                        rev.save_settings();
+                    }))
+                .with_spacer(10.)
+                .with_child(Button::<Reversi>::dynamic(|rev, env| {
+                    format!("hint PV: {}", if rev.settings.hint_pv { "on" } else { "off" })
+                })
+                    .on_click(|ctx, rev, env| {
+                        rev.settings.hint_pv = !rev.settings.hint_pv;
+                        rev.save_settings();
+                    }))
+                .with_child(Button::<Reversi>::dynamic(|rev, env| {
+                    format!("legal moves: {}", if rev.settings.show_legal_moves { "on" } else { "off" })
+                })
+                    .on_click(|ctx, rev, env| {
+                        rev.settings.show_legal_moves = !rev.settings.show_legal_moves;
+                        rev.save_settings();
+                    }))
+                .with_child(Button::<Reversi>::dynamic(|rev, env| {
+                    format!("move scores: {}", if rev.settings.show_move_scores { "on" } else { "off" })
+                })
+                    .on_click(|ctx, rev, env| {
+                        rev.settings.show_move_scores = !rev.settings.show_move_scores;
+                        rev.refresh_analysis();
+                        rev.save_settings();
+                    }))
+                .with_child(Button::<Reversi>::dynamic(|rev, env| {
+                    match rev.settings.highlight_time.as_secs() {
+                        0 => "last move: off".to_string(),
+                        secs => format!("last move: {} s", secs),
+                    }
+                })
+                    .on_click(|ctx, rev, env| {
+                        let times = Settings::HIGHLIGHT_TIMES;
+                        let idx = times.iter().position(|time| *time == rev.settings.highlight_time).unwrap_or(0);
+                        rev.settings.highlight_time = times[(idx + 1) % times.len()];
+                        rev.save_settings();
+                    }))
+                .with_spacer(10.)
+                .with_child(Button::<Reversi>::new("Close")
+                    .on_click(|ctx, rev, env| {
+                        ctx.submit_command(commands::CLOSE_WINDOW);
+                    }))
+        )
+    })
+        .title("Settings")
+        .window_size(SETTINGS_SIZE)
+        .resizable(false)
+}
+
+// the records of the profiles, kept up to date while it is open
+fn stats_window() -> WindowDesc<Reversi> {
+    WindowDesc::<Reversi>::new(|| {
+        Scroll::new(
+            Label::<Reversi>::dynamic(|rev, env| rev.stats.clone())
+                .with_font(FontDescriptor::new(FontFamily::MONOSPACE).with_size(11.))
+        )
+            .vertical()
+            .padding(10.)
+    })
+        .title("Statistics")
+        .window_size(STATS_SIZE)
+}
+
+// what a confirmation dialog asks the player
+#[derive(Clone, Copy, PartialEq)]
+enum Confirm {
+    Resign,
+    // the networked opponent's offer
+    Answer(Offer),
+    // the unfinished game found on startup
+    Resume,
+}
 
-                    }))),
-            FlexParams::new(0.25, None)
+// small window asking Yes or No, either answer closes it
+fn confirm_dialog(confirm: Confirm) -> WindowDesc<Reversi> {
+    let question = match confirm {
+        Confirm::Resign => "Resign the game?",
+        Confirm::Answer(Offer::Draw) => "Your opponent offers a draw. Accept?",
+        Confirm::Answer(Offer::Takeback) => "Your opponent asks to take back a move. Accept?",
+        Confirm::Resume => "Resume the unfinished game?",
+    };
+
+    WindowDesc::<Reversi>::new(move || {
+        Align::centered(
+            Flex::<Reversi>::column()
+                .with_child(Label::new(question))
+                .with_child(Label::<Reversi>::dynamic(move |rev, env| {
+                    match (confirm, &rev.saved) {
+                        (Confirm::Resume, Some(saved)) => saved.describe(),
+                        _ => String::new(),
+                    }
+                }))
+                .with_spacer(10.)
+                .with_child(
+                    Flex::row()
+                        .with_child(Button::<Reversi>::new("Yes")
+                            .on_click(move |ctx, rev, env| {
+                                match confirm {
+                                    Confirm::Resign => rev.resign(),
+                                    Confirm::Answer(_) => rev.answer_offer(true),
+                                    Confirm::Resume => rev.resume_saved(),
+                                }
+                                ctx.submit_command(commands::CLOSE_WINDOW);
+                            }))
+                        .with_child(Button::<Reversi>::new("No")
+                            .on_click(move |ctx, rev, env| {
+                                match confirm {
+                                    Confirm::Resign => {},
+                                    Confirm::Answer(_) => rev.answer_offer(false),
+                                    Confirm::Resume => rev.discard_saved(),
+                                }
+                                ctx.submit_command(commands::CLOSE_WINDOW);
+                            }))
+                )
         )
+    })
+        .title("REVERSI")
+        .window_size(DIALOG_SIZE)
+        .resizable(false)
+}
+
+// one button per mode the front ends share
+fn mode_buttons() -> impl Widget<Reversi> {
+    let mut buttons = Flex::<Reversi>::column();
+
+    for (label, mode) in GameMode::PRESETS {
+        buttons.add_child(Button::<Reversi>::new(format!("mode: {}", label))
+            .on_click(move |ctx, rev, env| {
+                rev.restart(mode.clone());
+            }));
+    }
+
+    buttons
+}
+
+// the space the constraints offer, `BOARD_SIZE` along a side they leave unbounded
+fn available(bc: &BoxConstraints) -> Size {
+    let max = bc.max();
+    let bounded = |side: f64| if side.is_finite() { side } else { BOARD_SIZE };
+
+    bc.constrain(Size::new(bounded(max.width), bounded(max.height)))
+}
+
+// device pixels per display point of the window being painted
+fn window_scale(ctx: &PaintCtx) -> f64 {
+    ctx.window().get_scale().map_or(1., |scale| scale.x())
+}
+
+/// An image drawn from a resampled copy the size of its rect in device pixels, so it stays
+/// sharp at any board size and DPI instead of being stretched by the renderer
+struct Sprite {
+    source: DynamicImage,
+    scaled: ImageBuf,
+    // size of `scaled` in pixels
+    pixels: (u32, u32),
+}
+
+impl Sprite {
+    fn decode(bytes: &[u8]) -> Self {
+        Self::from_image(ICanRead::new(Cursor::new(bytes)).with_guessed_format().unwrap().decode().unwrap())
+    }
+
+    // an image file of a theme
+    fn open(path: &Path) -> Result<Self, String> {
+        let source = ICanRead::open(path)
+            .map_err(|err| err.to_string())?
+            .decode()
+            .map_err(|err| err.to_string())?;
+
+        Ok(Self::from_image(source))
+    }
+
+    fn from_image(source: DynamicImage) -> Self {
+        Self {
+            scaled: ImageBuf::from_dynamic_image(source.clone()),
+            pixels: (source.width(), source.height()),
+            source,
+        }
+    }
+
+    // size of the source image in display points
+    fn size(&self) -> Size {
+        Size::new(self.source.width() as f64, self.source.height() as f64)
+    }
+
+    // the image for a rect of `size` display points, resampled only when the pixel size changes
+    fn at(&mut self, size: Size, scale: f64) -> &ImageBuf {
+        let pixels = (
+            (size.width * scale).round().max(1.) as u32,
+            (size.height * scale).round().max(1.) as u32,
+        );
+
+        if pixels != self.pixels {
+            self.scaled = ImageBuf::from_dynamic_image(self.source.resize_exact(pixels.0, pixels.1, FilterType::Lanczos3));
+            self.pixels = pixels;
+        }
+
+        &self.scaled
+    }
 }
 
 struct Grid {
@@ -101,12 +562,25 @@ struct Grid {
     ver_offset: f64,
     hor_offset: f64,
     cell_size: f64,
-    invalid_cell: ImageBuf,
-    black_cell: ImageBuf,
-    white_cell: ImageBuf,
-    swap_cells: [ImageBuf;3],
+    invalid_cell: Sprite,
+    black_cell: Sprite,
+    white_cell: Sprite,
+    swap_cells: [Sprite;3],
+    // whether the flip frames go with the discs, they are drawn for the built-in sprites only
+    swap_frames: bool,
+    // theme the disc sprites were loaded for
+    sprite_theme: String,
     timer_code: TimerToken,
     gaf: u32,
+    clock_timer: TimerToken,
+    // game the clock timer runs for
+    clock_game: Option<Instant>,
+    highlight_timer: TimerToken,
+    // turns played when the highlighted move was made, and when that was
+    highlight_ply: usize,
+    highlight_since: Option<Instant>,
+    // polls the networked game while there is one
+    net_timer: TimerToken,
 
 }
 
@@ -115,27 +589,41 @@ impl Widget<Reversi> for Grid {
     fn event(&mut self, ctx: &mut EventCtx, event: &Event, data: &mut Reversi, env: &Env) {
 
         match event {
+            Event::WindowConnected => {
+                if data.saved.is_some() {
+                    ctx.new_window(confirm_dialog(Confirm::Resume));
+                }
+            },
+            Event::WindowSize(size) => {
+                // kept in the settings for the next run
+                data.settings.window_size = (size.width, size.height);
+            },
             Event::MouseMove(mouse_event) => {
-                if let Point{x, y} = mouse_event.pos {
-                    let affected_x: usize = ((x - self.hor_offset) / self.cell_size).floor() as usize;
-                    let affected_y: usize = ((y - self.ver_offset) / self.cell_size).floor() as usize;
+                self.hot = self.cell_at(mouse_event.pos);
 
-                    self.hot = Some((affected_x, affected_y));
-
-                    ctx.request_paint();
-                }
+                ctx.request_paint();
             },
 
             Event::MouseUp(mouse_event) => {
-                if let Point {x, y} = mouse_event.pos {
-                    let affected_x: usize = ((x - self.hor_offset) / self.cell_size).floor() as usize;
-                    let affected_y: usize = ((y - self.ver_offset) / self.cell_size).floor() as usize;
-                    data.clicked(affected_x, affected_y);
+                if let Some((affected_x, affected_y)) = self.cell_at(mouse_event.pos) {
+                    if data.network.is_some() {
+                        data.network_move(affected_x, affected_y);
+                    } else if !data.engine_thinking {
+                        data.clicked(affected_x, affected_y);
+                    }
                 }
 
-                self.timer_code = ctx.request_timer(
-                Duration::from_millis(80)
-                );
+                if data.settings.animation_step.is_zero() {
+                    // no animation, the flipped discs show at once
+                    for _cell in data.field.iter_mut() {
+                        if let (Cell::Black(f) | Cell::White(f)) = _cell {
+                            *f = 0;
+                        }
+                    }
+                    ctx.request_paint();
+                } else {
+                    self.timer_code = ctx.request_timer(data.settings.animation_step);
+                }
             },
             Event::Timer(tkn) => {
                 if *tkn == self.timer_code {
@@ -151,14 +639,40 @@ impl Widget<Reversi> for Grid {
 
                     self.timer_code = if self.gaf != 0 {
                         self.gaf -= 1;
-                        ctx.request_timer(
-                            Duration::from_millis(80)
-                        )
+                        ctx.request_timer(data.settings.animation_step)
                     } else {
                         self.gaf = Self::MAX_GAF;
                         TimerToken::INVALID
                     };
                 }
+
+                if *tkn == self.highlight_timer {
+                    // the highlight ran out
+                    self.highlight_timer = TimerToken::INVALID;
+                    ctx.request_paint();
+                }
+
+                if *tkn == self.net_timer {
+                    if let Some(offer) = data.poll_network() {
+                        ctx.new_window(confirm_dialog(Confirm::Answer(offer)));
+                    }
+
+                    self.net_timer = if data.network.is_some() {
+                        ctx.request_timer(Self::NET_POLL)
+                    } else {
+                        TimerToken::INVALID
+                    };
+                }
+
+                if *tkn == self.clock_timer {
+                    data.tick_clock();
+
+                    self.clock_timer = if data.is_game && data.clocks.is_some() {
+                        ctx.request_timer(Self::CLOCK_TICK)
+                    } else {
+                        TimerToken::INVALID
+                    };
+                }
             }
             _ => {},
         }
@@ -169,11 +683,44 @@ impl Widget<Reversi> for Grid {
     }
 
     fn update(&mut self, ctx: &mut UpdateCtx, old_data: &Reversi, data: &Reversi, env: &Env) {
+
+        // every new timed game needs its clock ticking
+        let clock_game = data.clocks.as_ref().map(|clocks| clocks.started);
+        if clock_game.is_some() && clock_game != self.clock_game {
+            self.clock_game = clock_game;
+            self.clock_timer = ctx.request_timer(Self::CLOCK_TICK);
+        }
+
+        if data.network.is_some() && self.net_timer == TimerToken::INVALID {
+            self.net_timer = ctx.request_timer(Self::NET_POLL);
+        }
+
+        if data.history.len() != self.highlight_ply {
+            self.highlight_ply = data.history.len();
+            self.highlight_since = Some(Instant::now());
+
+            // moves that didn't come from a click, such as the computer's, animate all the same
+            if self.timer_code == TimerToken::INVALID && !data.settings.animation_step.is_zero() {
+                self.timer_code = ctx.request_timer(data.settings.animation_step);
+            }
+
+            if !data.settings.highlight_time.is_zero() {
+                self.highlight_timer = ctx.request_timer(data.settings.highlight_time);
+            }
+        }
+
         ctx.request_paint();
     }
 
     fn layout(&mut self, ctx: &mut LayoutCtx, bc: &BoxConstraints, data: &Reversi, env: &Env) -> Size {
-        Size::new(600.,600.)
+        let size = available(bc);
+
+        // square cells with a cell wide margin around the board, which is centered in the space
+        self.cell_size = (size.width / (REVERSI_FIELD_WIDTH + 2) as f64).min(size.height / (REVERSI_FIELD_HEIGHT + 2) as f64);
+        self.hor_offset = (size.width - self.cell_size * REVERSI_FIELD_WIDTH as f64) / 2_f64;
+        self.ver_offset = (size.height - self.cell_size * REVERSI_FIELD_HEIGHT as f64) / 2_f64;
+
+        size
     }
 
     fn paint(&mut self, ctx: &mut PaintCtx, data: &Reversi, env: &Env) {
@@ -182,15 +729,22 @@ impl Widget<Reversi> for Grid {
 
         if let Size {width, height} = ctx.size() {
 
-            let mut hor_offset: f64 = width / (REVERSI_FIELD_WIDTH + 2) as f64;
-            let mut ver_offset: f64 = height / (REVERSI_FIELD_HEIGHT + 2) as f64;
+            let scale = window_scale(ctx);
+            let cell = Size::new(self.cell_size, self.cell_size);
+            let board_right = self.hor_offset + self.cell_size * REVERSI_FIELD_WIDTH as f64;
+            let board_bottom = self.ver_offset + self.cell_size * REVERSI_FIELD_HEIGHT as f64;
+
+            let vector = data.settings.renderer == Renderer::Vector;
+            let theme = data.theme();
 
-            self.cell_size = hor_offset.max(ver_offset);
+            if theme.name != self.sprite_theme {
+                self.load_sprites(theme);
+            }
 
-            hor_offset = (width % (self.cell_size * REVERSI_FIELD_WIDTH as f64)) / 2_f64;
-            ver_offset = (height % (self.cell_size * REVERSI_FIELD_HEIGHT as f64)) / 2_f64;
-            self.ver_offset = ver_offset;
-            self.hor_offset = hor_offset;
+            /// draw board
+            if vector {
+                self.paint_board(ctx, theme);
+            }
 
             /// draw cells
             for (idx, cell) in data.field.iter().enumerate() {
@@ -201,18 +755,28 @@ impl Widget<Reversi> for Grid {
 
                         let (x, y): (f64, f64) = ((idx % REVERSI_FIELD_WIDTH) as f64, (idx / REVERSI_FIELD_WIDTH) as f64);
 
+                        if vector {
+                            let side = if _cell == Cell::Black(0) { PlayerTurn::Black } else { PlayerTurn::White };
+                            self.paint_disc(ctx, theme, self.cell_rect(x as usize, y as usize), side, f);
+                            continue;
+                        }
+
+                        // a theme's own sprites are swapped without the flip frames
+                        if !self.swap_frames {
+                            f = 0;
+                        }
 
                         let im = if _cell == Cell::Black(0) {
                             let swp_len = self.swap_cells.len();
                             if f > 0 {
-                                &self.swap_cells[swp_len - f]
-                            } else {&self.black_cell}
+                                &mut self.swap_cells[swp_len - f]
+                            } else {&mut self.black_cell}
                         } else {
                             if f > 0 {
-                                &self.swap_cells[f - 1]
-                            } else {&self.white_cell}
+                                &mut self.swap_cells[f - 1]
+                            } else {&mut self.white_cell}
 
-                        }.to_image(
+                        }.at(cell, scale).to_image(
                             ctx.render_ctx
                         );
 
@@ -237,37 +801,119 @@ impl Widget<Reversi> for Grid {
                 }
             }
 
-            /// draw grid
-            for line_idx in 0..REVERSI_FIELD_HEIGHT + 1 {
+            /// draw grid, the vector board has its own under the discs
+            for line_idx in (0..REVERSI_FIELD_HEIGHT + 1).filter(|_| !vector) {
                 ctx.stroke(
-                    Line::new((self.hor_offset, line_idx as f64 * self.cell_size + self.ver_offset), (width - self.hor_offset, line_idx as f64 * self.cell_size + self.ver_offset)),
-                    &Color::SILVER,
+                    Line::new((self.hor_offset, line_idx as f64 * self.cell_size + self.ver_offset), (board_right, line_idx as f64 * self.cell_size + self.ver_offset)),
+                    &theme.grid,
                     1.5
                 );
             }
 
 
-            for line_idx in 0..REVERSI_FIELD_WIDTH + 1 {
+            for line_idx in (0..REVERSI_FIELD_WIDTH + 1).filter(|_| !vector) {
                 ctx.stroke(
-                    Line::new((line_idx as f64 * self.cell_size + self.hor_offset, self.ver_offset), (line_idx as f64 * self.cell_size + self.hor_offset, height - self.ver_offset)),
-                    &Color::SILVER,
+                    Line::new((line_idx as f64 * self.cell_size + self.hor_offset, self.ver_offset), (line_idx as f64 * self.cell_size + self.hor_offset, board_bottom)),
+                    &theme.grid,
                     1.5
                 );
             }
 
+            /// draw last move
+            let highlighted = self.highlight_since.is_some_and(|since| since.elapsed() < data.settings.highlight_time);
+
+            if let Some(last_move) = data.history.last_move().filter(|_| highlighted) {
+                if let Some((x, y)) = last_move.cell {
+                    let cell_rect = self.cell_rect(x, y);
+                    ctx.stroke(Circle::new(cell_rect.center(), self.cell_size * 0.45), &theme.last_move, 3.);
+                }
+
+                for idx in &last_move.flipped {
+                    let cell_rect = self.cell_rect(idx % REVERSI_FIELD_WIDTH, idx / REVERSI_FIELD_WIDTH);
+                    ctx.stroke(Circle::new(cell_rect.center(), self.cell_size * 0.45), &theme.flipped, 2.);
+                }
+            }
+
+            /// draw legal moves and their scores
+            if data.settings.show_legal_moves || data.move_scores.is_some() {
+                let best = data.move_scores.as_ref().and_then(|scores| scores.best());
+
+                for (x, y) in data.board().valid_moves() {
+                    let cell_rect = self.cell_rect(x, y);
+
+                    if data.settings.show_legal_moves {
+                        ctx.fill(Circle::new(cell_rect.center(), self.cell_size / 8.), theme.legal(data.player_turn));
+                    }
+
+                    if let Some(score) = data.move_scores.as_ref().and_then(|scores| scores.score(x, y)) {
+                        let clr = if Some(score) == best { theme.best_score.clone() } else { theme.legal(data.player_turn).clone() };
+
+                        ctx.text().new_text_layout(
+                            MoveScores::format(score)
+                        ).font(theme_font(theme, FontFamily::MONOSPACE), self.cell_size / 4.)
+                            .text_color(clr)
+                            .build()
+                            .unwrap()
+                            .draw(
+                                Point::new(cell_rect.x0 + self.cell_size / 8., cell_rect.y0 + self.cell_size * 0.65),
+                                ctx.render_ctx
+                            );
+                    }
+                }
+            }
+
+            /// draw hint
+            if let Some(hint) = &data.hint {
+                let shown = if data.settings.hint_pv { hint.pv.len() } else { 1 };
+
+                for (ply, (x, y)) in hint.pv.iter().take(shown).enumerate().rev() {
+                    let cell_rect = self.cell_rect(*x, *y);
+
+                    if ply == 0 {
+                        ctx.stroke(cell_rect.inset(-4.), &theme.hint, 4.);
+                        continue;
+                    }
+
+                    // the variation is numbered in the color of the side playing each move
+                    let clr = if ply % 2 == 0 { data.player_turn } else { data.player_turn.opposite() };
+
+                    ctx.text().new_text_layout(
+                        (ply + 1).to_string()
+                    ).font(theme_font(theme, FontFamily::MONOSPACE), self.cell_size / 3.)
+                        .text_color(theme.legal(clr).clone())
+                        .build()
+                        .unwrap()
+                        .draw(
+                            Point::new(cell_rect.x0 + self.cell_size * 0.4, cell_rect.y0 + self.cell_size / 3.),
+                            ctx.render_ctx
+                        );
+                }
+            }
+
             if let Some((affected_x, affected_y)) = self.hot {
 
 
-                if affected_x >= 0 && affected_x <= REVERSI_FIELD_WIDTH - 1 && affected_y >= 0 && affected_y <= REVERSI_FIELD_HEIGHT - 1 {
+                if vector {
+                    let cell_rect = self.cell_rect(affected_x, affected_y);
+
+                    // a see-through disc on a legal move, a cross anywhere else
+                    if data.is_valid_cell(affected_x, affected_y) {
+                        ctx.fill(Circle::new(cell_rect.center(), self.cell_size * 0.42), theme.legal(data.player_turn));
+                    } else {
+                        let cross = cell_rect.inset(-self.cell_size * 0.3);
+                        ctx.stroke(Line::new((cross.x0, cross.y0), (cross.x1, cross.y1)), &theme.invalid, 3.);
+                        ctx.stroke(Line::new((cross.x0, cross.y1), (cross.x1, cross.y0)), &theme.invalid, 3.);
+                    }
+                } else if affected_x >= 0 && affected_x <= REVERSI_FIELD_WIDTH - 1 && affected_y >= 0 && affected_y <= REVERSI_FIELD_HEIGHT - 1 {
 
                     let mut im = match data.player_turn {
                         PlayerTurn::Black => {
-                            self.black_cell.to_image(
+                            self.black_cell.at(cell, scale).to_image(
                                 ctx.render_ctx
                             )
                         }
                         PlayerTurn::White => {
-                            self.white_cell.to_image(
+                            self.white_cell.at(cell, scale).to_image(
                                 ctx.render_ctx
                             )
                         }
@@ -276,7 +922,7 @@ impl Widget<Reversi> for Grid {
                     if !data.is_valid_cell(
                         affected_x, affected_y
                     ) {
-                        im = self.invalid_cell.to_image(
+                        im = self.invalid_cell.at(cell, scale).to_image(
                             ctx.render_ctx
                         );
                     }
@@ -296,17 +942,21 @@ impl Widget<Reversi> for Grid {
                 self.hot = None;
             }
 
+            // scores and clocks go in the margin above the board's top left corner, sized with the cells
+            let margin = Point::new(self.hor_offset - self.cell_size, self.ver_offset - self.cell_size);
+            let font_size = self.cell_size / 3.;
+
             let mut score_b_string = data.black_score.to_string();
             score_b_string.insert_str(0, "Black: ");
 
             ctx.text().new_text_layout(
                 score_b_string
-            ).font(FontFamily::MONOSPACE, 20.)
-                .text_color(Color::BLACK)
+            ).font(theme_font(theme, FontFamily::MONOSPACE), font_size)
+                .text_color(theme.score(PlayerTurn::Black).clone())
                 .build()
                 .unwrap()
                 .draw(
-                    Point::new(20.,10.),
+                    Point::new(margin.x + self.cell_size / 3., margin.y + self.cell_size / 6.),
                         ctx.render_ctx
                 );
 
@@ -315,15 +965,35 @@ impl Widget<Reversi> for Grid {
 
             ctx.text().new_text_layout(
                 score_b_string
-            ).font(FontFamily::MONOSPACE, 20.)
-                .text_color(Color::WHITE)
+            ).font(theme_font(theme, FontFamily::MONOSPACE), font_size)
+                .text_color(theme.score(PlayerTurn::White).clone())
                 .build()
                 .unwrap()
                 .draw(
-                    Point::new(20.,30.),
+                    Point::new(margin.x + self.cell_size / 3., margin.y + self.cell_size / 2.),
                     ctx.render_ctx
                 );
 
+            if let Some(clocks) = &data.clocks {
+                for (side, y) in [(PlayerTurn::Black, 1. / 6.), (PlayerTurn::White, 1. / 2.)] {
+                    let mut clock_string = clocks.clock(side).display();
+                    if data.player_turn == side {
+                        clock_string.insert_str(0, "> ");
+                    }
+
+                    ctx.text().new_text_layout(
+                        clock_string
+                    ).font(theme_font(theme, FontFamily::MONOSPACE), font_size)
+                        .text_color(theme.score(side).clone())
+                        .build()
+                        .unwrap()
+                        .draw(
+                            Point::new(margin.x + self.cell_size * 2.5, margin.y + self.cell_size * y),
+                            ctx.render_ctx
+                        );
+                }
+            }
+
         }
 
     }
@@ -342,7 +1012,11 @@ impl Grid {
 
     const SWP_LEN: usize = 3;
 
-    const ERROR_COLOR: Color = Color::rgba8(255, 0, 0, 255/2);
+    const CLOCK_TICK: Duration = Duration::from_millis(100);
+    const NET_POLL: Duration = Duration::from_millis(100);
+
+    // the shadow under vector discs, drawn the same on every theme
+    const DISC_SHADOW: Color = Color::rgba8(0, 0, 0, 255/3);
 
 
     pub fn new() -> Self {
@@ -353,437 +1027,1117 @@ impl Grid {
             hor_offset: 0.0,
             cell_size: 0.0,
 
-            invalid_cell: ImageBuf::from_dynamic_image(
-                ICanRead::new(Cursor::new(&Self::INVALID)).with_guessed_format().unwrap().decode().unwrap()
-            ),
+            invalid_cell: Sprite::decode(&Self::INVALID),
 
-            black_cell: ImageBuf::from_dynamic_image(
-                ICanRead::new(Cursor::new(&Self::BLACK)).with_guessed_format().unwrap().decode().unwrap()
-            ),
+            black_cell: Sprite::decode(&Self::BLACK),
 
-            white_cell: ImageBuf::from_dynamic_image(
-                ICanRead::new(Cursor::new(&Self::WHITE)).with_guessed_format().unwrap().decode().unwrap()
-            ),
+            white_cell: Sprite::decode(&Self::WHITE),
 
             swap_cells: [
-                ImageBuf::from_dynamic_image(
-                    ICanRead::new(Cursor::new(&Self::SWP1)).with_guessed_format().unwrap().decode().unwrap()
-                ),
-                ImageBuf::from_dynamic_image(
-                    ICanRead::new(Cursor::new(&Self::SWP2)).with_guessed_format().unwrap().decode().unwrap()
-                ),
-                ImageBuf::from_dynamic_image(
-                    ICanRead::new(Cursor::new(&Self::SWP3)).with_guessed_format().unwrap().decode().unwrap()
-                )
+                Sprite::decode(&Self::SWP1),
+                Sprite::decode(&Self::SWP2),
+                Sprite::decode(&Self::SWP3),
             ],
+            swap_frames: true,
+            sprite_theme: String::new(),
             timer_code: TimerToken::INVALID,
             gaf: Self::MAX_GAF,
+            clock_timer: TimerToken::INVALID,
+            clock_game: None,
+            net_timer: TimerToken::INVALID,
+            highlight_timer: TimerToken::INVALID,
+            highlight_ply: 0,
+            highlight_since: None,
+        }
+    }
+
+    // the board drawn with shapes: shaded felt in a frame, the grid, the four star points and
+    // the coordinates, letters under the board and numbers left of it
+    fn paint_board(&self, ctx: &mut PaintCtx, theme: &Theme) {
+        let board = Rect::new(
+            self.hor_offset,
+            self.ver_offset,
+            self.hor_offset + self.cell_size * REVERSI_FIELD_WIDTH as f64,
+            self.ver_offset + self.cell_size * REVERSI_FIELD_HEIGHT as f64,
+        );
+
+        ctx.fill(board.inset(self.cell_size / 12.), &theme.grid);
+        ctx.fill(board, &LinearGradient::new(UnitPoint::TOP_LEFT, UnitPoint::BOTTOM_RIGHT, (theme.board.clone(), theme.board_shade.clone())));
+
+        let line_width = (self.cell_size / 40.).max(1.);
+        for line_idx in 1..REVERSI_FIELD_HEIGHT {
+            let y = board.y0 + line_idx as f64 * self.cell_size;
+            ctx.stroke(Line::new((board.x0, y), (board.x1, y)), &theme.grid, line_width);
+        }
+        for line_idx in 1..REVERSI_FIELD_WIDTH {
+            let x = board.x0 + line_idx as f64 * self.cell_size;
+            ctx.stroke(Line::new((x, board.y0), (x, board.y1)), &theme.grid, line_width);
+        }
+
+        // where the corner regions begin
+        let (star_x, star_y) = (REVERSI_FIELD_WIDTH / 4, REVERSI_FIELD_HEIGHT / 4);
+        for (x, y) in [(star_x, star_y), (REVERSI_FIELD_WIDTH - star_x, star_y), (star_x, REVERSI_FIELD_HEIGHT - star_y), (REVERSI_FIELD_WIDTH - star_x, REVERSI_FIELD_HEIGHT - star_y)] {
+            let star = Point::new(board.x0 + x as f64 * self.cell_size, board.y0 + y as f64 * self.cell_size);
+            ctx.fill(Circle::new(star, self.cell_size / 14.), &theme.grid);
+        }
+
+        let columns = (0..REVERSI_FIELD_WIDTH).map(|x| {
+            let center = Point::new(self.cell_rect(x, 0).center().x, board.y1 + self.cell_size * 0.35);
+            (move_name(x, 0)[..1].to_string(), center)
+        });
+        let rows = (0..REVERSI_FIELD_HEIGHT).map(|y| {
+            let center = Point::new(board.x0 - self.cell_size * 0.35, self.cell_rect(0, y).center().y);
+            ((y + 1).to_string(), center)
+        });
+
+        for (label, center) in columns.chain(rows).collect::<Vec<_>>() {
+            let text = ctx.text().new_text_layout(
+                label
+            ).font(theme_font(theme, FontFamily::SYSTEM_UI), self.cell_size / 4.)
+                .text_color(theme.label.clone())
+                .build()
+                .unwrap();
+
+            let size = text.size();
+            ctx.draw_text(&text, Point::new(center.x - size.width / 2., center.y - size.height / 2.));
         }
     }
 
+    // a disc drawn with shapes: a shadow, a body lit from the top left and a rim. The frames of
+    // the flip animation narrow it to its edge in the old color and widen it in the new one.
+    fn paint_disc(&self, ctx: &mut PaintCtx, theme: &Theme, cell_rect: Rect, side: PlayerTurn, frame: usize) {
+        let progress = frame as f64 / Self::SWP_LEN as f64;
+        let (side, width) = match frame {
+            0 => (side, 1.),
+            _ if progress < 0.5 => (side.opposite(), 1. - 2. * progress),
+            _ => (side, 2. * progress - 1.),
+        };
+
+        let radius = self.cell_size * 0.42;
+        let radii = Vec2::new(radius * width.max(0.1), radius);
+        let center = cell_rect.center();
+
+        ctx.fill(Ellipse::new(center + Vec2::new(radius * 0.08, radius * 0.12), radii, 0.), &Self::DISC_SHADOW);
+
+        let (light, dark) = match side {
+            PlayerTurn::Black => theme.black_disc.clone(),
+            PlayerTurn::White => theme.white_disc.clone(),
+        };
+
+        let body = Ellipse::new(center, radii, 0.);
+        ctx.fill(body, &RadialGradient::new(0.6, (light, dark.clone())).with_origin(UnitPoint::new(0.3, 0.3)));
+        ctx.stroke(body, &dark, 1.);
+    }
+
+    // the theme's disc images, the built-in ones for those it doesn't replace or that don't load
+    fn load_sprites(&mut self, theme: &Theme) {
+        let load = |path: &Option<PathBuf>| {
+            path.as_ref()
+                .and_then(|path| Sprite::open(path)
+                    .map_err(|err| eprintln!("failed to load {}: {}", path.display(), err))
+                    .ok())
+        };
+
+        let (black, white) = (load(&theme.black_sprite), load(&theme.white_sprite));
+        // the built-in flip frames turn a built-in disc into the other one and fit no other
+        self.swap_frames = black.is_none() && white.is_none();
+        self.black_cell = black.unwrap_or_else(|| Sprite::decode(&Self::BLACK));
+        self.white_cell = white.unwrap_or_else(|| Sprite::decode(&Self::WHITE));
+        self.sprite_theme = theme.name.clone();
+    }
+
+    // the board cell under the point, None off the board
+    fn cell_at(&self, pos: Point) -> Option<(usize, usize)> {
+        let x = ((pos.x - self.hor_offset) / self.cell_size).floor();
+        let y = ((pos.y - self.ver_offset) / self.cell_size).floor();
+
+        let on_board = x >= 0. && y >= 0. && x < REVERSI_FIELD_WIDTH as f64 && y < REVERSI_FIELD_HEIGHT as f64;
+        on_board.then_some((x as usize, y as usize))
+    }
+
+    // the square of the board cell as it was last laid out
+    fn cell_rect(&self, x: usize, y: usize) -> Rect {
+        Rect::new(
+            x as f64 * self.cell_size + self.hor_offset,
+            y as f64 * self.cell_size + self.ver_offset,
+            (x as f64 + 1_f64) * self.cell_size + self.hor_offset,
+            (y as f64 + 1_f64) * self.cell_size + self.ver_offset,
+        )
+    }
+
 }
 
 
-#[derive(Data, Clone)]
+#[derive(Data, Clone, Lens)]
 pub struct Reversi {
     pub mode: GameMode,
     pub player_turn: PlayerTurn,
     #[data(ignore)]
     pub field: Field,
+    #[data(same_fn = "PartialEq::eq")]
+    pub history: History,
+    // engine assessment of every position of the game so far, from Black's point of view. The
+    // latest ones hold the assessment before them until the assessor is done with them
+    #[data(same_fn = "PartialEq::eq")]
+    pub evals: Vec<i32>,
+    // assesses the positions on a thread of its own, see `Assessor`
+    #[data(ignore)]
+    pub assessor: Option<Rc<Assessor>>,
+    // the game the victory screen reports on
+    #[data(same_fn = "PartialEq::eq")]
+    pub last_game: Option<GameRecord>,
+    // move by move report on `last_game`, made on request
+    #[data(same_fn = "PartialEq::eq")]
+    pub analysis: Option<GameAnalysis>,
+    // moves analysed so far and moves in all while the analysis runs
+    #[data(same_fn = "PartialEq::eq")]
+    pub analyzing: Option<(usize, usize)>,
+    // stops the running analysis once its game is off the victory screen
+    #[data(ignore)]
+    pub analysis_stop: Option<Arc<AtomicBool>>,
+    // the PvE opponent, it thinks on a thread of its own, see `OpponentThread`
+    #[data(ignore)]
+    pub opponent: Option<Rc<OpponentThread>>,
+    // engine processes playing Black and White, kept across games
+    #[data(ignore)]
+    pub externals: [Option<Arc<EngineSlot>>; 2],
+    // a computer player is choosing the move of the side to move, the board takes no clicks
+    pub engine_thinking: bool,
+    // hands the results of work done on other threads back to the window
+    #[data(ignore)]
+    pub sink: Option<ExtEventSink>,
+    // numbers the games of the run, moves worked out on other threads name the game they are for
+    pub game_id: u64,
+    // every recorded game, read when first needed
+    #[data(ignore)]
+    pub ratings: Option<Rc<RefCell<RatingStore>>>,
+    // the side panel lists the best rated players and engines
+    pub show_leaderboard: bool,
+    pub leaderboard: String,
+    // named players to pick for either side, read when first needed
     #[data(ignore)]
-    pub rng: ThreadRng,
+    pub profiles: Option<Rc<RefCell<Profiles>>>,
+    // text of the statistics window, empty until it is first opened
+    pub stats: String,
+    // game played over the network, the board follows the session's game
+    #[data(ignore)]
+    pub network: Option<Rc<RefCell<Session>>>,
+    pub net_status: String,
+    // moves of the session's game already played on the board
+    pub net_ply: usize,
+    // latest lines of the session's chat and the one being typed
+    pub chat: String,
+    pub chat_input: String,
+    // unfinished game of an earlier run, waiting for the player to resume or drop it
+    #[data(ignore)]
+    pub saved: Option<SavedGame>,
+    // who plays the resumed game, None for a game set up from the settings
+    #[data(same_fn = "PartialEq::eq")]
+    pub resumed: Option<ResumedPlayers>,
+    // sound output, opened when the first sound is played
+    #[data(ignore)]
+    pub sounds: Option<Rc<Sounds>>,
+    // the built-in themes and the theme files, `settings.theme` picks one by name
+    #[data(ignore)]
+    pub themes: Rc<Vec<Theme>>,
+    pub settings: Settings,
+    #[data(same_fn = "PartialEq::eq")]
+    pub clocks: Option<GameClocks>,
+    // searches the hints and move scores on a thread of its own, see `Adviser`
+    #[data(ignore)]
+    pub adviser: Option<Rc<Adviser>>,
+    // engine advice for the position on the board, dropped once a move is made
+    #[data(same_fn = "PartialEq::eq")]
+    pub hint: Option<Hint>,
+    // analysis overlay for the position on the board, None while it is switched off
+    #[data(same_fn = "PartialEq::eq")]
+    pub move_scores: Option<MoveScores>,
     pub is_game: bool,
-    pub victorious: String,
+    pub outcome: Option<Outcome>,
     pub black_score: u32,
     pub white_score: u32,
 
 }
 
 impl Reversi {
-    pub fn new() -> Self {
+    const LEADERBOARD_SIZE: usize = 10;
+    const CHAT_LINES: usize = 6;
 
-        let mut initial_field: Field = [Cell::Free ; REVERSI_FIELD_SIZE];
-        let top_left_center: (usize, usize) = ((REVERSI_FIELD_WIDTH / 2) - 1, (REVERSI_FIELD_HEIGHT / 2) - 1);
-
-        initial_field[top_left_center.1 * REVERSI_FIELD_WIDTH + top_left_center.0] = Cell::White(0);
-        initial_field[(top_left_center.1 + 1) * REVERSI_FIELD_WIDTH + top_left_center.0] = Cell::Black(0);
-        initial_field[top_left_center.1 * REVERSI_FIELD_WIDTH + top_left_center.0 + 1] = Cell::Black(0);
-        initial_field[(top_left_center.1 + 1) * REVERSI_FIELD_WIDTH + top_left_center.0 + 1] = Cell::White(0);
+    // the game, the turns played when the computer was asked and its move, see `engine_moved`
+    const ENGINE_MOVED: Selector<(u64, usize, Result<Option<(usize, usize)>, String>)> = Selector::new("reversi.engine-moved");
+    // the game, its moves analysed so far and its moves in all, see `analysis_progress`
+    const ANALYSIS_PROGRESS: Selector<(u64, usize, usize)> = Selector::new("reversi.analysis-progress");
+    // the game and its analysis, see `analyzed`
+    const ANALYZED: Selector<(u64, GameAnalysis)> = Selector::new("reversi.analyzed");
 
+    pub fn new() -> Self {
 
         Self {
             mode: GameMode::PvP,
-            field: initial_field,
+            field: Board::new().field,
+            history: History::new(),
+            // the start position is balanced
+            evals: vec![0],
+            assessor: None,
+            last_game: None,
+            analysis: None,
+            analyzing: None,
+            analysis_stop: None,
             player_turn: PlayerTurn::Black,
-            rng: thread_rng(),
+            opponent: None,
+            externals: [None, None],
+            engine_thinking: false,
+            sink: None,
+            game_id: 0,
+            ratings: None,
+            show_leaderboard: false,
+            leaderboard: String::new(),
+            profiles: None,
+            stats: String::new(),
+            network: None,
+            net_status: String::new(),
+            net_ply: 0,
+            chat: String::new(),
+            chat_input: String::new(),
+            saved: None,
+            resumed: None,
+            sounds: None,
+            themes: Rc::new(Theme::built_in()),
+            settings: Settings::default(),
+            clocks: None,
+            adviser: None,
+            hint: None,
+            move_scores: None,
             is_game: true,
-            victorious: "".to_string(),
+            outcome: None,
             black_score: 2,
             white_score: 2,
         }
 
     }
 
-    pub fn switch_turn(&mut self) {
-
+    /// a new game in the default mode of the settings, with the autosaved one of the last run
+    /// offered to resume
+    pub fn with_saved_game(settings: Settings, sink: ExtEventSink) -> Self {
+        let mut reversi = Self::new();
+        let mode = settings.default_mode.clone();
+        reversi.settings = settings;
+        reversi.assessor = Some(Rc::new(Assessor::start(sink.clone())));
+        reversi.adviser = Some(Rc::new(Adviser::start(sink.clone())));
+        reversi.sink = Some(sink);
+        reversi.themes = Rc::new(Theme::load_all(Theme::dir().as_deref()));
+        // Black is never a computer on startup, the engine sides aren't kept
+        reversi.reset(mode);
+
+        if let Some(path) = SavedGame::default_path() {
+            reversi.saved = SavedGame::load(&path).unwrap_or_else(|err| {
+                eprintln!("failed to read the saved game from {}: {}", path.display(), err);
+                None
+            });
+        }
 
-        if let GameMode::PvE(error_chance) = self.mode {
+        reversi
+    }
 
-            if self.player_turn == PlayerTurn::Black {
-                self.player_turn = PlayerTurn::White;
+    /// starts a new game in the given mode, keeping the settings. A networked game is started
+    /// over by the host instead.
+    pub fn restart(&mut self, mode: GameMode) {
+        if let Some(session) = self.network.clone() {
+            if let Err(err) = session.borrow_mut().restart() {
+                self.net_status = err;
+            }
+            self.sync_network();
+            return;
+        }
 
-                //
+        self.reset(mode);
+        // an engine playing Black opens the game
+        self.computer_turn();
+    }
 
-                let mut moves: Vec<(usize, usize, usize)> = Vec::new();
+    // sets up a new game without letting a computer player open it
+    fn reset(&mut self, mode: GameMode) {
+        if let Some(stop) = self.analysis_stop.take() {
+            stop.store(true, Ordering::Relaxed);
+        }
 
-                for (idx, cell) in self.field.iter().enumerate() {
-                    let (x, y): (usize, usize) = (idx % REVERSI_FIELD_WIDTH, idx / REVERSI_FIELD_WIDTH);
+        let settings = self.settings.clone();
+        let (externals, sink, game_id) = (self.externals.clone(), self.sink.clone(), self.game_id + 1);
+        let ratings = self.ratings.clone();
+        let (show_leaderboard, leaderboard) = (self.show_leaderboard, self.leaderboard.clone());
+        let (profiles, stats) = (self.profiles.clone(), self.stats.clone());
+        let (network, net_status, net_ply) = (self.network.clone(), self.net_status.clone(), self.net_ply);
+        let (chat, chat_input) = (self.chat.clone(), self.chat_input.clone());
+        let saved = self.saved.take();
+        let (sounds, themes) = (self.sounds.clone(), self.themes.clone());
+        let (assessor, adviser) = (self.assessor.clone(), self.adviser.clone());
+        *self = Self::new();
+        self.settings = settings;
+        self.externals = externals;
+        self.sink = sink;
+        self.game_id = game_id;
+        self.ratings = ratings;
+        self.show_leaderboard = show_leaderboard;
+        self.leaderboard = leaderboard;
+        self.profiles = profiles;
+        self.stats = stats;
+        self.network = network;
+        self.net_status = net_status;
+        self.net_ply = net_ply;
+        self.chat = chat;
+        self.chat_input = chat_input;
+        self.saved = saved;
+        self.sounds = sounds;
+        self.themes = themes;
+        if let Some(assessor) = &assessor {
+            assessor.new_game(game_id);
+        }
+        self.assessor = assessor;
+        self.adviser = adviser;
+        self.advise_position();
+        // networked games are untimed
+        if self.network.is_none() {
+            self.clocks = self.settings.time_control.map(GameClocks::new);
+        }
+        self.mode = mode;
+        self.refresh_analysis();
+    }
 
-                    let mut cost: usize = 0;
+    pub fn board(&self) -> Board {
+        Board {
+            field: self.field,
+            player_turn: self.player_turn,
+        }
+    }
 
-                    if *cell == Cell::Free && self.is_valid_cell_cost(x, y, &mut cost) {
-                        moves.push((x, y, cost));
-                    }
-                }
-                if moves.len() > 0 {
-                    moves.sort_by_key(|(x, y, cost)| *cost);
+    /// ends the game: the victory screen shows the outcome and the next game is set up in the same mode
+    pub fn finish(&mut self, outcome: Outcome) {
+        let last_game = GameRecord {
+            history: self.history.clone(),
+            evals: self.evals.clone(),
+        };
+
+        let entry = GameEntry::new(self.mode.label(), &self.side_name(PlayerTurn::Black),
+                                   &self.side_name(PlayerTurn::White), &self.history, outcome);
+        if let Err(err) = self.ratings().borrow_mut().record(entry) {
+            eprintln!("failed to record the game: {}", err);
+        }
+        self.refresh_leaderboard();
+        self.play(Sound::GameOver);
+        // an open statistics window counts the game in
+        if !self.stats.is_empty() {
+            self.refresh_stats();
+        }
 
-                    if self.rng.gen::<f64>() < f64::MAX * error_chance {
-                        let random_move = moves[self.rng.gen_range(0..moves.len())];
-                        self.clicked(random_move.0, random_move.1);
-                    } else {
-                        let best_move = moves[0];
-                        self.clicked(best_move.0, best_move.1);
-                    }
+        // a networked game was never saved here
+        if self.network.is_none() {
+            Self::remove_autosave();
+        }
 
-                    self.switch_turn();
-                } else {
-                    let current_mode = self.mode.clone();
-                    *self = Self::new();
-                    self.mode = current_mode;
-                    self.is_game = false;
-                }
-                //
+        self.reset(self.mode.clone());
+        self.is_game = false;
+        self.outcome = Some(outcome);
+        self.last_game = Some(last_game);
+    }
 
-                self.player_turn = PlayerTurn::Black;
+    /// charges the side to move for the time since the last tick, a fallen flag ends the game
+    pub fn tick_clock(&mut self) {
+        // the clocks of the next game wait behind the victory screen
+        if !self.is_game {
+            return;
+        }
 
-                let mut has_to_reset: bool = true;
-                for (idx, cell) in self.field.iter().enumerate() {
-                    let (x, y): (usize, usize) = (idx % REVERSI_FIELD_WIDTH, idx / REVERSI_FIELD_WIDTH);
+        let side = self.player_turn;
 
-
-                    if *cell == Cell::Free && self.is_valid_cell(x, y) {
-                        has_to_reset = false;
-                    }
-                }
-
-                if has_to_reset {
-                    let current_mode = self.mode.clone();
-                    *self = Self::new();
-                    self.mode = current_mode;
-                    self.is_game = false;
-                }
+        if let Some(clocks) = &mut self.clocks {
+            if !clocks.tick(side) {
+                self.finish(Outcome::on_time(side));
             }
+        }
+    }
 
-        } else {
-
-            self.player_turn = if self.player_turn == PlayerTurn::Black {
-                PlayerTurn::White
-            } else {
-                PlayerTurn::Black
-            };
+    pub fn switch_turn(&mut self) {
 
+        if let Some(clocks) = &mut self.clocks {
+            if !clocks.press(self.player_turn) {
+                self.finish(Outcome::on_time(self.player_turn));
+                return;
+            }
         }
 
-        let mut black_score = 0;
-        let mut white_score = 0;
+        self.play(Sound::Move);
+        self.player_turn = self.player_turn.opposite();
 
-        for cell in self.field {
-            if let Cell::White(_) = cell {
-                white_score += 1;
-            }
+        let (black_score, white_score) = self.board().score();
+        self.black_score = black_score;
+        self.white_score = white_score;
+        self.queue_assessment();
 
-            if let Cell::Black(_) = cell {
-                black_score += 1;
+        if !self.board().has_valid_move() {
+            if self.board().is_over() {
+                self.finish(Outcome::from_board(&self.board()));
+                return;
             }
+
+            self.play(Sound::Pass);
+
+            // nothing to play, the turn goes back
+            self.history.push_pass(self.player_turn);
+            self.player_turn = self.player_turn.opposite();
+            self.queue_assessment();
         }
 
-        self.white_score = white_score;
-        self.black_score = black_score;
+        self.advise_position();
+        self.autosave();
 
-        self.victorious = if (white_score > black_score) { "Black" } else { "White" }.parse().unwrap();
+        // the computer's own move brings the overlay up to date
+        if !self.computer_turn() {
+            self.refresh_analysis();
+        } else {
+            self.move_scores = None;
+        }
+    }
 
+    // the position on the board takes its place in `evals` now and its assessment once the
+    // assessor gets to it
+    fn queue_assessment(&mut self) {
+        self.evals.push(self.evals.last().copied().unwrap_or(0));
+
+        if let Some(assessor) = &self.assessor {
+            assessor.queue(self.game_id, self.evals.len() - 1, self.board(), self.settings.threads);
+        }
     }
 
-    pub fn is_valid_cell(&self, x: usize, y: usize) -> bool {
-        if self.field[y * REVERSI_FIELD_WIDTH + x] != Cell::Free { return false ;}
+    /// takes in an assessment of a position of the game on the board, or of the finished game the
+    /// victory screen shows. Every reset after that drops `last_game`, so the game before this one
+    /// is the only earlier game it can be for.
+    pub fn assessed(&mut self, game: u64, ply: usize, eval: i32) {
+        let evals = if game == self.game_id {
+            &mut self.evals
+        } else if game + 1 == self.game_id {
+            match &mut self.last_game {
+                Some(last_game) => &mut last_game.evals,
+                None => return,
+            }
+        } else {
+            return;
+        };
 
-        let (x, y): (isize, isize) = (x as isize, y as isize);
+        if let Some(slot) = evals.get_mut(ply) {
+            *slot = eval;
+        }
+    }
 
+    // keeps the game at this board for a later run, networked games are the host's to keep
+    fn autosave(&self) {
+        if self.network.is_some() || !self.is_game || self.history.is_empty() {
+            return;
+        }
+        let Some(path) = SavedGame::default_path() else {
+            return;
+        };
 
+        if let Err(err) = SavedGame::from(self).save(&path) {
+            eprintln!("failed to save the game to {}: {}", path.display(), err);
+        }
+    }
 
-        for (x_coef, y_coef) in [(1_isize, 0_isize),
-            (0_isize, 1_isize),
-            (-1_isize, 0_isize),
-            (0_isize, -1_isize),
-            (1_isize, 1_isize),
-            (1_isize, -1_isize),
-            (-1_isize, 1_isize),
-            (-1_isize, -1_isize)] {
+    fn remove_autosave() {
+        if let Some(path) = SavedGame::default_path() {
+            if let Err(err) = SavedGame::remove(&path) {
+                eprintln!("failed to remove the saved game {}: {}", path.display(), err);
+            }
+        }
+    }
 
-            let mut factor = 1_isize;
+    /// carries on with the game saved by the last run, with its players and clocks
+    pub fn resume_saved(&mut self) {
+        if let Some(saved) = self.saved.take() {
+            self.resume(saved);
+        }
+    }
 
+    /// carries on with a saved game at this board, with its players and clocks. What
+    /// `SavedGame::from` makes of the game afterwards is the saved game again.
+    pub fn resume(&mut self, saved: SavedGame) {
+        self.network = None;
+        self.net_status.clear();
+        self.reset(saved.mode.clone());
+        // the game keeps its players and clocks, the settings are for the games set up here
+        self.resumed = Some(ResumedPlayers {
+            black: saved.black.clone(),
+            white: saved.white.clone(),
+            engine_command: saved.engine_command.clone(),
+            external_black: saved.external_black,
+            external_white: saved.external_white,
+        });
+
+        let positions = saved.history.positions();
+        let board = saved.board();
+        self.field = board.field;
+        self.player_turn = board.player_turn;
+        (self.black_score, self.white_score) = board.score();
+        // the assessor goes through them while the game goes on, the position on the board first
+        self.evals = vec![0; positions.len()];
+        if let Some(assessor) = &self.assessor {
+            for (ply, position) in positions.iter().enumerate().rev() {
+                assessor.queue(self.game_id, ply, *position, self.settings.threads);
+            }
+        }
+        self.history = saved.history;
+        self.clocks = saved.clocks.map(|(black, white)| GameClocks::resume(black, white));
 
+        self.refresh_analysis();
+        // the engine may be the one to move
+        self.computer_turn();
+    }
 
-            while (0..REVERSI_FIELD_HEIGHT as isize).contains(&(y + y_coef * factor))
-                && (0..REVERSI_FIELD_WIDTH as isize).contains(&(x + x_coef * factor))
-                && self.player_turn.is_reverse_of(&self.field[(y + y_coef * factor) as usize * REVERSI_FIELD_WIDTH + (x + x_coef * factor) as usize]) {
+    /// drops the game saved by the last run
+    pub fn discard_saved(&mut self) {
+        if self.saved.take().is_some() && self.history.is_empty() {
+            Self::remove_autosave();
+        }
+    }
 
-                factor += 1;
+    /// the theme the settings name, the default one when there is none by the name
+    pub fn theme(&self) -> &Theme {
+        self.themes.iter()
+            .find(|theme| theme.name == self.settings.theme)
+            .unwrap_or(&self.themes[0])
+    }
 
+    /// writes the settings file, see `Settings::default_path`
+    pub fn save_settings(&self) {
+        if let Some(path) = Settings::default_path() {
+            if let Err(err) = self.settings.save(&path) {
+                eprintln!("failed to save the settings to {}: {}", path.display(), err);
             }
+        }
+    }
 
+    // plays the sound when sounds are on
+    fn play(&mut self, sound: Sound) {
+        if !self.settings.sounds {
+            return;
+        }
 
+        if self.sounds.is_none() {
+            self.sounds = Sounds::open().map(Rc::new);
+        }
 
-            if factor > 1 && (0..REVERSI_FIELD_HEIGHT as isize).contains(&(y + y_coef * factor))
-                && (0..REVERSI_FIELD_WIDTH as isize).contains(&(x + x_coef * factor))
-                && self.player_turn == self.field[(y + y_coef * factor) as usize * REVERSI_FIELD_WIDTH + (x + x_coef * factor) as usize] {
+        if let Some(sounds) = &self.sounds {
+            sounds.play(sound);
+        }
+    }
 
-                return true;
-            }
+    fn ratings(&mut self) -> Rc<RefCell<RatingStore>> {
+        self.ratings.get_or_insert_with(|| Rc::new(RefCell::new(RatingStore::open_default()))).clone()
+    }
+
+    pub fn refresh_leaderboard(&mut self) {
+        if self.show_leaderboard {
+            self.leaderboard = leaderboard(&self.ratings().borrow().standings(), Self::LEADERBOARD_SIZE);
         }
-        return false;
     }
 
+    fn profiles(&mut self) -> Rc<RefCell<Profiles>> {
+        self.profiles.get_or_insert_with(|| Rc::new(RefCell::new(Profiles::open_default()))).clone()
+    }
 
-    pub fn is_valid_cell_cost(&self, x: usize, y: usize, cost: &mut usize) -> bool {
-        if !self.is_valid_cell(x, y) {
-            return false;
+    /// plays the side under the next profile, the game starts over for it. The names of a
+    /// networked game were given when it was joined.
+    pub fn next_profile(&mut self, side: PlayerTurn) {
+        if self.network.is_some() {
+            return;
         }
 
-        let mut n_inverse: usize = 0;
+        let Some(name) = self.profiles().borrow().next(self.settings.player_name(side)).map(String::from) else {
+            return;
+        };
+
+        match side {
+            PlayerTurn::Black => self.settings.black_name = name,
+            PlayerTurn::White => self.settings.white_name = name,
+        }
+        self.restart(self.mode.clone());
+    }
 
-        for (x_coef, y_coef) in [(1_isize, 0_isize),
-            (0_isize, 1_isize),
-            (-1_isize, 0_isize),
-            (0_isize, -1_isize),
-            (1_isize, 1_isize),
-            (1_isize, -1_isize),
-            (-1_isize, 1_isize),
-            (-1_isize, -1_isize)] {
+    /// keeps the names typed for both sides as profiles
+    pub fn save_profiles(&mut self) {
+        let profiles = self.profiles();
 
-            let mut factor = 1_isize;
+        for name in [&self.settings.black_name, &self.settings.white_name] {
+            if let Err(err) = profiles.borrow_mut().add(name) {
+                eprintln!("failed to save the profile: {}", err);
+            }
+        }
+    }
 
+    /// statistics of every profile, of the two names on the board while there are none
+    pub fn refresh_stats(&mut self) {
+        let mut names = self.profiles().borrow().names.clone();
+        if names.is_empty() {
+            names = vec![self.player_name(PlayerTurn::Black).to_string(), self.player_name(PlayerTurn::White).to_string()];
+        }
 
+        let ratings = self.ratings();
+        let ratings = ratings.borrow();
+        let reports: Vec<String> = names.iter()
+            .map(|name| ProfileStats::from_games(name, &ratings.games).report())
+            .collect();
+        self.stats = reports.join("\n\n");
+    }
 
-            while (0..REVERSI_FIELD_HEIGHT as isize).contains(&(y as isize + y_coef * factor))
-                && (0..REVERSI_FIELD_WIDTH as isize).contains(&(x as isize + x_coef * factor))
-                && self.player_turn.is_reverse_of(&self.field[(y as isize + y_coef * factor) as usize * REVERSI_FIELD_WIDTH + (x as isize + x_coef * factor) as usize]) {
-                factor += 1;
+    // name the side is rated under: the networked opponent's, the external engine's, the PvE
+    // opponent's or the player's
+    fn side_name(&self, side: PlayerTurn) -> String {
+        if let Some(session) = &self.network {
+            let session = session.borrow();
+            // a server may seat the one who joined as either color
+            return if side == session.side {
+                session.name.clone()
+            } else {
+                session.peer_name.clone().unwrap_or_default()
+            };
+        }
 
+        if self.is_external(side) {
+            if let Some(slot) = &self.externals[if side == PlayerTurn::Black { 0 } else { 1 }] {
+                return slot.name();
             }
+        }
 
+        match &self.mode {
+            GameMode::PvE(opponent) if side == PlayerTurn::White => opponent.name().to_string(),
+            _ => self.player_name(side).to_string(),
+        }
+    }
 
+    // name of the player at the board for the side, from the resumed game or the settings
+    fn player_name(&self, side: PlayerTurn) -> &str {
+        match &self.resumed {
+            Some(players) if side == PlayerTurn::Black => &players.black,
+            Some(players) => &players.white,
+            None => self.settings.player_name(side),
+        }
+    }
 
-            if factor > 1 && (0..REVERSI_FIELD_HEIGHT as isize).contains(&(y as isize + y_coef * factor))
-                && (0..REVERSI_FIELD_WIDTH as isize).contains(&(x as isize + x_coef * factor))
-                && self.player_turn == self.field[(y as isize + y_coef * factor) as usize * REVERSI_FIELD_WIDTH + (x as isize + x_coef * factor) as usize] {
+    fn engine_command(&self) -> &str {
+        self.resumed.as_ref().map_or(&self.settings.engine_command, |players| &players.engine_command)
+    }
 
-                n_inverse += factor as usize;
+    // an external engine plays the side, in the resumed game or by the settings
+    fn is_external(&self, side: PlayerTurn) -> bool {
+        match &self.resumed {
+            Some(players) if side == PlayerTurn::Black => players.external_black,
+            Some(players) => players.external_white,
+            None => self.settings.is_external(side),
+        }
+    }
 
-            }
+    // the PvE opponent when it plays the side, None for sides played at the board or by an
+    // external engine
+    fn opponent_for(&mut self, side: PlayerTurn) -> Option<Rc<OpponentThread>> {
+        let sink = self.sink.clone()?;
+
+        match &self.mode {
+            GameMode::PvE(opponent) if side == PlayerTurn::White && !self.is_external(side) => {
+                let (opponent, settings) = (opponent.clone(), self.settings.clone());
+                Some(self.opponent.get_or_insert_with(|| Rc::new(OpponentThread::start(opponent, settings, sink))).clone())
+            },
+            _ => None,
         }
+    }
 
-        *cost = n_inverse;
-        true
+    // the engine process of the side, replaced when the command changed
+    fn external_engine(&mut self, side: PlayerTurn) -> Arc<EngineSlot> {
+        let command = self.engine_command().to_string();
+        let slot = &mut self.externals[if side == PlayerTurn::Black { 0 } else { 1 }];
+
+        match slot {
+            Some(engine) if engine.command == command => engine.clone(),
+            _ => slot.insert(Arc::new(EngineSlot::new(&command))).clone(),
+        }
     }
 
+    // asks the external engine of the side to move for its move on a thread of its own, the
+    // move comes back as an `ENGINE_MOVED` command like the PvE opponent's
+    fn start_engine_move(&mut self) -> bool {
+        let Some(sink) = self.sink.clone() else {
+            return false;
+        };
 
-    pub fn clicked(&mut self, x: usize, y: usize) {
+        let engine = self.external_engine(self.player_turn);
+        let (game, ply, board) = (self.game_id, self.history.len(), self.board());
 
-        if self.is_valid_cell(x, y) {
-            self.field[y * REVERSI_FIELD_WIDTH + x] = self.player_turn.produce();
+        thread::spawn(move || {
+            let choice = engine.request_move(&board);
+            // the window is gone when this fails, nobody is waiting for the move anymore
+            let _ = sink.submit_command(Self::ENGINE_MOVED, (game, ply, choice), Target::Auto);
+        });
 
+        self.engine_thinking = true;
+        true
+    }
 
-            let mut inverse: Vec<usize> = Vec::new();
+    /// plays the move a computer player chose, unless the game moved on while it was thinking
+    pub fn engine_moved(&mut self, game: u64, ply: usize, choice: Result<Option<(usize, usize)>, String>) {
+        if game != self.game_id || ply != self.history.len() || !self.is_game {
+            return;
+        }
 
-            for (x_coef, y_coef) in [(1_isize, 0_isize),
-                (0_isize, 1_isize),
-                (-1_isize, 0_isize),
-                (0_isize, -1_isize),
-                (1_isize, 1_isize),
-                (1_isize, -1_isize),
-                (-1_isize, 1_isize),
-                (-1_isize, -1_isize)] {
+        self.engine_thinking = false;
 
-                let mut factor = 1_isize;
+        match choice {
+            Ok(Some((x, y))) => self.clicked(x, y),
+            // the side is left to the player at the board
+            Ok(None) => {},
+            Err(err) => {
+                eprintln!("external engine for {}: {}", self.player_turn.name(), err);
+                // a late answer may still be on its way, the next move starts the engine afresh
+                self.externals[if self.player_turn == PlayerTurn::Black { 0 } else { 1 }] = None;
+            },
+        }
+    }
 
+    // lets the computer move when it plays the side to move, false when the move is left to
+    // the player at the board. The move comes later, see `engine_moved`
+    fn computer_turn(&mut self) -> bool {
+        if !self.is_game || self.network.is_some() {
+            return false;
+        }
 
+        if self.is_external(self.player_turn) {
+            return self.start_engine_move();
+        }
 
-                while (0..REVERSI_FIELD_HEIGHT as isize).contains(&(y as isize + y_coef * factor))
-                    && (0..REVERSI_FIELD_WIDTH as isize).contains(&(x as isize + x_coef * factor))
-                    && self.player_turn.is_reverse_of(&self.field[(y as isize + y_coef * factor) as usize * REVERSI_FIELD_WIDTH + (x as isize + x_coef * factor) as usize]) {
+        let Some(opponent) = self.opponent_for(self.player_turn) else {
+            return false;
+        };
+        // the clock as it was when the opponent was asked, it keeps running while it thinks
+        let clock = self.clocks.as_ref().map(|clocks| clocks.state(self.player_turn));
 
-                    inverse.push((y as isize + y_coef * factor) as usize * REVERSI_FIELD_WIDTH + (x as isize + x_coef * factor) as usize);
-                    factor += 1;
+        opponent.request_move(self.game_id, self.history.len(), self.board(), clock);
+        self.engine_thinking = true;
+        true
+    }
 
-                }
+    /// hosts a networked game on the port of the address box, the host plays Black
+    pub fn host_game(&mut self) {
+        // a session hosted before frees the port first
+        self.network = None;
 
+        match Session::host(self.settings.net_port(), &self.settings.black_name) {
+            Ok(session) => self.start_network(session),
+            Err(err) => self.net_status = format!("failed to host: {}", err),
+        }
+    }
 
+    /// joins the game hosted at the address box, the host assigns the color
+    pub fn join_game(&mut self) {
+        self.network = None;
 
-                if factor > 1 && (0..REVERSI_FIELD_HEIGHT as isize).contains(&(y as isize + y_coef * factor))
-                    && (0..REVERSI_FIELD_WIDTH as isize).contains(&(x as isize + x_coef * factor))
-                    && self.player_turn == self.field[(y as isize + y_coef * factor) as usize * REVERSI_FIELD_WIDTH + (x as isize + x_coef * factor) as usize] {
+        match Session::join(&self.settings.net_address, &self.settings.white_name) {
+            Ok(session) => self.start_network(session),
+            Err(err) => self.net_status = format!("failed to join {}: {}", self.settings.net_address, err),
+        }
+    }
 
-                    for idx in &inverse {
-                        self.field[*idx].inverse();
-                        if let (White(f) | Black(f)) = self.field.get_mut(*idx).unwrap() {
-                            *f = 1;
-                        }
-                    }
+    fn start_network(&mut self, session: Session) {
+        self.net_status = session.status();
+        self.network = Some(Rc::new(RefCell::new(session)));
+        self.net_ply = 0;
+        self.reset(GameMode::PvP);
+    }
 
-                } else {
-                    inverse.clear();
-                }
-            }
+    /// ends the networked game and goes back to playing at this board
+    pub fn leave_network(&mut self) {
+        if self.network.take().is_some() {
+            self.net_status.clear();
+            self.restart(GameMode::PvP);
+        }
+    }
 
-            self.switch_turn();
+    /// takes in what the opponent sent, returns an offer that came for the player to answer
+    pub fn poll_network(&mut self) -> Option<Offer> {
+        let session = self.network.clone()?;
+
+        let events = session.borrow_mut().poll();
+        let status = session.borrow().status();
+        let mut offered = None;
+
+        // a refusal or notice stays in view until the next event
+        for event in events {
+            self.net_status = match event {
+                NetEvent::Rejected(text) | NetEvent::Info(text) => format!("{} ({})", status, text),
+                NetEvent::Offered(offer) => {
+                    offered = Some(offer);
+                    format!("{} ({} offered)", status, offer.name())
+                },
+                NetEvent::Answered(offer, accepted) => {
+                    format!("{} ({} {})", status, offer.name(), if accepted { "accepted" } else { "declined" })
+                },
+                _ => status.clone(),
+            };
         }
 
+        if !self.net_status.starts_with(&status) {
+            self.net_status = status;
+        }
+        self.refresh_chat();
+        self.sync_network();
+        offered
+    }
 
+    /// plays a move of this end's color in the networked game
+    pub fn network_move(&mut self, x: usize, y: usize) {
+        let Some(session) = self.network.clone() else {
+            return;
+        };
 
+        let played = session.borrow_mut().play(x, y);
+        if let Err(err) = played {
+            self.net_status = format!("{} ({})", session.borrow().status(), err);
         }
+        self.sync_network();
+    }
 
+    /// gives up the game: a networked one through the session, one at this board for the side
+    /// to move
+    pub fn resign(&mut self) {
+        let Some(session) = self.network.clone() else {
+            if self.is_game {
+                self.finish(Outcome::resigned(self.player_turn));
+            }
+            return;
+        };
 
-}
+        let resigned = session.borrow_mut().resign();
+        if let Err(err) = resigned {
+            self.net_status = format!("{} ({})", session.borrow().status(), err);
+        }
+        self.sync_network();
+    }
 
+    /// proposes a draw or a takeback to the networked opponent
+    pub fn propose(&mut self, offer: Offer) {
+        let Some(session) = self.network.clone() else {
+            return;
+        };
+
+        let proposed = session.borrow_mut().propose(offer);
+        let status = session.borrow().status();
+        self.net_status = match proposed {
+            Ok(()) => format!("{} ({} proposed)", status, offer.name()),
+            Err(err) => format!("{} ({})", status, err),
+        };
+    }
 
+    /// accepts or declines the networked opponent's offer
+    pub fn answer_offer(&mut self, accept: bool) {
+        let Some(session) = self.network.clone() else {
+            return;
+        };
 
-#[derive(Clone, Copy, Debug)]
-pub enum Cell {
-    Black(usize),
-    White(usize),
-    Free
-}
+        let answered = session.borrow_mut().answer(accept);
+        if let Err(err) = answered {
+            self.net_status = format!("{} ({})", session.borrow().status(), err);
+        }
+        self.sync_network();
+    }
 
-impl PartialEq<Cell> for Cell {
-    fn eq(&self, other: &Cell) -> bool {
+    /// sends the typed chat line
+    pub fn send_chat(&mut self) {
+        let Some(session) = self.network.clone() else {
+            return;
+        };
 
-        if let (Cell::White(_), Cell::White(_)) = (*self, *other) {
-            return true;
+        let said = session.borrow_mut().say(&self.chat_input);
+        match said {
+            Ok(()) => self.chat_input.clear(),
+            Err(err) => self.net_status = format!("{} ({})", session.borrow().status(), err),
         }
+        self.refresh_chat();
+    }
 
-        if let (Cell::Black(_), Cell::Black(_)) = (*self, *other) {
-            return true;
+    fn refresh_chat(&mut self) {
+        if let Some(session) = &self.network {
+            let chat = &session.borrow().chat;
+            self.chat = chat[chat.len().saturating_sub(Self::CHAT_LINES)..].join("\n");
         }
+    }
 
-        if let (Cell::Free, Cell::Free) = (*self, *other) {
-            return true;
+    // plays the session's moves the board hasn't seen yet, a shorter game means it was started
+    // over or a move was taken back
+    fn sync_network(&mut self) {
+        let Some(session) = self.network.clone() else {
+            return;
+        };
+
+        let (moves, outcome) = {
+            let session = session.borrow();
+            (session.game.history.moves.clone(), session.game.outcome())
+        };
+
+        if moves.len() < self.net_ply || (!self.is_game && outcome.is_none()) {
+            self.reset(GameMode::PvP);
+            self.net_ply = 0;
         }
 
-        false
-    }
-}
-
-impl Cell {
-    pub fn clr(&self) -> &Color {
-        match self {
-            Cell::Black(_) => &Color::BLACK,
-            Cell::White(_) => &Color::WHITE,
-            Cell::Free => &Color::GRAY,
+        for played in &moves[self.net_ply..] {
+            // passes follow by themselves
+            if let Some((x, y)) = played.cell {
+                self.clicked(x, y);
+            }
         }
-    }
 
-    pub fn inverse(&mut self) {
-        *self = match self {
-            Black(f) => White(*f),
-            White(f) => Black(*f),
-            Free => Free,
+        self.net_ply = moves.len();
+
+        // a resignation or agreed draw ends the game before the board does
+        if let Some(outcome) = outcome.filter(|outcome| outcome.reason != Reason::Discs) {
+            if self.is_game {
+                self.finish(outcome);
+            }
         }
     }
-}
 
-impl PartialEq<PlayerTurn> for Cell {
-    fn eq(&self, other: &PlayerTurn) -> bool {
-        (*self == Cell::White(0) && *other == PlayerTurn::White) || (*self == Cell::Black(0) && *other == PlayerTurn::Black)
-    }
-}
+    /// classifies every move of the finished game the victory screen shows on a thread of its
+    /// own, the progress and the analysis come back as `ANALYSIS_PROGRESS` and `ANALYZED` commands
+    pub fn analyze(&mut self) {
+        if self.is_game || self.analyzing.is_some() {
+            return;
+        }
 
+        let (Some(last_game), Some(sink)) = (&self.last_game, self.sink.clone()) else {
+            return;
+        };
 
+        let (game, history, threads) = (self.game_id, last_game.history.clone(), self.settings.threads);
+        let stop = Arc::new(AtomicBool::new(false));
+        let stopped = stop.clone();
 
+        thread::spawn(move || {
+            let analysis = GameAnalysis::run(&history, threads, |done, total| {
+                // a failed command means the window is gone
+                !stopped.load(Ordering::Relaxed)
+                    && sink.submit_command(Self::ANALYSIS_PROGRESS, (game, done, total), Target::Auto).is_ok()
+            });
 
-#[repr(u8)]
-#[derive(PartialEq, Clone, Data, Copy, Debug)]
-pub enum PlayerTurn {
-    Black,
-    White,
-}
+            if let Some(analysis) = analysis {
+                let _ = sink.submit_command(Self::ANALYZED, (game, analysis), Target::Auto);
+            }
+        });
 
-impl PartialEq<Cell> for PlayerTurn {
-    fn eq(&self, other: &Cell) -> bool {
-        (*self == PlayerTurn::White && *other == Cell::White(0)) || (*self == PlayerTurn::Black && *other == Cell::Black(0))
+        self.analysis_stop = Some(stop);
+        self.analyzing = Some((0, last_game.history.moves.iter().filter(|played| played.cell.is_some()).count()));
     }
-}
 
+    /// takes in how far the analysis of the game on the victory screen got
+    pub fn analysis_progress(&mut self, game: u64, done: usize, total: usize) {
+        if game == self.game_id && self.analyzing.is_some() {
+            self.analyzing = Some((done, total));
+        }
+    }
 
-impl PlayerTurn {
-    const BLACK_NAME: &'static str = "Black";
-    const WHITE_NAME: &'static str = "White";
+    /// takes in the analysis of the game on the victory screen, unless a new game started meanwhile
+    pub fn analyzed(&mut self, game: u64, analysis: GameAnalysis) {
+        if game == self.game_id && !self.is_game {
+            self.analyzing = None;
+            self.analysis_stop = None;
+            self.analysis = Some(analysis);
+        }
+    }
 
-    const SELECT_BLACK: Color = Color::rgba8(0, 0, 0, 255/5);
-    const SELECT_WHITE: Color = Color::rgba8(255, 255, 255, 255/5);
+    /// scores the moves of the side to move for the analysis overlay when it is switched on, they
+    /// show once `scored` gets them
+    pub fn refresh_analysis(&mut self) {
+        self.move_scores = None;
 
-    const SELECT_VALID_BLACK: Color = Color::rgba8(0, 0, 0, 255/2);
-    const SELECT_VALID_WHITE: Color = Color::rgba8(255,255,255,255/2);
+        if let (true, Some(adviser)) = (self.settings.show_move_scores, &self.adviser) {
+            adviser.score_moves(self.board(), self.settings.threads);
+        }
+    }
 
-    pub fn name(&self) -> &'static str {
-        match self {
-            PlayerTurn::Black => Self::BLACK_NAME,
-            PlayerTurn::White => Self::WHITE_NAME,
+    /// takes in the move scores of the overlay, unless they are for a position no longer on the
+    /// board
+    pub fn scored(&mut self, position: u64, scores: MoveScores) {
+        if self.settings.show_move_scores && position == tt::hash(&self.board()) {
+            self.move_scores = Some(scores);
         }
+    }
 
+    /// asks the engine for the best move of the side to move, it shows once `hinted` gets it
+    pub fn show_hint(&mut self) {
+        if let (true, Some(adviser)) = (self.is_game, &self.adviser) {
+            adviser.hint(self.board(), self.settings.threads);
+        }
     }
 
-    pub fn clr(&self) -> &Color {
-        match self {
-            PlayerTurn::Black => &Self::SELECT_BLACK,
-            PlayerTurn::White => &Self::SELECT_WHITE,
+    /// takes in a hint, unless it was searched for a position no longer on the board
+    pub fn hinted(&mut self, position: u64, hint: Option<Hint>) {
+        if self.is_game && position == tt::hash(&self.board()) {
+            self.hint = hint;
         }
     }
 
-    pub fn strong_clr(&self) -> &Color {
-        match self {
-            PlayerTurn::Black => &Self::SELECT_VALID_BLACK,
-            PlayerTurn::White => &Self::SELECT_VALID_WHITE,
+    // lets the adviser drop what it was still asked about the positions before
+    fn advise_position(&self) {
+        if let Some(adviser) = &self.adviser {
+            adviser.new_position(&self.board());
         }
     }
 
-    pub fn produce(&self) -> Cell {
-        match self {
-            PlayerTurn::Black => {
-                Cell::Black(0)
-            }
-            PlayerTurn::White => {
-                Cell::White(0)
+    pub fn is_valid_cell(&self, x: usize, y: usize) -> bool {
+        self.board().is_valid_cell(x, y)
+    }
+
+
+    pub fn clicked(&mut self, x: usize, y: usize) {
+
+        let inverse = self.board().flips(x, y);
+
+        if !inverse.is_empty() {
+            self.hint = None;
+            self.history.push(self.player_turn, (x, y), inverse.clone());
+            self.field[y * REVERSI_FIELD_WIDTH + x] = self.player_turn.produce();
+
+            for idx in &inverse {
+                self.field[*idx].inverse();
+                // the flip animation starts, the discs show their new color at once without one
+                if let (White(f) | Black(f)) = self.field.get_mut(*idx).unwrap() {
+                    *f = if self.settings.animation_step.is_zero() { 0 } else { 1 };
+                }
             }
+
+            self.switch_turn();
         }
-    }
 
-    pub fn is_reverse_of(&self, cell: &Cell) -> bool {
-        (*self == PlayerTurn::White && *cell == Cell::Black(0)) ||
-            (*self == PlayerTurn::Black && *cell == Cell::White(0))
     }
+
+
 }
 
-#[derive(Data, Clone, PartialEq)]
-pub enum GameMode {
-    PvP,
-    PvE(f64),
+
+/// The game at the board without its UI state, which is what gets saved and serialized of it.
+/// `Reversi::resume` carries on with it.
+impl From<&Reversi> for SavedGame {
+    fn from(rev: &Reversi) -> Self {
+        SavedGame {
+            mode: rev.mode.clone(),
+            black: rev.player_name(PlayerTurn::Black).to_string(),
+            white: rev.player_name(PlayerTurn::White).to_string(),
+            engine_command: rev.engine_command().to_string(),
+            external_black: rev.is_external(PlayerTurn::Black),
+            external_white: rev.is_external(PlayerTurn::White),
+            clocks: rev.clocks.map(|clocks| (clocks.black, clocks.white)),
+            history: rev.history.clone(),
+        }
+    }
 }
 
 
 struct VictoryScreen {
-    crown: ImageBuf,
+    crown: Sprite,
 }
 
 impl Widget<Reversi> for VictoryScreen {
@@ -797,27 +2151,37 @@ impl Widget<Reversi> for VictoryScreen {
     }
 
     fn update(&mut self, ctx: &mut UpdateCtx, old_data: &Reversi, data: &Reversi, env: &Env) {
-
+        ctx.request_paint();
     }
 
     fn layout(&mut self, ctx: &mut LayoutCtx, bc: &BoxConstraints, data: &Reversi, env: &Env) -> Size {
-        Size::new(600.,600.)
+        available(bc)
     }
 
     fn paint(&mut self, ctx: &mut PaintCtx, data: &Reversi, env: &Env) {
-        let bbox = ctx.region().bounding_box();
+        let bbox = ctx.size().to_rect();
 
-        ctx.fill(
-            bbox,
-            if &data.victorious == "Black" { &Color::BLACK } else {&Color::WHITE}
-        );
+        if let (Some(last_game), Some(analysis)) = (&data.last_game, &data.analysis) {
+            ctx.fill(bbox, &data.theme().background);
+            self.paint_chart(ctx, data.theme(), last_game, Rect::new(50., 40., bbox.width() - 50., bbox.height() / 2. - 40.));
+            self.paint_report(ctx, data, analysis, Point::new(50., bbox.height() / 2.));
+            return;
+        }
+
+        // the winner's disc color, written on in the other one
+        let theme = data.theme();
+        let (fill, text_color) = match data.outcome.and_then(|outcome| outcome.winner) {
+            Some(PlayerTurn::Black) => (&theme.black_disc.1, &theme.white_disc.0),
+            Some(PlayerTurn::White) => (&theme.white_disc.0, &theme.black_disc.1),
+            None => (&theme.background, &theme.label),
+        };
+        ctx.fill(bbox, fill);
 
-        let mut winner_string = data.victorious.clone();
-        winner_string.push_str(" wins!");
+        let winner_string = data.outcome.map(|outcome| outcome.describe()).unwrap_or_default();
 
         let text = ctx.text().new_text_layout(
             winner_string
-        ).text_color(Color::SILVER)
+        ).text_color(text_color.clone())
             .font(FontFamily::SYSTEM_UI, 20.)
             .build()
             .unwrap();
@@ -828,7 +2192,8 @@ impl Widget<Reversi> for VictoryScreen {
             &text, Point::new(bbox.width() / 2. - (text_sz.width / 2.), bbox.height() / 2. - (text_sz.height / 2.))
         );
 
-        let bmp = self.crown.to_image(
+        let crown_size = self.crown.size();
+        let bmp = self.crown.at(crown_size, window_scale(ctx)).to_image(
             ctx.render_ctx
         );
 
@@ -836,25 +2201,114 @@ impl Widget<Reversi> for VictoryScreen {
         ctx.draw_image(
             &bmp,
             Rect::new(
-                bbox.width() / 2.0 - self.crown.width() as f64 / 2.0,
+                bbox.width() / 2.0 - crown_size.width / 2.0,
                 bbox.height() / 2.0 + text_sz.height / 2.0 + 60.,
-                bbox.width() / 2.0 + self.crown.width() as f64 / 2.0,
-                bbox.height() / 2.0 + text_sz.height / 2.0 + 60.0 + self.crown.height() as f64
+                bbox.width() / 2.0 + crown_size.width / 2.0,
+                bbox.height() / 2.0 + text_sz.height / 2.0 + 60.0 + crown_size.height
             ),
             InterpolationMode::Bilinear
-        )
+        );
+
+        if let Some(last_game) = &data.last_game {
+            self.paint_chart(ctx, data.theme(), last_game, Rect::new(50., 40., bbox.width() - 50., bbox.height() / 2. - 40.));
+        }
 
     }
 }
 
 impl VictoryScreen {
+    // moves listed under the summary, the rest is in the exported report
+    const REPORT_MOVES: usize = 10;
+
+    // result, per side counts of every class and the costliest moves
+    fn paint_report(&self, ctx: &mut PaintCtx, data: &Reversi, analysis: &GameAnalysis, origin: Point) {
+        let mut lines = vec![data.outcome.map(|outcome| outcome.describe()).unwrap_or_default()];
+
+        for side in [PlayerTurn::Black, PlayerTurn::White] {
+            let counts: Vec<String> = Classification::ALL.iter()
+                .map(|class| format!("{} {}", analysis.count(side, *class), class.name()))
+                .collect();
+            lines.push(format!("{}: {}", side.name(), counts.join(", ")));
+        }
+
+        lines.push(String::new());
+
+        for analysed in analysis.worst(Self::REPORT_MOVES) {
+            lines.push(format!(
+                "{:>2}. {} {} {} (-{:.1}), best {}",
+                analysed.ply + 1,
+                analysed.side.name(),
+                move_name(analysed.played.0, analysed.played.1),
+                analysed.class.name(),
+                analysed.loss() as f64 / DISC as f64,
+                move_name(analysed.best.0, analysed.best.1),
+            ));
+        }
+
+        for (idx, line) in lines.into_iter().enumerate() {
+            ctx.text().new_text_layout(
+                line
+            ).font(FontFamily::MONOSPACE, 14.)
+                .text_color(data.theme().label.clone())
+                .build()
+                .unwrap()
+                .draw(
+                    Point::new(origin.x, origin.y + idx as f64 * 18.),
+                    ctx.render_ctx
+                );
+        }
+    }
+
+    // evaluation and both disc counts over the plies of the game
+    fn paint_chart(&self, ctx: &mut PaintCtx, theme: &Theme, last_game: &GameRecord, area: Rect) {
+        ctx.fill(area, &theme.background);
+        ctx.stroke(area, &theme.chart, 1.);
+
+        let middle = area.y0 + area.height() / 2.;
+        ctx.stroke(Line::new((area.x0, middle), (area.x1, middle)), &theme.chart, 0.5);
+
+        let discs = last_game.discs();
+        let plies = discs.len().max(2) - 1;
+        let ply_x = |ply: usize| area.x0 + area.width() * ply as f64 / plies as f64;
+
+        // disc counts go from 0 at the bottom to a full board at the top
+        let disc_y = |count: u32| area.y1 - area.height() * count as f64 / REVERSI_FIELD_SIZE as f64;
+
+        for side in [PlayerTurn::Black, PlayerTurn::White] {
+            for (ply, pair) in discs.windows(2).enumerate() {
+                let (from, to) = match side {
+                    PlayerTurn::Black => (pair[0].0, pair[1].0),
+                    PlayerTurn::White => (pair[0].1, pair[1].1),
+                };
+
+                ctx.stroke(Line::new((ply_x(ply), disc_y(from)), (ply_x(ply + 1), disc_y(to))), theme.score(side), 1.5);
+            }
+        }
+
+        // the evaluation is centered on the middle line, Black's advantage upwards
+        let eval_range = last_game.evals.iter().map(|eval| eval.abs()).max().unwrap_or(0).max(4 * DISC) as f64;
+        let eval_y = |eval: i32| middle - area.height() / 2. * eval as f64 / eval_range;
+
+        for (ply, pair) in last_game.evals.windows(2).enumerate() {
+            ctx.stroke(Line::new((ply_x(ply), eval_y(pair[0])), (ply_x(ply + 1), eval_y(pair[1]))), &theme.eval, 2.);
+        }
+
+        ctx.text().new_text_layout(
+            format!("eval (max {:.1}), discs per ply", eval_range / DISC as f64)
+        ).font(FontFamily::MONOSPACE, 12.)
+            .text_color(theme.eval.clone())
+            .build()
+            .unwrap()
+            .draw(
+                Point::new(area.x0 + 4., area.y0 + 4.),
+                ctx.render_ctx
+            );
+    }
 
     const CROWN: [u8;8714] = *include_bytes!("../res/crown.png");
 
     fn new() -> Self {
-        Self { crown: ImageBuf::from_dynamic_image(
-            ICanRead::new(Cursor::new(&Self::CROWN)).with_guessed_format().unwrap().decode().unwrap()
-        ) }
+        Self { crown: Sprite::decode(&Self::CROWN) }
     }
 }
 
@@ -863,3 +2317,406 @@ impl VictoryScreen {
 
 
 
+
+
+/// The names and engine sides a game resumed from the last run was played with. They stay with
+/// that game and leave the settings be, the next game is set up from the settings again.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ResumedPlayers {
+    pub black: String,
+    pub white: String,
+    pub engine_command: String,
+    pub external_black: bool,
+    pub external_white: bool,
+}
+
+
+/// An external engine process for one side, started by the first thread that needs a move from
+/// it, so neither starting it nor waiting for its moves holds up the window
+pub struct EngineSlot {
+    // what it was started with, a changed command replaces the slot
+    command: String,
+    engine: Mutex<Option<ExternalEngine>>,
+}
+
+impl EngineSlot {
+    fn new(command: &str) -> Self {
+        Self {
+            command: command.to_string(),
+            engine: Mutex::new(None),
+        }
+    }
+
+    // waits for the engine's move, within `ExternalEngine::DEFAULT_TIMEOUT`
+    fn request_move(&self, board: &Board) -> Result<Option<(usize, usize)>, String> {
+        let mut engine = self.engine.lock().unwrap_or_else(PoisonError::into_inner);
+
+        if engine.is_none() {
+            let launched = ExternalEngine::launch(&self.command, ExternalEngine::DEFAULT_DEPTH)
+                .map_err(|err| format!("failed to start engine '{}': {}", self.command, err))?;
+            *engine = Some(launched);
+        }
+
+        engine.as_mut().unwrap().request_move(board).map_err(|err| err.to_string())
+    }
+
+    // the name the engine gave, its command before it did or while it is thinking
+    fn name(&self) -> String {
+        match self.engine.try_lock().as_deref() {
+            Ok(Some(engine)) => engine.name(),
+            _ => self.command.clone(),
+        }
+    }
+}
+
+
+/// The PvE opponent playing on a thread of its own, so neither the window nor the clocks wait for
+/// its moves. Every move comes back to the window as an `ENGINE_MOVED` command.
+pub struct OpponentThread {
+    // the game, the turns played, the position and the clock of the side to move
+    jobs: Sender<(u64, usize, Board, Option<ClockState>)>,
+}
+
+impl OpponentThread {
+    fn start(opponent: Opponent, settings: Settings, sink: ExtEventSink) -> Self {
+        let (jobs, queue) = mpsc::channel::<(u64, usize, Board, Option<ClockState>)>();
+
+        thread::spawn(move || {
+            // made here, the player and the tree or table it keeps between moves stay on this thread
+            let player = opponent.player(&settings);
+
+            for (game, ply, board, clock) in queue {
+                player.borrow_mut().set_clock(clock);
+                let choice = player.borrow_mut().choose_move(&board);
+
+                // the window is gone
+                if sink.submit_command(Reversi::ENGINE_MOVED, (game, ply, Ok(choice)), Target::Auto).is_err() {
+                    break;
+                }
+            }
+        });
+
+        Self {
+            jobs,
+        }
+    }
+
+    fn request_move(&self, game: u64, ply: usize, board: Board, clock: Option<ClockState>) {
+        // the thread only stops once the window is gone, or once the opponent was dropped and
+        // nothing is left to answer
+        let _ = self.jobs.send((game, ply, board, clock));
+    }
+}
+
+
+// what the `Adviser` is asked about a position
+#[derive(Clone, Copy)]
+enum Advice {
+    Hint,
+    MoveScores,
+}
+
+/// Searches the hints the player asks for and the scores of the analysis overlay on a thread of
+/// its own with one searcher kept for the whole run, so the window doesn't wait for them. The
+/// answers come back to the window as `HINTED` and `SCORED` commands.
+pub struct Adviser {
+    // what is asked, the position, its key and the threads to search it with
+    jobs: Sender<(Advice, Board, u64, usize)>,
+    // key of the position on the board, requests for positions played past are skipped
+    position: Arc<AtomicU64>,
+}
+
+impl Adviser {
+    // the position's key and the hint for it
+    const HINTED: Selector<(u64, Option<Hint>)> = Selector::new("reversi.hinted");
+    // the position's key and the scores of its moves
+    const SCORED: Selector<(u64, MoveScores)> = Selector::new("reversi.scored");
+
+    fn start(sink: ExtEventSink) -> Self {
+        let (jobs, queue) = mpsc::channel::<(Advice, Board, u64, usize)>();
+        let position = Arc::new(AtomicU64::new(0));
+        let current = position.clone();
+
+        thread::spawn(move || {
+            let mut searcher = Searcher::new(1);
+
+            for (advice, board, key, threads) in queue {
+                if key != current.load(Ordering::Relaxed) {
+                    continue;
+                }
+
+                searcher.set_threads(threads);
+                let sent = match advice {
+                    Advice::Hint => {
+                        let hint = Hint::search(&mut searcher, &board);
+                        sink.submit_command(Self::HINTED, (key, hint), Target::Auto)
+                    },
+                    Advice::MoveScores => {
+                        let scores = MoveScores::search(&mut searcher, &board);
+                        sink.submit_command(Self::SCORED, (key, scores), Target::Auto)
+                    },
+                };
+
+                // the window is gone
+                if sent.is_err() {
+                    break;
+                }
+            }
+        });
+
+        Self {
+            jobs,
+            position,
+        }
+    }
+
+    fn new_position(&self, board: &Board) {
+        self.position.store(tt::hash(board), Ordering::Relaxed);
+    }
+
+    fn hint(&self, board: Board, threads: usize) {
+        self.ask(Advice::Hint, board, threads);
+    }
+
+    fn score_moves(&self, board: Board, threads: usize) {
+        self.ask(Advice::MoveScores, board, threads);
+    }
+
+    fn ask(&self, advice: Advice, board: Board, threads: usize) {
+        self.new_position(&board);
+        // the thread only stops once the window is gone, with nobody left to show the advice to
+        let _ = self.jobs.send((advice, board, tt::hash(&board), threads));
+    }
+}
+
+
+/// Assesses the positions of the games on a thread of its own with one searcher kept for the whole
+/// run, so neither the window nor the clocks wait for it. Every assessment comes back to the window
+/// as an `ASSESSED` command.
+pub struct Assessor {
+    // the game, the position's index in its evals, the position and the threads to search it with
+    jobs: Sender<(u64, usize, Board, usize)>,
+    // the game on the board, positions still queued for games before the last one are skipped
+    game: Arc<AtomicU64>,
+}
+
+impl Assessor {
+    // the game, the position's index in its evals and the assessment
+    const ASSESSED: Selector<(u64, usize, i32)> = Selector::new("reversi.assessed");
+
+    fn start(sink: ExtEventSink) -> Self {
+        let (jobs, queue) = mpsc::channel::<(u64, usize, Board, usize)>();
+        let game = Arc::new(AtomicU64::new(0));
+        let current = game.clone();
+
+        thread::spawn(move || {
+            let mut searcher = Searcher::new(1);
+
+            for (game, ply, board, threads) in queue {
+                if game + 1 < current.load(Ordering::Relaxed) {
+                    continue;
+                }
+
+                searcher.set_threads(threads);
+                let eval = assess(&mut searcher, &board);
+
+                // the window is gone
+                if sink.submit_command(Self::ASSESSED, (game, ply, eval), Target::Auto).is_err() {
+                    break;
+                }
+            }
+        });
+
+        Self {
+            jobs,
+            game,
+        }
+    }
+
+    fn new_game(&self, game: u64) {
+        self.game.store(game, Ordering::Relaxed);
+    }
+
+    fn queue(&self, game: u64, ply: usize, board: Board, threads: usize) {
+        // the thread only stops once the window is gone, with nothing left to assess for
+        let _ = self.jobs.send((game, ply, board, threads));
+    }
+}
+
+
+/// Engine assessment of the current position, Black's share of the bar grows from the bottom
+struct EvalBar;
+
+impl Widget<Reversi> for EvalBar {
+
+    fn event(&mut self, ctx: &mut EventCtx, event: &Event, data: &mut Reversi, env: &Env) {
+
+    }
+
+    fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle, data: &Reversi, env: &Env) {
+
+    }
+
+    fn update(&mut self, ctx: &mut UpdateCtx, old_data: &Reversi, data: &Reversi, env: &Env) {
+        if old_data.evals != data.evals || old_data.theme() != data.theme() {
+            ctx.request_paint();
+        }
+    }
+
+    fn layout(&mut self, ctx: &mut LayoutCtx, bc: &BoxConstraints, data: &Reversi, env: &Env) -> Size {
+        Size::new(Self::WIDTH, available(bc).height)
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, data: &Reversi, env: &Env) {
+        let Size { width, height } = ctx.size();
+        let eval = data.evals.last().copied().unwrap_or(0);
+
+        // a few discs ahead already fills most of the bar, a won game fills all of it
+        let black_share = 0.5 + 0.5 * (eval as f64 / DISC as f64 / Self::SCALE).tanh();
+        let split = height * (1. - black_share);
+
+        let theme = data.theme();
+        ctx.fill(Rect::new(0., 0., width, split), theme.eval_bar(PlayerTurn::White));
+        ctx.fill(Rect::new(0., split, width, height), theme.eval_bar(PlayerTurn::Black));
+        ctx.stroke(Line::new((0., height / 2.), (width, height / 2.)), &theme.chart, 1.);
+
+        // the number goes on the leading side's end of the bar, in the other side's color
+        let (clr, y) = if eval >= 0 { (theme.eval_bar(PlayerTurn::White), height - 16.) } else { (theme.eval_bar(PlayerTurn::Black), 4.) };
+
+        ctx.text().new_text_layout(
+            MoveScores::format(eval)
+        ).font(FontFamily::MONOSPACE, 9.)
+            .text_color(clr.clone())
+            .build()
+            .unwrap()
+            .draw(
+                Point::new(1., y),
+                ctx.render_ctx
+            );
+    }
+}
+
+impl EvalBar {
+    const WIDTH: f64 = 28.;
+    // discs per unit of tanh: a lead of 8 atanh(1/2), about 4.4 discs, fills three quarters of the bar
+    const SCALE: f64 = 8.;
+}
+
+
+/// Writes the exported analysis report to the file picked in the save dialog and takes in what
+/// the threads working for the window send back
+struct Delegate;
+
+impl Delegate {
+    const JSON: FileSpec = FileSpec::new("JSON", &["json"]);
+}
+
+impl AppDelegate<Reversi> for Delegate {
+
+    // keeps what was changed in the side panel as well, such as the names
+    fn window_removed(&mut self, id: WindowId, data: &mut Reversi, env: &Env, ctx: &mut DelegateCtx) {
+        data.save_settings();
+    }
+
+    fn command(&mut self, ctx: &mut DelegateCtx, target: Target, cmd: &Command, data: &mut Reversi, env: &Env) -> Handled {
+
+        if let Some(&(game, ply, eval)) = cmd.get(Assessor::ASSESSED) {
+            data.assessed(game, ply, eval);
+            return Handled::Yes;
+        }
+
+        if let Some(&(game, done, total)) = cmd.get(Reversi::ANALYSIS_PROGRESS) {
+            data.analysis_progress(game, done, total);
+            return Handled::Yes;
+        }
+
+        if let Some((game, analysis)) = cmd.get(Reversi::ANALYZED) {
+            data.analyzed(*game, analysis.clone());
+            return Handled::Yes;
+        }
+
+        if let Some((position, hint)) = cmd.get(Adviser::HINTED) {
+            data.hinted(*position, hint.clone());
+            return Handled::Yes;
+        }
+
+        if let Some((position, scores)) = cmd.get(Adviser::SCORED) {
+            data.scored(*position, scores.clone());
+            return Handled::Yes;
+        }
+
+        if let Some((game, ply, choice)) = cmd.get(Reversi::ENGINE_MOVED) {
+            data.engine_moved(*game, *ply, choice.clone());
+            return Handled::Yes;
+        }
+
+        if let Some(file_info) = cmd.get(commands::SAVE_FILE_AS) {
+            if let Some(analysis) = &data.analysis {
+                let path = file_info.path();
+                let contents = match path.extension().and_then(|ext| ext.to_str()) {
+                    Some("json") => report::json(analysis, data.outcome.as_ref()),
+                    _ => report::html(analysis, data.outcome.as_ref()),
+                };
+
+                if let Err(err) = std::fs::write(path, contents) {
+                    eprintln!("failed to export the analysis to {}: {}", path.display(), err);
+                }
+            }
+
+            return Handled::Yes;
+        }
+
+        Handled::No
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reversi::clock::Clock;
+    use reversi::notation::{board_string, parse_transcript};
+
+    fn saved() -> SavedGame {
+        let control = TimeControl::PRESETS[1];
+        let mut black = Clock::new(control);
+        black.tick(Duration::from_secs(7));
+        black.finish_move();
+
+        SavedGame {
+            mode: GameMode::PvE(Opponent::Mcts(20_000)),
+            black: "Alice".to_string(),
+            white: "mcts".to_string(),
+            engine_command: String::new(),
+            external_black: false,
+            external_white: false,
+            clocks: Some((black, Clock::new(control))),
+            history: parse_transcript("f5d6c3d3c4").unwrap(),
+        }
+    }
+
+    #[test]
+    fn a_resumed_game_saves_as_it_was_saved() {
+        let saved = saved();
+        let mut rev = Reversi::new();
+        rev.resume(saved.clone());
+
+        assert_eq!(board_string(&rev.board()), board_string(&saved.board()));
+        assert_eq!(SavedGame::from(&rev), saved);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn the_game_at_the_board_goes_through_json() {
+        let mut rev = Reversi::new();
+        rev.resume(saved());
+
+        let json = serde_json::to_string(&SavedGame::from(&rev)).unwrap();
+        let mut back = Reversi::new();
+        back.resume(serde_json::from_str(&json).unwrap());
+
+        assert_eq!(SavedGame::from(&back), SavedGame::from(&rev));
+        assert_eq!(back.history, rev.history);
+        assert_eq!(back.player_turn, rev.player_turn);
+    }
+}
@@ -0,0 +1,94 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::time::Duration;
+use crate::engine::greedy::Greedy;
+use crate::engine::mcts::{Mcts, MctsConfig};
+use crate::engine::search::{AlphaBeta, SearchLimits};
+use crate::engine::{Player, DEFAULT_SEED};
+use crate::settings::Settings;
+
+
+#[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "gui", derive(druid::Data))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum GameMode {
+    PvP,
+    PvE(Opponent),
+}
+
+impl GameMode {
+    /// the modes the front ends offer, with their labels
+    pub const PRESETS: [(&'static str, GameMode); 6] = [
+        ("PvP", GameMode::PvP),
+        ("PvE (1)", GameMode::PvE(Opponent::Greedy(0.2))),
+        ("PvE (2)", GameMode::PvE(Opponent::Greedy(0.4))),
+        ("PvE (3)", GameMode::PvE(Opponent::Greedy(0.6))),
+        ("PvE (MCTS)", GameMode::PvE(Opponent::Mcts(20_000))),
+        ("PvE (alpha-beta)", GameMode::PvE(Opponent::AlphaBeta(8))),
+    ];
+
+    pub fn label(&self) -> &'static str {
+        Self::PRESETS.iter()
+            .find(|(_, mode)| mode == self)
+            .map_or("PvE", |(label, _)| label)
+    }
+}
+
+/// Computer opponent of `GameMode::PvE`
+#[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "gui", derive(druid::Data))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum Opponent {
+    // greedy disc counter with the given error chance
+    Greedy(f64),
+    // Monte Carlo Tree Search with the given playout budget per move
+    Mcts(u32),
+    // alpha-beta search to the given depth
+    AlphaBeta(u8),
+}
+
+impl Opponent {
+    const ALPHA_BETA_TIME: Duration = Duration::from_secs(2);
+
+    pub const NAMES: [&'static str; 5] = ["greedy1", "greedy2", "greedy3", "mcts", "alphabeta"];
+
+    /// the opponents of the side panel by the names the terminal front ends use for them
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "greedy1" => Some(Opponent::Greedy(0.2)),
+            "greedy2" => Some(Opponent::Greedy(0.4)),
+            "greedy3" => Some(Opponent::Greedy(0.6)),
+            "mcts" => Some(Opponent::Mcts(20_000)),
+            "alphabeta" => Some(Opponent::AlphaBeta(8)),
+            _ => None,
+        }
+    }
+
+    /// the name `from_name` knows the opponent by, ratings are kept under it
+    pub fn name(&self) -> &'static str {
+        Self::NAMES.iter()
+            .find(|name| Self::from_name(name).as_ref() == Some(self))
+            .copied()
+            .unwrap_or(match self {
+                Opponent::Greedy(_) => "greedy",
+                Opponent::Mcts(_) => "mcts",
+                Opponent::AlphaBeta(_) => "alphabeta",
+            })
+    }
+
+    pub fn player(&self, settings: &Settings) -> Rc<RefCell<dyn Player>> {
+        match self {
+            Opponent::Greedy(error_chance) => Rc::new(RefCell::new(
+                Greedy::new(*error_chance, DEFAULT_SEED)
+            )),
+            Opponent::Mcts(iterations) => Rc::new(RefCell::new(
+                Mcts::new(MctsConfig { iterations: Some(*iterations), ..MctsConfig::default() })
+            )),
+            Opponent::AlphaBeta(depth) => Rc::new(RefCell::new(
+                AlphaBeta::new(SearchLimits { depth: *depth, time: Some(Self::ALPHA_BETA_TIME), soft_time: None }, settings.threads)
+            )),
+        }
+    }
+}
@@ -0,0 +1,220 @@
+use std::io::{self, BufRead, Write};
+use std::time::Instant;
+use crate::board::{move_name, parse_move, Board};
+use crate::engine::eval::DISC;
+use crate::engine::search::{SearchLimits, Searcher};
+use crate::notation::parse_ggf_position;
+
+
+/// Engine side of the NBoard protocol: a GUI sends commands line by line, the engine keeps the
+/// game position in step with them and answers on its output
+pub struct NBoardEngine {
+    board: Board,
+    depth: u8,
+    searcher: Searcher,
+}
+
+impl NBoardEngine {
+    pub const NAME: &'static str = "reversi-druid";
+    pub const DEFAULT_DEPTH: u8 = 8;
+
+    pub fn new(threads: usize) -> Self {
+        Self {
+            board: Board::new(),
+            depth: Self::DEFAULT_DEPTH,
+            searcher: Searcher::new(threads),
+        }
+    }
+
+    pub fn board(&self) -> &Board {
+        &self.board
+    }
+
+    /// reads commands until the input ends or the GUI sends "quit"
+    pub fn run(&mut self, input: impl BufRead, mut out: impl Write) -> io::Result<()> {
+        for line in input.lines() {
+            if !self.handle(&line?, &mut out)? {
+                break;
+            }
+            out.flush()?;
+        }
+
+        Ok(())
+    }
+
+    /// answers one command, false once the GUI asked the engine to quit. Commands this engine
+    /// doesn't know are ignored as the protocol asks.
+    pub fn handle(&mut self, line: &str, out: &mut impl Write) -> io::Result<bool> {
+        let line = line.trim();
+        let (command, args) = line.split_once(' ').unwrap_or((line, ""));
+
+        match command {
+            "nboard" => writeln!(out, "set myname {}", Self::NAME)?,
+            "set" => self.set(args.trim()),
+            "move" => self.play(args.trim()),
+            "hint" => self.hint(args.trim().parse().unwrap_or(1), out)?,
+            "go" => self.go(out)?,
+            "ping" => writeln!(out, "pong {}", args.trim())?,
+            // nothing is learned from finished games, the GUI still waits for the answer
+            "learn" => writeln!(out, "learned")?,
+            "quit" => return Ok(false),
+            _ => {},
+        }
+
+        Ok(true)
+    }
+
+    fn set(&mut self, args: &str) {
+        let (option, value) = args.split_once(' ').unwrap_or((args, ""));
+
+        match option {
+            "depth" => match value.trim().parse::<u8>() {
+                Ok(depth) => self.depth = depth.clamp(1, 60),
+                Err(_) => eprintln!("nboard: bad depth '{}'", value),
+            },
+            "game" => match parse_ggf_position(value) {
+                Ok(board) => {
+                    self.board = board;
+                    self.searcher.clear();
+                },
+                Err(err) => eprintln!("nboard: bad game: {}", err),
+            },
+            _ => {},
+        }
+    }
+
+    // a move the GUI made on the board, possibly with an evaluation and time behind slashes
+    fn play(&mut self, args: &str) {
+        let name = args.split('/').next().unwrap_or_default().trim();
+
+        if name.eq_ignore_ascii_case("PA") {
+            self.board.pass();
+            return;
+        }
+
+        match parse_move(name) {
+            Some((x, y)) if self.board.is_valid_cell(x, y) => {
+                self.board.play(x, y);
+            },
+            _ => eprintln!("nboard: illegal move '{}'", name),
+        }
+    }
+
+    fn limits(&self) -> SearchLimits {
+        SearchLimits {
+            depth: self.depth,
+            time: None,
+            soft_time: None,
+        }
+    }
+
+    fn go(&mut self, out: &mut impl Write) -> io::Result<()> {
+        writeln!(out, "status thinking")?;
+        out.flush()?;
+
+        let result = self.searcher.search(&self.board, &self.limits());
+        writeln!(out, "nodestats {} {:.3}", result.nodes, result.elapsed.as_secs_f64())?;
+
+        let name = result.best_move.map_or("PA".to_string(), |(x, y)| move_name(x, y).to_uppercase());
+        writeln!(out, "=== {}/{:.2}/{:.3}", name, result.score as f64 / DISC as f64, result.elapsed.as_secs_f64())?;
+        writeln!(out, "status")
+    }
+
+    // the `count` best moves with their principal variations, best first
+    fn hint(&mut self, count: usize, out: &mut impl Write) -> io::Result<()> {
+        writeln!(out, "status hinting")?;
+        out.flush()?;
+
+        let start = Instant::now();
+        let limits = self.limits();
+        let result = self.searcher.search(&self.board, &limits);
+
+        if let Some(best_move) = result.best_move {
+            let pv: String = result.pv.iter().map(|(x, y)| move_name(*x, *y).to_uppercase()).collect();
+            writeln!(out, "search {} {:.2} 0 {}", pv, result.score as f64 / DISC as f64, result.depth)?;
+
+            if count > 1 {
+                // each reply is one ply into the tree already
+                let reply_limits = SearchLimits { depth: limits.depth.saturating_sub(1).max(1), ..limits };
+                let mut others = self.searcher.score_moves(&self.board, &reply_limits);
+                others.retain(|(cell, _)| *cell != best_move);
+                others.sort_by_key(|(_, score)| -score);
+
+                for ((x, y), score) in others.into_iter().take(count - 1) {
+                    writeln!(out, "search {} {:.2} 0 {}", move_name(x, y).to_uppercase(), score as f64 / DISC as f64, result.depth)?;
+                }
+            }
+        }
+
+        writeln!(out, "nodestats {} {:.3}", result.nodes, start.elapsed().as_secs_f64())?;
+        writeln!(out, "status")
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notation::ggf_position;
+
+    // everything the engine answers to the commands, line by line
+    fn answers(engine: &mut NBoardEngine, commands: &str) -> Vec<String> {
+        let mut out = Vec::new();
+        engine.run(commands.as_bytes(), &mut out).unwrap();
+        String::from_utf8(out).unwrap().lines().map(String::from).collect()
+    }
+
+    #[test]
+    fn the_engine_introduces_itself_and_answers_pings() {
+        let lines = answers(&mut NBoardEngine::new(1), "nboard 2\nping 7\nlearn\n");
+        assert_eq!(lines, ["set myname reversi-druid", "pong 7", "learned"]);
+    }
+
+    #[test]
+    fn moves_and_games_set_the_position() {
+        let mut engine = NBoardEngine::new(1);
+        answers(&mut engine, "move f5/0.00/1.2\n");
+
+        let mut board = Board::new();
+        board.play(5, 4);
+        assert_eq!(*engine.board(), board);
+
+        // an illegal move leaves the position alone
+        answers(&mut engine, "move a1\n");
+        assert_eq!(*engine.board(), board);
+
+        board.play(5, 5);
+        answers(&mut engine, &format!("set game {}\n", ggf_position(&board)));
+        assert_eq!(*engine.board(), board);
+    }
+
+    #[test]
+    fn go_plays_a_legal_move() {
+        let mut engine = NBoardEngine::new(1);
+        let lines = answers(&mut engine, "set depth 2\ngo\n");
+
+        assert_eq!(lines.first().map(String::as_str), Some("status thinking"));
+        assert_eq!(lines.last().map(String::as_str), Some("status"));
+
+        let reply = lines.iter().find_map(|line| line.strip_prefix("=== ")).unwrap();
+        let (x, y) = parse_move(reply.split('/').next().unwrap()).unwrap();
+        assert!(engine.board().is_valid_cell(x, y));
+    }
+
+    #[test]
+    fn hint_lists_the_best_moves() {
+        let mut engine = NBoardEngine::new(1);
+        let lines = answers(&mut engine, "set depth 2\nhint 3\n");
+
+        let searches: Vec<&String> = lines.iter().filter(|line| line.starts_with("search ")).collect();
+        // the start position has four moves
+        assert_eq!(searches.len(), 3);
+        assert!(searches.iter().all(|line| line.ends_with(" 0 2")));
+    }
+
+    #[test]
+    fn quit_ends_the_session() {
+        let lines = answers(&mut NBoardEngine::new(1), "ping 1\nquit\nping 2\n");
+        assert_eq!(lines, ["pong 1"]);
+    }
+}
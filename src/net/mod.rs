@@ -0,0 +1,7 @@
+//! Networked games between two instances: one hosts the game and checks every move against
+//! the rules, the other joins it. Both speak the line based protocol of `protocol`, and so does
+//! the dedicated `server` with its lobby and spectators.
+
+pub mod protocol;
+pub mod server;
+pub mod session;
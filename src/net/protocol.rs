@@ -0,0 +1,416 @@
+use std::fmt;
+use std::io::{self, ErrorKind, Read, Write};
+use std::net::{Shutdown, TcpStream};
+use std::time::{Duration, Instant};
+use crate::board::{move_name, parse_move, PlayerTurn};
+use crate::outcome::{Outcome, Reason};
+
+
+/// Bumped whenever a message changes, peers of another version are turned away
+pub const VERSION: u32 = 5;
+
+pub const DEFAULT_PORT: u16 = 7341;
+
+/// a client pings this often, the host or server answers
+pub const PING_INTERVAL: Duration = Duration::from_secs(5);
+/// a peer not heard from for this long is gone, even when its end of the connection never closed
+pub const IDLE_TIMEOUT: Duration = Duration::from_secs(20);
+/// a new connection that hasn't said HELLO by then is closed
+pub const HELLO_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// "host:port" with `DEFAULT_PORT` when the address has no port
+pub fn with_default_port(address: &str) -> String {
+    if address.contains(':') { address.to_string() } else { format!("{}:{}", address, DEFAULT_PORT) }
+}
+
+/// secret for WELCOME that gets a player its seat back after a dropped connection
+pub fn new_token() -> String {
+    format!("{:016x}", rand::random::<u64>())
+}
+
+/// What a player can propose for the opponent to accept or decline
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum Offer {
+    Draw,
+    // the proposing side's last move and whatever followed it are taken back
+    Takeback,
+}
+
+impl Offer {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Offer::Draw => "draw",
+            Offer::Takeback => "takeback",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        [Offer::Draw, Offer::Takeback].into_iter().find(|offer| offer.name() == name)
+    }
+}
+
+/// One line of the protocol, the first word names the message
+#[derive(Clone, Debug, PartialEq)]
+pub enum Message {
+    // first line of a joining client, with the token of the seat it comes back to or "-":
+    // "HELLO 4 - Ann", "HELLO 4 5f0c2a9e17d3b864 Ann"
+    Hello { version: u32, token: Option<String>, name: String },
+    // the color the client plays, the token that reclaims its seat and its opponent, sent once
+    // a game starts: "WELCOME 4 white 5f0c2a9e17d3b864 Bob"
+    Welcome { version: u32, side: PlayerTurn, token: String, name: String },
+    // the whole game so far as a transcript, sent to players and spectators after every change:
+    // "STATE f5d6c3"
+    State { moves: String },
+    // a client's move for the host or server to check: "MOVE d3"
+    Move { x: usize, y: usize },
+    // a client wants an opponent, a host has already seated it
+    Seek,
+    // a client asks a server for its games
+    List,
+    // the number of GAME lines that follow: "GAMES 2"
+    Games(usize),
+    // one game of a server: "GAME 3 Ann vs Bob, 12 moves"
+    Game { id: u32, text: String },
+    // a client follows a game without playing: "WATCH 3"
+    Watch(u32),
+    // the server's answer to WATCH, STATE follows: "WATCHING 3 Ann vs Bob"
+    Watching { id: u32, text: String },
+    // a line for the chat, attributed by its sender: "CHAT Ann: good luck"
+    Chat(String),
+    // the sender gives up the game
+    Resign,
+    // "OFFER draw", "OFFER takeback"
+    Offer(Offer),
+    // answers to the opponent's offer: "ACCEPT draw", "DECLINE takeback"
+    Accept(Offer),
+    Decline(Offer),
+    // a game decided off the board, sent after STATE by whoever keeps the game:
+    // "RESULT white resign", "RESULT draw agreed"
+    Result(Outcome),
+    // anything for the player to read: "INFO waiting for an opponent"
+    Info(String),
+    // refused connection or rejected move: "ERROR not your turn"
+    Error(String),
+    // a client asks whether the host or server is still there, which answers PONG
+    Ping,
+    Pong,
+    // the peer leaves for good
+    Bye,
+}
+
+impl Message {
+    pub fn parse(line: &str) -> Result<Self, String> {
+        let line = line.trim();
+        let (word, rest) = line.split_once(' ').unwrap_or((line, ""));
+        let rest = rest.trim();
+
+        match word {
+            "HELLO" => {
+                let mut parts = rest.splitn(3, ' ');
+                let version = parse_version(parts.next().unwrap_or(""))?;
+                let token = match parts.next() {
+                    Some("-") => None,
+                    Some(token) if !token.is_empty() => Some(token.to_string()),
+                    _ => return Err(format!("no token in '{}'", line)),
+                };
+                Ok(Message::Hello { version, token, name: parts.next().unwrap_or("").trim().to_string() })
+            },
+            "WELCOME" => {
+                let mut parts = rest.splitn(4, ' ');
+                let version = parse_version(parts.next().unwrap_or(""))?;
+                let side = match parts.next() {
+                    Some("black") => PlayerTurn::Black,
+                    Some("white") => PlayerTurn::White,
+                    _ => return Err(format!("no color in '{}'", line)),
+                };
+                let token = parts.next().filter(|token| !token.is_empty()).ok_or_else(|| format!("no token in '{}'", line))?;
+                Ok(Message::Welcome { version, side, token: token.to_string(), name: parts.next().unwrap_or("").trim().to_string() })
+            },
+            "STATE" => Ok(Message::State { moves: rest.to_string() }),
+            "MOVE" => parse_move(rest)
+                .map(|(x, y)| Message::Move { x, y })
+                .ok_or_else(|| format!("no square in '{}'", line)),
+            "SEEK" => Ok(Message::Seek),
+            "LIST" => Ok(Message::List),
+            "GAMES" => rest.parse()
+                .map(Message::Games)
+                .map_err(|_| format!("no count in '{}'", line)),
+            "GAME" | "WATCHING" => {
+                let (id, text) = rest.split_once(' ').unwrap_or((rest, ""));
+                let id = parse_id(id)?;
+                let text = text.trim().to_string();
+                Ok(if word == "GAME" { Message::Game { id, text } } else { Message::Watching { id, text } })
+            },
+            "WATCH" => Ok(Message::Watch(parse_id(rest)?)),
+            "CHAT" => Ok(Message::Chat(rest.to_string())),
+            "RESIGN" => Ok(Message::Resign),
+            "OFFER" | "ACCEPT" | "DECLINE" => {
+                let offer = Offer::from_name(rest).ok_or_else(|| format!("no offer in '{}'", line))?;
+                Ok(match word {
+                    "OFFER" => Message::Offer(offer),
+                    "ACCEPT" => Message::Accept(offer),
+                    _ => Message::Decline(offer),
+                })
+            },
+            "RESULT" => {
+                let (winner, reason) = rest.split_once(' ').unwrap_or((rest, ""));
+                let winner = match winner {
+                    "black" => Some(PlayerTurn::Black),
+                    "white" => Some(PlayerTurn::White),
+                    "draw" => None,
+                    _ => return Err(format!("no winner in '{}'", line)),
+                };
+                let reason = Reason::from_name(reason.trim()).ok_or_else(|| format!("no reason in '{}'", line))?;
+                Ok(Message::Result(Outcome { winner, reason }))
+            },
+            "INFO" => Ok(Message::Info(rest.to_string())),
+            "ERROR" => Ok(Message::Error(rest.to_string())),
+            "PING" => Ok(Message::Ping),
+            "PONG" => Ok(Message::Pong),
+            "BYE" => Ok(Message::Bye),
+            _ => Err(format!("unknown message '{}'", line)),
+        }
+    }
+}
+
+fn parse_version(text: &str) -> Result<u32, String> {
+    text.parse().map_err(|_| format!("no protocol version in '{}'", text))
+}
+
+fn parse_id(text: &str) -> Result<u32, String> {
+    text.parse().map_err(|_| format!("no game number in '{}'", text))
+}
+
+fn side_name(side: PlayerTurn) -> &'static str {
+    match side {
+        PlayerTurn::Black => "black",
+        PlayerTurn::White => "white",
+    }
+}
+
+// names and texts must stay on their line
+fn one_line(text: &str) -> String {
+    text.replace(['\n', '\r'], " ")
+}
+
+impl fmt::Display for Message {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Message::Hello { version, token, name } => write!(f, "HELLO {} {} {}", version, token.as_deref().unwrap_or("-"), one_line(name)),
+            Message::Welcome { version, side, token, name } => write!(f, "WELCOME {} {} {} {}", version, side_name(*side), token, one_line(name)),
+            Message::State { moves } => write!(f, "STATE {}", moves),
+            Message::Move { x, y } => write!(f, "MOVE {}", move_name(*x, *y)),
+            Message::Seek => write!(f, "SEEK"),
+            Message::List => write!(f, "LIST"),
+            Message::Games(count) => write!(f, "GAMES {}", count),
+            Message::Game { id, text } => write!(f, "GAME {} {}", id, one_line(text)),
+            Message::Watch(id) => write!(f, "WATCH {}", id),
+            Message::Watching { id, text } => write!(f, "WATCHING {} {}", id, one_line(text)),
+            Message::Chat(text) => write!(f, "CHAT {}", one_line(text)),
+            Message::Resign => write!(f, "RESIGN"),
+            Message::Offer(offer) => write!(f, "OFFER {}", offer.name()),
+            Message::Accept(offer) => write!(f, "ACCEPT {}", offer.name()),
+            Message::Decline(offer) => write!(f, "DECLINE {}", offer.name()),
+            Message::Result(outcome) => {
+                let winner = outcome.winner.map_or("draw", side_name);
+                write!(f, "RESULT {} {}", winner, outcome.reason.name())
+            },
+            Message::Info(text) => write!(f, "INFO {}", one_line(text)),
+            Message::Error(text) => write!(f, "ERROR {}", one_line(text)),
+            Message::Ping => write!(f, "PING"),
+            Message::Pong => write!(f, "PONG"),
+            Message::Bye => write!(f, "BYE"),
+        }
+    }
+}
+
+/// Non-blocking TCP stream that reads and writes whole messages. What the socket doesn't take
+/// at once is queued for `flush`, and a peer that lets either side pile up is cut off.
+pub struct Connection {
+    stream: TcpStream,
+    // bytes of a line that hasn't been completed yet
+    buffer: Vec<u8>,
+    // bytes sent but not yet taken by the socket
+    outgoing: Vec<u8>,
+    // the peer closed its end, what it sent before is still handed out
+    closed: bool,
+    opened: Instant,
+    // when the peer last sent anything
+    heard: Instant,
+}
+
+impl Connection {
+    // longest line a peer may send, far above any message
+    const MAX_LINE: usize = 4 * 1024;
+    // most bytes read in one `receive`, the rest waits in the socket for the next one
+    const MAX_READ: usize = 64 * 1024;
+    // most bytes waiting for a peer that doesn't read
+    const MAX_OUTGOING: usize = 256 * 1024;
+
+    pub fn new(stream: TcpStream) -> io::Result<Self> {
+        stream.set_nonblocking(true)?;
+        stream.set_nodelay(true)?;
+
+        Ok(Self {
+            stream,
+            buffer: Vec::new(),
+            outgoing: Vec::new(),
+            closed: false,
+            opened: Instant::now(),
+            heard: Instant::now(),
+        })
+    }
+
+    /// how long the connection has been open
+    pub fn age(&self) -> Duration {
+        self.opened.elapsed()
+    }
+
+    /// how long the peer hasn't sent anything, see `IDLE_TIMEOUT`
+    pub fn silent_for(&self) -> Duration {
+        self.heard.elapsed()
+    }
+
+    /// ends the connection for both ends, the peer reads the end of it
+    pub fn close(&self) {
+        let _ = self.stream.shutdown(Shutdown::Both);
+    }
+
+    /// queues the message and writes what the socket takes. Fails when the peer stopped reading
+    /// and too much is waiting for it.
+    pub fn send(&mut self, message: &Message) -> io::Result<()> {
+        self.outgoing.extend_from_slice(format!("{}\n", message).as_bytes());
+        self.flush()
+    }
+
+    /// writes queued bytes until the socket is full, never waits for it
+    pub fn flush(&mut self) -> io::Result<()> {
+        let mut written = 0;
+
+        while written < self.outgoing.len() {
+            match self.stream.write(&self.outgoing[written..]) {
+                Ok(0) => return Err(ErrorKind::WriteZero.into()),
+                Ok(count) => written += count,
+                Err(err) if err.kind() == ErrorKind::WouldBlock => break,
+                Err(err) if err.kind() == ErrorKind::Interrupted => {},
+                Err(err) => return Err(err),
+            }
+        }
+
+        self.outgoing.drain(..written);
+
+        if self.outgoing.len() > Self::MAX_OUTGOING {
+            return Err(io::Error::other("the peer doesn't read what it is sent"));
+        }
+
+        Ok(())
+    }
+
+    /// every message that arrived since the last call, lines that don't parse come back as
+    /// errors. Fails once the peer has closed the connection or sent a line that is too long.
+    pub fn receive(&mut self) -> io::Result<Vec<Result<Message, String>>> {
+        let mut chunk = [0_u8; 1024];
+        let mut read_now = 0;
+
+        while !self.closed && read_now < Self::MAX_READ {
+            match self.stream.read(&mut chunk) {
+                Ok(0) => self.closed = true,
+                Ok(read) => {
+                    self.buffer.extend_from_slice(&chunk[..read]);
+                    self.heard = Instant::now();
+                    read_now += read;
+                },
+                Err(err) if err.kind() == ErrorKind::WouldBlock => break,
+                Err(err) if err.kind() == ErrorKind::Interrupted => {},
+                Err(err) => return Err(err),
+            }
+        }
+
+        let mut messages = Vec::new();
+
+        while let Some(end) = self.buffer.iter().position(|byte| *byte == b'\n') {
+            let line: Vec<u8> = self.buffer.drain(..=end).collect();
+            let line = String::from_utf8_lossy(&line);
+
+            if !line.trim().is_empty() {
+                messages.push(Message::parse(&line));
+            }
+        }
+
+        if self.buffer.len() > Self::MAX_LINE {
+            return Err(io::Error::new(ErrorKind::InvalidData, "the peer sent a line that is too long"));
+        }
+
+        if messages.is_empty() && self.closed {
+            return Err(io::Error::new(ErrorKind::UnexpectedEof, "connection closed"));
+        }
+
+        Ok(messages)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn messages_read_back_from_their_lines() {
+        let messages = [
+            Message::Hello { version: VERSION, token: None, name: "Ann Smith".to_string() },
+            Message::Hello { version: VERSION, token: Some("5f0c2a9e17d3b864".to_string()), name: "Ann".to_string() },
+            Message::Welcome { version: VERSION, side: PlayerTurn::White, token: "5f0c2a9e17d3b864".to_string(), name: "Bob".to_string() },
+            Message::State { moves: "f5d6c3".to_string() },
+            Message::State { moves: String::new() },
+            Message::Move { x: 3, y: 2 },
+            Message::Seek,
+            Message::List,
+            Message::Games(2),
+            Message::Game { id: 3, text: "Ann vs Bob, 12 moves".to_string() },
+            Message::Watch(3),
+            Message::Watching { id: 3, text: "Ann vs Bob, 12 moves".to_string() },
+            Message::Chat("Ann: good luck".to_string()),
+            Message::Resign,
+            Message::Offer(Offer::Draw),
+            Message::Accept(Offer::Takeback),
+            Message::Decline(Offer::Draw),
+            Message::Result(Outcome::resigned(PlayerTurn::Black)),
+            Message::Result(Outcome::agreed_draw()),
+            Message::Info("waiting for an opponent".to_string()),
+            Message::Error("not your turn".to_string()),
+            Message::Ping,
+            Message::Pong,
+            Message::Bye,
+        ];
+
+        for message in messages {
+            let line = message.to_string();
+            assert_eq!(Message::parse(&line), Ok(message), "{}", line);
+        }
+    }
+
+    #[test]
+    fn lines_as_they_are_sent() {
+        assert_eq!(Message::Move { x: 3, y: 2 }.to_string(), "MOVE d3");
+        assert_eq!(Message::Hello { version: 5, token: None, name: "Ann".to_string() }.to_string(), "HELLO 5 - Ann");
+        assert_eq!(Message::Result(Outcome::on_time(PlayerTurn::Black)).to_string(), "RESULT white time");
+        // a line break would start another message
+        assert_eq!(Message::Chat("one\ntwo".to_string()).to_string(), "CHAT one two");
+    }
+
+    #[test]
+    fn broken_lines_are_errors() {
+        for line in ["", "HELLO", "HELLO x Ann", "WELCOME 4 red - Bob", "WELCOME 4 white", "MOVE z9", "GAMES many",
+                     "WATCH", "OFFER tea", "RESULT grey time", "RESULT black bored", "SHOUT hi"] {
+            assert!(Message::parse(line).is_err(), "'{}' reads", line);
+        }
+    }
+
+    #[test]
+    fn the_default_port_goes_where_there_is_none() {
+        assert_eq!(with_default_port("example.org"), format!("example.org:{}", DEFAULT_PORT));
+        assert_eq!(with_default_port("127.0.0.1:9000"), "127.0.0.1:9000");
+    }
+}
@@ -0,0 +1,513 @@
+use std::fs::OpenOptions;
+use std::io::{self, ErrorKind, Write};
+use std::net::TcpListener;
+use std::path::PathBuf;
+use crate::board::PlayerTurn;
+use crate::game::Game;
+use crate::mode::GameMode;
+use crate::net::protocol::{new_token, Connection, Message, Offer, HELLO_TIMEOUT, IDLE_TIMEOUT, VERSION};
+use crate::notation::{ggf_with_outcome, transcript};
+use crate::settings::Settings;
+
+
+/// What a client is doing on the server
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Activity {
+    // said HELLO, hasn't asked for anything yet
+    Idle,
+    // waits to be paired
+    Seeking,
+    // seated in the game with this id
+    Playing(u32),
+    Watching(u32),
+}
+
+struct Client {
+    connection: Connection,
+    // None until HELLO
+    name: Option<String>,
+    activity: Activity,
+}
+
+struct ServerGame {
+    id: u32,
+    // players by name, a seat stays theirs while they are away
+    black: String,
+    white: String,
+    // Black's and White's, a player coming back needs its token to get the seat
+    tokens: [String; 2],
+    game: Game,
+    // an offer of this side waiting for the opponent's answer
+    offer: Option<(PlayerTurn, Offer)>,
+}
+
+impl ServerGame {
+    fn describe(&self) -> String {
+        format!("{} vs {}, {} moves", self.black, self.white, self.game.history.len())
+    }
+
+    fn player(&self, side: PlayerTurn) -> &str {
+        match side {
+            PlayerTurn::Black => &self.black,
+            PlayerTurn::White => &self.white,
+        }
+    }
+
+    fn token(&self, side: PlayerTurn) -> &str {
+        &self.tokens[if side == PlayerTurn::Black { 0 } else { 1 }]
+    }
+}
+
+/// Game server for any number of clients: seekers are paired two by two into games the server
+/// keeps, spectators follow a game of their choice and every finished game is appended to the
+/// GGF archive. Speaks the protocol of `Session`, so its clients are the app and `reversi-cli`.
+pub struct Server {
+    listener: TcpListener,
+    archive: Option<PathBuf>,
+    clients: Vec<Client>,
+    games: Vec<ServerGame>,
+    next_id: u32,
+}
+
+impl Server {
+    pub fn bind(port: u16, archive: Option<PathBuf>) -> io::Result<Self> {
+        let listener = TcpListener::bind(("0.0.0.0", port))?;
+        listener.set_nonblocking(true)?;
+
+        Ok(Self {
+            listener,
+            archive,
+            clients: Vec::new(),
+            games: Vec::new(),
+            next_id: 1,
+        })
+    }
+
+    pub fn port(&self) -> u16 {
+        self.listener.local_addr().map_or(0, |address| address.port())
+    }
+
+    /// accepts new clients and handles everything they sent, returns lines for the server log
+    pub fn poll(&mut self) -> Vec<String> {
+        let mut log = Vec::new();
+
+        loop {
+            match self.listener.accept() {
+                Ok((stream, address)) => match Connection::new(stream) {
+                    Ok(connection) => {
+                        log.push(format!("{} connected", address));
+                        self.clients.push(Client { connection, name: None, activity: Activity::Idle });
+                    },
+                    Err(err) => log.push(format!("{}: {}", address, err)),
+                },
+                Err(err) if err.kind() == ErrorKind::WouldBlock => break,
+                Err(err) => {
+                    log.push(err.to_string());
+                    break;
+                },
+            }
+        }
+
+        let mut idx = 0;
+
+        while idx < self.clients.len() {
+            let received = self.clients[idx].connection.receive();
+            let mut stays = received.is_ok() && !self.is_stale(idx);
+
+            for message in received.unwrap_or_default() {
+                match message {
+                    Ok(Message::Bye) => {
+                        stays = false;
+                        break;
+                    },
+                    Ok(message) => self.handle(idx, message, &mut log),
+                    Err(err) => self.send(idx, &Message::Error(err)),
+                }
+            }
+
+            if stays {
+                idx += 1;
+            } else {
+                self.drop_client(idx, &mut log);
+            }
+        }
+
+        // after handling everything, so each client gets what the others' messages brought it.
+        // A client that doesn't read what it is sent is dropped once too much waits for it.
+        let mut idx = 0;
+
+        while idx < self.clients.len() {
+            match self.clients[idx].connection.flush() {
+                Ok(()) => idx += 1,
+                Err(err) => {
+                    log.push(format!("{}: {}", self.clients[idx].name.as_deref().unwrap_or("a client"), err));
+                    self.drop_client(idx, &mut log);
+                },
+            }
+        }
+
+        log
+    }
+
+    fn handle(&mut self, idx: usize, message: Message, log: &mut Vec<String>) {
+        if self.clients[idx].name.is_none() && !matches!(message, Message::Hello { .. }) {
+            self.send(idx, &Message::Error("HELLO comes first".to_string()));
+            return;
+        }
+
+        match message {
+            Message::Hello { version, token, name } => self.hello(idx, version, token, name, log),
+            Message::Seek => self.seek(idx, log),
+            Message::List => {
+                self.send(idx, &Message::Games(self.games.len()));
+                for listed in 0..self.games.len() {
+                    let game = &self.games[listed];
+                    let message = Message::Game { id: game.id, text: game.describe() };
+                    self.send(idx, &message);
+                }
+            },
+            Message::Watch(id) => match self.games.iter().find(|game| game.id == id) {
+                Some(game) => {
+                    let watching = Message::Watching { id, text: game.describe() };
+                    let state = Message::State { moves: transcript(&game.game.history) };
+                    self.clients[idx].activity = Activity::Watching(id);
+                    self.send(idx, &watching);
+                    self.send(idx, &state);
+                },
+                None => self.send(idx, &Message::Error(format!("no game {}", id))),
+            },
+            Message::Move { x, y } => self.play(idx, x, y, log),
+            Message::Chat(text) => self.chat(idx, text),
+            Message::Resign => {
+                if let Some((pos, side)) = self.seat_of(idx) {
+                    self.games[pos].game.resign(side);
+                    self.conclude(pos, log);
+                }
+            },
+            Message::Offer(offer) => self.offer(idx, offer),
+            Message::Accept(offer) => self.answer(idx, offer, true, log),
+            Message::Decline(offer) => self.answer(idx, offer, false, log),
+            Message::Ping => self.send(idx, &Message::Pong),
+            Message::Info(_) | Message::Error(_) | Message::Pong => {},
+            message => self.send(idx, &Message::Error(format!("unexpected {}", message))),
+        }
+    }
+
+    fn hello(&mut self, idx: usize, version: u32, token: Option<String>, name: String, log: &mut Vec<String>) {
+        if self.clients[idx].name.is_some() {
+            self.send(idx, &Message::Error("HELLO was said already".to_string()));
+            return;
+        }
+
+        if version != VERSION {
+            self.send(idx, &Message::Error(format!("protocol version {} is needed, not {}", VERSION, version)));
+            return;
+        }
+
+        let name = if name.is_empty() { format!("guest {}", idx + 1) } else { name };
+
+        // back to a seat it left, which only the token the seat was given with reclaims
+        let seat = self.games.iter().find_map(|game| {
+            [PlayerTurn::Black, PlayerTurn::White].into_iter()
+                .find(|side| game.player(*side) == name)
+                .map(|side| (game.id, side, game.token(side) == token.as_deref().unwrap_or("")))
+        });
+
+        if let Some(other) = self.clients.iter().position(|client| client.name.as_deref() == Some(name.as_str())) {
+            if !matches!(seat, Some((_, _, true))) {
+                self.send(idx, &Message::Error(format!("{} is here already", name)));
+                return;
+            }
+
+            // the player is back over a new connection before the old one was noticed to drop,
+            // the old one leaves with the next poll without taking the seat along
+            self.clients[other].name = None;
+            self.clients[other].activity = Activity::Idle;
+            self.clients[other].connection.close();
+        }
+
+        if let Some((id, _, false)) = seat {
+            self.send(idx, &Message::Error(format!("{} is seated in game {}, coming back needs its token", name, id)));
+            return;
+        }
+
+        log.push(format!("{} said hello", name));
+        self.clients[idx].name = Some(name.clone());
+
+        if let Some((id, side, _)) = seat {
+            log.push(format!("{} is back in game {}", name, id));
+            self.seat(idx, id, side);
+            self.notify_opponent(id, side, &format!("{} is back", name));
+        }
+    }
+
+    fn seek(&mut self, idx: usize, log: &mut Vec<String>) {
+        if matches!(self.clients[idx].activity, Activity::Playing(_) | Activity::Seeking) {
+            return;
+        }
+
+        let opponent = self.clients.iter().position(|client| client.activity == Activity::Seeking);
+
+        let Some(opponent) = opponent else {
+            self.clients[idx].activity = Activity::Seeking;
+            self.send(idx, &Message::Info("waiting for an opponent".to_string()));
+            return;
+        };
+
+        // whoever waited longer plays Black
+        let id = self.next_id;
+        self.next_id += 1;
+
+        let game = ServerGame {
+            id,
+            black: self.clients[opponent].name.clone().unwrap_or_default(),
+            white: self.clients[idx].name.clone().unwrap_or_default(),
+            tokens: [new_token(), new_token()],
+            game: Game::new(GameMode::PvP, &Settings::default()),
+            offer: None,
+        };
+        log.push(format!("game {}: {}", id, game.describe()));
+        self.games.push(game);
+
+        self.seat(opponent, id, PlayerTurn::Black);
+        self.seat(idx, id, PlayerTurn::White);
+    }
+
+    // tells the client its color and opponent and sends it the game
+    fn seat(&mut self, idx: usize, id: u32, side: PlayerTurn) {
+        let Some(game) = self.games.iter().find(|game| game.id == id) else {
+            return;
+        };
+
+        let welcome = Message::Welcome {
+            version: VERSION,
+            side,
+            token: game.token(side).to_string(),
+            name: game.player(side.opposite()).to_string(),
+        };
+        let state = Message::State { moves: transcript(&game.game.history) };
+
+        self.clients[idx].activity = Activity::Playing(id);
+        self.send(idx, &welcome);
+        self.send(idx, &state);
+    }
+
+    fn play(&mut self, idx: usize, x: usize, y: usize, log: &mut Vec<String>) {
+        let Activity::Playing(id) = self.clients[idx].activity else {
+            self.send(idx, &Message::Error("not in a game".to_string()));
+            return;
+        };
+        let Some(pos) = self.games.iter().position(|game| game.id == id) else {
+            return;
+        };
+
+        let name = self.clients[idx].name.clone().unwrap_or_default();
+        let game = &mut self.games[pos];
+        let side = game.game.board.player_turn;
+
+        let error = if game.player(side) != name {
+            Some("not your turn")
+        } else if !game.game.play(x, y) {
+            Some("no legal move")
+        } else {
+            None
+        };
+
+        if let Some(error) = error {
+            self.send(idx, &Message::Error(error.to_string()));
+            // a rejected move leaves the client in sync too
+            let state = Message::State { moves: transcript(&self.games[pos].game.history) };
+            self.send(idx, &state);
+            return;
+        }
+
+        self.games[pos].offer = None;
+        self.conclude(pos, log);
+    }
+
+    // the game and color of a seated client, None after telling the client it doesn't play
+    fn seat_of(&mut self, idx: usize) -> Option<(usize, PlayerTurn)> {
+        let name = self.clients[idx].name.clone().unwrap_or_default();
+        let seat = match self.clients[idx].activity {
+            Activity::Playing(id) => self.games.iter().position(|game| game.id == id).map(|pos| {
+                let side = if self.games[pos].black == name { PlayerTurn::Black } else { PlayerTurn::White };
+                (pos, side)
+            }),
+            _ => None,
+        };
+
+        if seat.is_none() {
+            self.send(idx, &Message::Error("not in a game".to_string()));
+        }
+        seat
+    }
+
+    // a chat line goes to everyone else at the game, players and spectators alike
+    fn chat(&mut self, idx: usize, text: String) {
+        let (Activity::Playing(id) | Activity::Watching(id)) = self.clients[idx].activity else {
+            self.send(idx, &Message::Error("not in a game".to_string()));
+            return;
+        };
+
+        let message = Message::Chat(text);
+        for client in 0..self.clients.len() {
+            if client != idx && matches!(self.clients[client].activity, Activity::Playing(game) | Activity::Watching(game) if game == id) {
+                self.send(client, &message);
+            }
+        }
+    }
+
+    fn offer(&mut self, idx: usize, offer: Offer) {
+        let Some((pos, side)) = self.seat_of(idx) else {
+            return;
+        };
+        let game = &mut self.games[pos];
+
+        if offer == Offer::Takeback && !game.game.history.moves.iter().any(|played| played.side == side && played.cell.is_some()) {
+            self.send(idx, &Message::Error("no move to take back".to_string()));
+            return;
+        }
+
+        game.offer = Some((side, offer));
+        let id = game.id;
+        self.send_player(id, side.opposite(), &Message::Offer(offer));
+    }
+
+    // the opponent's answer goes back to whoever offered, the server settles an accepted offer
+    fn answer(&mut self, idx: usize, offer: Offer, accept: bool, log: &mut Vec<String>) {
+        let Some((pos, side)) = self.seat_of(idx) else {
+            return;
+        };
+        let game = &mut self.games[pos];
+
+        if game.offer != Some((side.opposite(), offer)) {
+            self.send(idx, &Message::Error(format!("no {} was offered", offer.name())));
+            return;
+        }
+
+        game.offer = None;
+        let id = game.id;
+        let reply = if accept { Message::Accept(offer) } else { Message::Decline(offer) };
+        self.send_player(id, side.opposite(), &reply);
+
+        if !accept {
+            return;
+        }
+
+        match offer {
+            Offer::Draw => self.games[pos].game.agree_draw(),
+            Offer::Takeback => {
+                self.games[pos].game.take_back(side.opposite());
+            },
+        }
+        self.conclude(pos, log);
+    }
+
+    // sends the changed game around, a finished one is archived and its clients set free
+    fn conclude(&mut self, pos: usize, log: &mut Vec<String>) {
+        let id = self.games[pos].id;
+        self.broadcast(id);
+
+        if let Some(outcome) = self.games[pos].game.outcome() {
+            let finished = self.games.remove(pos);
+            log.push(format!("game {} over: {} ({})", id, outcome.describe(), finished.describe()));
+
+            if let Err(err) = self.save(&finished) {
+                log.push(format!("failed to archive game {}: {}", id, err));
+            }
+
+            for client in 0..self.clients.len() {
+                if matches!(self.clients[client].activity, Activity::Playing(game) | Activity::Watching(game) if game == id) {
+                    self.clients[client].activity = Activity::Idle;
+                    self.send(client, &Message::Info(format!("game over: {}", outcome.describe())));
+                }
+            }
+        }
+    }
+
+    // the game to its players and spectators
+    fn broadcast(&mut self, id: u32) {
+        let Some(game) = self.games.iter().find(|game| game.id == id) else {
+            return;
+        };
+        let state = Message::State { moves: transcript(&game.game.history) };
+        let result = game.game.decided.map(Message::Result);
+
+        for client in 0..self.clients.len() {
+            if matches!(self.clients[client].activity, Activity::Playing(game) | Activity::Watching(game) if game == id) {
+                self.send(client, &state);
+                if let Some(result) = &result {
+                    self.send(client, result);
+                }
+            }
+        }
+    }
+
+    fn notify_opponent(&mut self, id: u32, side: PlayerTurn, text: &str) {
+        self.send_player(id, side.opposite(), &Message::Info(text.to_string()));
+    }
+
+    // to the player of the side when it is connected
+    fn send_player(&mut self, id: u32, side: PlayerTurn, message: &Message) {
+        let Some(player) = self.games.iter()
+            .find(|game| game.id == id)
+            .map(|game| game.player(side).to_string()) else {
+            return;
+        };
+
+        if let Some(client) = self.clients.iter().position(|client| client.name.as_deref() == Some(player.as_str())) {
+            self.send(client, message);
+        }
+    }
+
+    // a client that went quiet, or never said HELLO, is dropped however its connection looks
+    fn is_stale(&self, idx: usize) -> bool {
+        let client = &self.clients[idx];
+        client.connection.silent_for() > IDLE_TIMEOUT || (client.name.is_none() && client.connection.age() > HELLO_TIMEOUT)
+    }
+
+    // a player's seat is kept for it to come back, a game both players left is given up
+    fn drop_client(&mut self, idx: usize, log: &mut Vec<String>) {
+        let client = self.clients.remove(idx);
+        let name = client.name.unwrap_or_default();
+        log.push(format!("{} left", if name.is_empty() { "a client" } else { &name }));
+
+        let Activity::Playing(id) = client.activity else {
+            return;
+        };
+        let Some(game) = self.games.iter().find(|game| game.id == id) else {
+            return;
+        };
+
+        let side = if game.black == name { PlayerTurn::Black } else { PlayerTurn::White };
+        let opponent = game.player(side.opposite()).to_string();
+
+        if self.clients.iter().any(|client| client.name.as_deref() == Some(opponent.as_str())) {
+            self.notify_opponent(id, side, &format!("{} left, the seat is kept", name));
+        } else {
+            log.push(format!("game {} abandoned", id));
+            self.games.retain(|game| game.id != id);
+
+            for client in &mut self.clients {
+                if client.activity == Activity::Watching(id) {
+                    client.activity = Activity::Idle;
+                }
+            }
+        }
+    }
+
+    // appends the game to the archive, a line per game
+    fn save(&self, game: &ServerGame) -> io::Result<()> {
+        let Some(path) = &self.archive else {
+            return Ok(());
+        };
+
+        let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+        writeln!(file, "{}", ggf_with_outcome(&game.game.history, &game.black, &game.white, game.game.decided.as_ref()))
+    }
+
+    // a client whose connection failed or whose queue overflowed is dropped with the next poll
+    fn send(&mut self, idx: usize, message: &Message) {
+        let _ = self.clients[idx].connection.send(message);
+    }
+}
@@ -0,0 +1,648 @@
+use std::io::{self, ErrorKind};
+use std::net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::thread;
+use std::time::{Duration, Instant};
+use crate::board::PlayerTurn;
+use crate::game::Game;
+use crate::mode::GameMode;
+use crate::net::protocol::{new_token, with_default_port, Connection, Message, Offer, HELLO_TIMEOUT, IDLE_TIMEOUT, PING_INTERVAL, VERSION};
+use crate::notation::{parse_transcript, transcript};
+use crate::settings::Settings;
+
+
+/// What a poll of the session brought
+#[derive(Clone, Debug, PartialEq)]
+pub enum NetEvent {
+    // the opponent (re)joined, under this name
+    Joined(String),
+    // the game changed, replay it from `Session::game`
+    Updated,
+    // the connection was lost
+    Left,
+    // the peer refused a move or the connection
+    Rejected(String),
+    // a notice from the peer
+    Info(String),
+    // a chat line, also kept in `Session::chat`
+    Chat(String),
+    // the opponent proposes a draw or a takeback, `Session::answer` replies
+    Offered(Offer),
+    // the opponent accepted (true) or declined our offer
+    Answered(Offer, bool),
+}
+
+enum Role {
+    // waits for the client, checks its moves and keeps the game
+    Host { listener: TcpListener, port: u16 },
+    // plays the moves the host or server sends, or only follows the game with `watch`,
+    // reconnects when the connection drops
+    Join { address: SocketAddr, retry_at: Instant, closed: bool, watch: Option<u32> },
+}
+
+/// One side of a game over TCP. The host plays Black and owns the game: a client's moves are
+/// only played once the host accepted them and sent the game back. A client can join a
+/// `reversi-server` the same way, the server pairs it with another one.
+pub struct Session {
+    pub game: Game,
+    // color played at this end
+    pub side: PlayerTurn,
+    pub name: String,
+    pub peer_name: Option<String>,
+    // client: the token of its seat from WELCOME, host: the one it gave the client
+    token: Option<String>,
+    role: Role,
+    connection: Option<Connection>,
+    // host: new connections that haven't said HELLO yet
+    arrivals: Vec<Connection>,
+    // client: a connection to the host that is being opened on a thread of its own
+    connecting: Option<Receiver<io::Result<TcpStream>>>,
+    // client: when to ping the host next
+    ping_at: Instant,
+    // client: a move was sent and the host's answer hasn't come yet
+    pending: bool,
+    // chat lines of both ends, oldest first
+    pub chat: Vec<String>,
+    // the opponent's offer waiting for our answer
+    pub offer: Option<Offer>,
+    // our offer waiting for the opponent's answer
+    pub offered: Option<Offer>,
+}
+
+impl Session {
+    // pause between attempts to reach a host that went away
+    const RECONNECT_INTERVAL: Duration = Duration::from_secs(2);
+    const CONNECT_TIMEOUT: Duration = Duration::from_secs(1);
+
+    /// listens for the opponent on all interfaces
+    pub fn host(port: u16, name: &str) -> io::Result<Self> {
+        let listener = TcpListener::bind(("0.0.0.0", port))?;
+        listener.set_nonblocking(true)?;
+        let port = listener.local_addr()?.port();
+
+        Ok(Self {
+            game: Self::new_game(),
+            side: PlayerTurn::Black,
+            name: name.to_string(),
+            peer_name: None,
+            token: None,
+            role: Role::Host { listener, port },
+            connection: None,
+            arrivals: Vec::new(),
+            connecting: None,
+            ping_at: Instant::now(),
+            pending: false,
+            chat: Vec::new(),
+            offer: None,
+            offered: None,
+        })
+    }
+
+    /// connects to a host or server at "address:port", at `DEFAULT_PORT` without a port
+    pub fn join(address: &str, name: &str) -> io::Result<Self> {
+        Self::connect_to(address, name, None)
+    }
+
+    /// follows a game of a server without playing in it
+    pub fn watch(address: &str, name: &str, game: u32) -> io::Result<Self> {
+        Self::connect_to(address, name, Some(game))
+    }
+
+    fn connect_to(address: &str, name: &str, watch: Option<u32>) -> io::Result<Self> {
+        let address = with_default_port(address);
+        let address = address.to_socket_addrs()?
+            .next()
+            .ok_or_else(|| io::Error::new(ErrorKind::InvalidInput, format!("no address for '{}'", address)))?;
+
+        let mut session = Self {
+            game: Self::new_game(),
+            side: PlayerTurn::White,
+            name: name.to_string(),
+            peer_name: None,
+            token: None,
+            role: Role::Join { address, retry_at: Instant::now(), closed: false, watch },
+            connection: None,
+            arrivals: Vec::new(),
+            connecting: None,
+            ping_at: Instant::now(),
+            pending: false,
+            chat: Vec::new(),
+            offer: None,
+            offered: None,
+        };
+
+        session.connect()?;
+        Ok(session)
+    }
+
+    fn new_game() -> Game {
+        Game::new(GameMode::PvP, &Settings::default())
+    }
+
+    /// host: the port it listens on
+    pub fn port(&self) -> Option<u16> {
+        match self.role {
+            Role::Host { port, .. } => Some(port),
+            Role::Join { .. } => None,
+        }
+    }
+
+    pub fn is_host(&self) -> bool {
+        matches!(self.role, Role::Host { .. })
+    }
+
+    /// the opponent is connected and known by name
+    pub fn is_connected(&self) -> bool {
+        self.connection.is_some() && self.peer_name.is_some()
+    }
+
+    pub fn is_watching(&self) -> bool {
+        matches!(self.role, Role::Join { watch: Some(_), .. })
+    }
+
+    pub fn is_my_turn(&self) -> bool {
+        self.is_connected() && !self.is_watching() && !self.pending && self.game.outcome().is_none() && self.game.board.player_turn == self.side
+    }
+
+    pub fn status(&self) -> String {
+        match (&self.role, &self.peer_name) {
+            (Role::Join { watch: Some(_), .. }, Some(game)) if self.connection.is_some() => format!("watching {}", game),
+            (_, Some(peer)) if self.connection.is_some() => format!("playing {} as {}", peer, self.side.name()),
+            (Role::Host { port, .. }, _) => format!("waiting for an opponent on port {}", port),
+            (Role::Join { closed: true, .. }, _) => "the host ended the game".to_string(),
+            (Role::Join { address, .. }, _) if self.connection.is_some() => format!("joining {}", address),
+            (Role::Join { address, .. }, _) => format!("reconnecting to {}", address),
+        }
+    }
+
+    // client: opens the first connection, reconnecting is left to `reconnect`
+    fn connect(&mut self) -> io::Result<()> {
+        if let Role::Join { address, .. } = self.role {
+            self.introduce(TcpStream::connect_timeout(&address, Self::CONNECT_TIMEOUT)?)?;
+        }
+
+        Ok(())
+    }
+
+    // client: introduces itself over the new connection and asks for a game or to watch one.
+    // The host or server seats a player back in its game when the name and the token of its
+    // seat match.
+    fn introduce(&mut self, stream: TcpStream) -> io::Result<()> {
+        let Role::Join { watch, .. } = self.role else {
+            return Ok(());
+        };
+
+        let mut connection = Connection::new(stream)?;
+        connection.send(&Message::Hello { version: VERSION, token: self.token.clone(), name: self.name.clone() })?;
+        connection.send(&match watch {
+            Some(id) => Message::Watch(id),
+            None => Message::Seek,
+        })?;
+        self.connection = Some(connection);
+        self.ping_at = Instant::now() + PING_INTERVAL;
+        Ok(())
+    }
+
+    /// plays a move of this end's color. The host plays it right away, a client sends it and
+    /// sees it played with the next update.
+    pub fn play(&mut self, x: usize, y: usize) -> Result<(), String> {
+        if self.is_watching() {
+            return Err("spectators don't play".to_string());
+        }
+        if !self.is_connected() {
+            return Err("no opponent connected".to_string());
+        }
+        if !self.is_my_turn() {
+            return Err("not your turn".to_string());
+        }
+        if !self.game.board.is_valid_cell(x, y) {
+            return Err("no legal move".to_string());
+        }
+
+        if self.is_host() {
+            self.game.play(x, y);
+            self.clear_offers();
+            self.send_state();
+        } else {
+            self.send(&Message::Move { x, y });
+            self.pending = true;
+        }
+
+        Ok(())
+    }
+
+    /// sends a line to the opponent, and with a server to the spectators too
+    pub fn say(&mut self, text: &str) -> Result<(), String> {
+        let text = text.trim();
+
+        if text.is_empty() {
+            return Err("nothing to say".to_string());
+        }
+        if self.connection.is_none() {
+            return Err("not connected".to_string());
+        }
+
+        let line = format!("{}: {}", self.name, text);
+        self.send(&Message::Chat(line.clone()));
+        self.chat.push(line);
+        Ok(())
+    }
+
+    /// gives up the game, the host decides it right away
+    pub fn resign(&mut self) -> Result<(), String> {
+        self.check_playing()?;
+
+        if self.is_host() {
+            self.game.resign(self.side);
+            self.clear_offers();
+            self.send_state();
+        } else {
+            self.send(&Message::Resign);
+        }
+
+        Ok(())
+    }
+
+    /// proposes a draw or to take back our last move, the answer comes as `NetEvent::Answered`
+    pub fn propose(&mut self, offer: Offer) -> Result<(), String> {
+        self.check_playing()?;
+
+        if offer == Offer::Takeback && !self.game.history.moves.iter().any(|played| played.side == self.side && played.cell.is_some()) {
+            return Err("no move to take back".to_string());
+        }
+        if self.offered.is_some() {
+            return Err("an offer is waiting for an answer already".to_string());
+        }
+
+        self.send(&Message::Offer(offer));
+        self.offered = Some(offer);
+        Ok(())
+    }
+
+    /// accepts or declines the opponent's offer, the host settles an accepted one
+    pub fn answer(&mut self, accept: bool) -> Result<(), String> {
+        let offer = self.offer.take().ok_or("no offer to answer")?;
+
+        if !accept {
+            self.send(&Message::Decline(offer));
+            return Ok(());
+        }
+
+        self.send(&Message::Accept(offer));
+
+        if self.is_host() {
+            self.settle(offer, self.side.opposite());
+        }
+
+        Ok(())
+    }
+
+    fn check_playing(&self) -> Result<(), String> {
+        if self.is_watching() {
+            Err("spectators don't play".to_string())
+        } else if !self.is_connected() {
+            Err("no opponent connected".to_string())
+        } else if self.game.outcome().is_some() {
+            Err("the game is over".to_string())
+        } else {
+            Ok(())
+        }
+    }
+
+    // host: carries out an accepted offer of the side and sends the game
+    fn settle(&mut self, offer: Offer, side: PlayerTurn) {
+        let settled = match offer {
+            Offer::Draw => {
+                self.game.agree_draw();
+                true
+            },
+            Offer::Takeback => self.game.take_back(side),
+        };
+
+        if !settled {
+            self.send(&Message::Error("nothing to take back".to_string()));
+        }
+
+        self.clear_offers();
+        self.send_state();
+    }
+
+    // a move or a settled offer makes the open ones void
+    fn clear_offers(&mut self) {
+        self.offer = None;
+        self.offered = None;
+    }
+
+    /// host: starts over from the opening position. A client asks for a new opponent once the
+    /// game is over, which only a server gives.
+    pub fn restart(&mut self) -> Result<(), String> {
+        if self.is_host() {
+            self.game = Self::new_game();
+            self.clear_offers();
+            self.send_state();
+            return Ok(());
+        }
+
+        if self.is_watching() || self.game.outcome().is_none() {
+            return Err("only the host starts new games".to_string());
+        }
+
+        self.send(&Message::Seek);
+        Ok(())
+    }
+
+    /// handles what arrived since the last poll, accepts or reconnects as needed
+    pub fn poll(&mut self) -> Vec<NetEvent> {
+        let mut events = Vec::new();
+
+        self.reconnect();
+
+        match self.connection.as_mut().map(Connection::receive) {
+            Some(Ok(received)) => {
+                for message in received {
+                    match message {
+                        Ok(message) => self.handle(message, &mut events),
+                        Err(err) => self.send(&Message::Error(err)),
+                    }
+                }
+            },
+            Some(Err(_)) => self.disconnect(&mut events),
+            None => {},
+        }
+
+        self.keep_alive(&mut events);
+
+        // what the socket didn't take when it was sent, a peer that doesn't read is given up
+        if self.connection.as_mut().is_some_and(|connection| connection.flush().is_err()) {
+            self.disconnect(&mut events);
+        }
+
+        // after reading, so a client coming back finds its dropped connection noticed
+        self.accept(&mut events);
+        events
+    }
+
+    fn handle(&mut self, message: Message, events: &mut Vec<NetEvent>) {
+        match (self.is_host(), message) {
+            // the connection was taken on its HELLO, see `welcome`
+            (true, Message::Hello { .. }) => self.send(&Message::Error("HELLO was said already".to_string())),
+            (true, Message::Move { x, y }) => {
+                let side = self.side.opposite();
+
+                if self.peer_name.is_none() {
+                    self.send(&Message::Error("HELLO comes first".to_string()));
+                } else if self.game.outcome().is_some() || self.game.board.player_turn != side {
+                    self.send(&Message::Error("not your turn".to_string()));
+                } else if !self.game.play(x, y) {
+                    self.send(&Message::Error("no legal move".to_string()));
+                } else {
+                    self.clear_offers();
+                    events.push(NetEvent::Updated);
+                }
+
+                // a rejected move leaves the client in sync too
+                self.send_state();
+            },
+            // the client has its seat already
+            (true, Message::Seek) => {},
+            (true, Message::Resign) => {
+                if self.game.outcome().is_none() {
+                    self.game.resign(self.side.opposite());
+                    self.clear_offers();
+                    events.push(NetEvent::Updated);
+                }
+                self.send_state();
+            },
+            (is_host, Message::Accept(offer)) => {
+                if self.offered != Some(offer) {
+                    self.send(&Message::Error(format!("no {} was offered", offer.name())));
+                    return;
+                }
+
+                self.offered = None;
+                events.push(NetEvent::Answered(offer, true));
+
+                // a client gets the settled game from the host
+                if is_host {
+                    self.settle(offer, self.side);
+                    events.push(NetEvent::Updated);
+                }
+            },
+            (false, Message::Welcome { side, token, name, .. }) => {
+                self.side = side;
+                self.token = Some(token);
+                self.peer_name = Some(name.clone());
+                events.push(NetEvent::Joined(name));
+            },
+            (false, Message::State { moves }) => {
+                // the host sends the game after every move it checked, ours included
+                self.pending = false;
+
+                match parse_transcript(&moves) {
+                    Ok(history) if history != self.game.history => {
+                        self.game = Self::new_game();
+                        self.game.board = history.positions().last().copied().unwrap_or_default();
+                        self.game.history = history;
+                        self.clear_offers();
+                        events.push(NetEvent::Updated);
+                    },
+                    // a RESULT follows for a game that is still decided
+                    Ok(_) if self.game.decided.is_some() => {
+                        self.game.decided = None;
+                        events.push(NetEvent::Updated);
+                    },
+                    Ok(_) => {},
+                    Err(err) => events.push(NetEvent::Rejected(format!("bad game from the host: {}", err))),
+                }
+            },
+            (false, Message::Result(outcome)) => {
+                self.game.decided = Some(outcome);
+                self.clear_offers();
+                events.push(NetEvent::Updated);
+            },
+            (false, Message::Watching { text, .. }) => {
+                self.peer_name = Some(text.clone());
+                events.push(NetEvent::Joined(text));
+            },
+            (_, Message::Chat(text)) => {
+                self.chat.push(text.clone());
+                events.push(NetEvent::Chat(text));
+            },
+            (_, Message::Offer(offer)) => {
+                self.offer = Some(offer);
+                events.push(NetEvent::Offered(offer));
+            },
+            (_, Message::Decline(offer)) => {
+                if self.offered == Some(offer) {
+                    self.offered = None;
+                    events.push(NetEvent::Answered(offer, false));
+                }
+            },
+            (true, Message::Ping) => self.send(&Message::Pong),
+            // heard, which is all a ping is for
+            (false, Message::Pong) => {},
+            (_, Message::Info(text)) => events.push(NetEvent::Info(text)),
+            (_, Message::Error(text)) => events.push(NetEvent::Rejected(text)),
+            (_, Message::Bye) => {
+                if let Role::Join { closed, .. } = &mut self.role {
+                    *closed = true;
+                }
+                self.disconnect(events);
+            },
+            (_, message) => self.send(&Message::Error(format!("unexpected {}", message))),
+        }
+    }
+
+    // a peer not heard from for `IDLE_TIMEOUT` is gone, a client pings the host to be heard
+    fn keep_alive(&mut self, events: &mut Vec<NetEvent>) {
+        let Some(connection) = &self.connection else {
+            return;
+        };
+
+        if connection.silent_for() > IDLE_TIMEOUT {
+            self.disconnect(events);
+        } else if !self.is_host() && Instant::now() >= self.ping_at {
+            self.ping_at = Instant::now() + PING_INTERVAL;
+            self.send(&Message::Ping);
+        }
+    }
+
+    // host: takes new connections and seats the first HELLO. A connection that doesn't say it
+    // within `HELLO_TIMEOUT` is closed, so it can't keep the seat from anyone.
+    fn accept(&mut self, events: &mut Vec<NetEvent>) {
+        let Role::Host { listener, .. } = &self.role else {
+            return;
+        };
+
+        match listener.accept() {
+            Ok((stream, _)) => match Connection::new(stream) {
+                Ok(connection) => self.arrivals.push(connection),
+                Err(err) => events.push(NetEvent::Rejected(err.to_string())),
+            },
+            Err(err) if err.kind() == ErrorKind::WouldBlock => {},
+            Err(err) => events.push(NetEvent::Rejected(err.to_string())),
+        }
+
+        let mut idx = 0;
+
+        while idx < self.arrivals.len() {
+            // the client waits for WELCOME before it sends anything else that matters
+            let hello = match self.arrivals[idx].receive() {
+                Ok(received) => received.into_iter().find_map(|message| match message {
+                    Ok(Message::Hello { version, token, name }) => Some((version, token, name)),
+                    _ => None,
+                }),
+                Err(_) => {
+                    self.arrivals.remove(idx);
+                    continue;
+                },
+            };
+
+            match hello {
+                Some((version, token, name)) => {
+                    let connection = self.arrivals.remove(idx);
+                    self.welcome(connection, version, token, name, events);
+                },
+                None if self.arrivals[idx].age() > HELLO_TIMEOUT => {
+                    self.arrivals.remove(idx);
+                },
+                None => idx += 1,
+            }
+        }
+    }
+
+    // host: seats the client that said HELLO. While a client is seated only the same client
+    // coming back, by its name and token, is let in, and its new connection replaces the old
+    // one that may not have noticed the drop yet.
+    fn welcome(&mut self, mut connection: Connection, version: u32, token: Option<String>, name: String, events: &mut Vec<NetEvent>) {
+        if version != VERSION {
+            let _ = connection.send(&Message::Error(format!("protocol version {} is needed, not {}", VERSION, version)));
+            return;
+        }
+
+        let returning = self.peer_name.as_ref() == Some(&name) && token.is_some() && token == self.token;
+
+        if self.connection.is_some() && !returning {
+            let _ = connection.send(&Message::Error("the game is full".to_string()));
+            return;
+        }
+
+        let token = if returning { token.unwrap_or_default() } else { new_token() };
+
+        self.connection = Some(connection);
+        self.clear_offers();
+        self.send(&Message::Welcome { version: VERSION, side: self.side.opposite(), token: token.clone(), name: self.name.clone() });
+        self.send_state();
+        self.token = Some(token);
+        self.peer_name = Some(name.clone());
+        events.push(NetEvent::Joined(name));
+    }
+
+    // client: tries the host again every `RECONNECT_INTERVAL` after losing it. The connection
+    // is opened on a thread of its own, a poll never waits for it.
+    fn reconnect(&mut self) {
+        let Role::Join { address, retry_at, closed: false, .. } = self.role else {
+            return;
+        };
+
+        if self.connection.is_some() {
+            return;
+        }
+
+        if let Some(connecting) = &self.connecting {
+            match connecting.try_recv() {
+                Ok(Ok(stream)) => {
+                    self.connecting = None;
+                    // a failed attempt is tried again like any other
+                    let _ = self.introduce(stream);
+                },
+                Ok(Err(_)) | Err(TryRecvError::Disconnected) => self.connecting = None,
+                Err(TryRecvError::Empty) => {},
+            }
+            return;
+        }
+
+        if Instant::now() >= retry_at {
+            if let Role::Join { retry_at, .. } = &mut self.role {
+                *retry_at = Instant::now() + Self::RECONNECT_INTERVAL;
+            }
+
+            let (sender, receiver) = mpsc::channel();
+            thread::spawn(move || {
+                let _ = sender.send(TcpStream::connect_timeout(&address, Self::CONNECT_TIMEOUT));
+            });
+            self.connecting = Some(receiver);
+        }
+    }
+
+    fn disconnect(&mut self, events: &mut Vec<NetEvent>) {
+        self.connection = None;
+        self.pending = false;
+        self.clear_offers();
+        events.push(NetEvent::Left);
+    }
+
+    fn send_state(&mut self) {
+        let moves = transcript(&self.game.history);
+        self.send(&Message::State { moves });
+
+        if let Some(outcome) = self.game.decided {
+            self.send(&Message::Result(outcome));
+        }
+    }
+
+    // a failed send drops the connection: the host waits for the client again, a client reconnects
+    fn send(&mut self, message: &Message) {
+        if let Some(connection) = &mut self.connection {
+            if connection.send(message).is_err() {
+                self.connection = None;
+            }
+        }
+    }
+}
+
+impl Drop for Session {
+    fn drop(&mut self) {
+        self.send(&Message::Bye);
+    }
+}
@@ -0,0 +1,406 @@
+use crate::board::{move_name, parse_move, Board, Cell, PlayerTurn, REVERSI_FIELD_HEIGHT, REVERSI_FIELD_SIZE, REVERSI_FIELD_WIDTH};
+use crate::history::History;
+use crate::outcome::{Outcome, Reason};
+
+
+/// Position as 64 squares row by row, `X` black, `O` white and `-` empty, followed by the side to
+/// move, e.g. "---------------------------OX------XO--------------------------- X"
+pub fn board_string(board: &Board) -> String {
+    let mut out: String = board.field.iter().map(|cell| square_char(*cell, 'X', 'O')).collect();
+    out.push(' ');
+    out.push(side_char(board.player_turn, 'X', 'O'));
+    out
+}
+
+/// Reads what `board_string` writes. Whitespace inside the squares is ignored, `*` and `x` are
+/// taken for black, `o` for white and `.` for empty as well. Without a side to move black moves.
+pub fn parse_board_string(text: &str) -> Result<Board, String> {
+    let chars: Vec<char> = text.chars().filter(|chr| !chr.is_whitespace()).collect();
+
+    if chars.len() != REVERSI_FIELD_SIZE && chars.len() != REVERSI_FIELD_SIZE + 1 {
+        return Err(format!("a position has {} squares and the side to move, got {} characters", REVERSI_FIELD_SIZE, chars.len()));
+    }
+
+    let mut board = Board::new();
+
+    for (idx, chr) in chars[..REVERSI_FIELD_SIZE].iter().enumerate() {
+        board.field[idx] = match chr {
+            'X' | 'x' | '*' => Cell::Black(0),
+            'O' | 'o' => Cell::White(0),
+            '-' | '.' => Cell::Free,
+            _ => return Err(format!("unknown square '{}'", chr)),
+        };
+    }
+
+    board.player_turn = match chars.get(REVERSI_FIELD_SIZE) {
+        None | Some('X' | 'x' | '*') => PlayerTurn::Black,
+        Some('O' | 'o') => PlayerTurn::White,
+        Some(chr) => return Err(format!("unknown side to move '{}'", chr)),
+    };
+
+    Ok(board)
+}
+
+/// The board drawn in text with the coordinates around it
+pub fn ascii(board: &Board) -> String {
+    let mut out = String::from(" ");
+
+    for x in 0..REVERSI_FIELD_WIDTH {
+        out.push(' ');
+        out.push((b'a' + x as u8) as char);
+    }
+    out.push('\n');
+
+    for y in 0..REVERSI_FIELD_HEIGHT {
+        out.push_str(&(y + 1).to_string());
+
+        for x in 0..REVERSI_FIELD_WIDTH {
+            let idx = y * REVERSI_FIELD_WIDTH + x;
+            let chr = if board.field[idx] == Cell::Free && board.is_valid_cell(x, y) {
+                '.'
+            } else {
+                square_char(board.field[idx], 'X', 'O')
+            };
+
+            out.push(' ');
+            out.push(chr);
+        }
+        out.push('\n');
+    }
+
+    let (black_score, white_score) = board.score();
+    out.push_str(&format!("X: {}  O: {}  {} to move\n", black_score, white_score, board.player_turn.name()));
+    out
+}
+
+/// Moves one after another without separators, passes are left out: "f5d6c3d3c4"
+pub fn transcript(history: &History) -> String {
+    history.moves.iter()
+        .filter_map(|played| played.cell)
+        .map(|(x, y)| move_name(x, y))
+        .collect()
+}
+
+/// Replays a transcript from the start position, the passes it leaves out are put back.
+/// Separators between the moves are allowed.
+pub fn parse_transcript(text: &str) -> Result<History, String> {
+    let chars: Vec<char> = text.chars().filter(|chr| chr.is_ascii_alphanumeric()).collect();
+
+    if !chars.len().is_multiple_of(2) {
+        return Err("every move takes a letter and a digit".to_string());
+    }
+
+    let moves: Vec<String> = chars.chunks(2).map(|pair| pair.iter().collect()).collect();
+    let (history, _) = replay(Board::new(), moves.iter().map(|name| Some(name.as_str())))?;
+    Ok(history)
+}
+
+/// Game in the Generic Game Format of online Othello servers, `result` is Black's disc lead
+/// when the game is over
+pub fn ggf(history: &History, black: &str, white: &str) -> String {
+    ggf_with_outcome(history, black, white, None)
+}
+
+/// `ggf` for a game that may have ended off the board: a win on time or by resignation counts
+/// as 64 discs and is marked ":t" or ":r", an agreed draw is "0:s"
+pub fn ggf_with_outcome(history: &History, black: &str, white: &str, outcome: Option<&Outcome>) -> String {
+    let positions = history.positions();
+    let last = positions.last().copied().unwrap_or_default();
+
+    let mut out = format!("(;GM[Othello]PC[reversi-druid]PB[{}]PW[{}]", ggf_escape(black), ggf_escape(white));
+
+    let lead = match outcome.map(|outcome| (outcome.winner, outcome.reason)) {
+        Some((_, Reason::Agreement)) => Some("0:s".to_string()),
+        Some((winner, reason @ (Reason::Time | Reason::Resignation))) => {
+            let discs = if winner == Some(PlayerTurn::Black) { 64 } else { -64 };
+            Some(format!("{:+}:{}", discs, if reason == Reason::Time { 't' } else { 'r' }))
+        },
+        _ if last.is_over() => {
+            let (black_score, white_score) = last.score();
+            Some(format!("{:+}", black_score as i32 - white_score as i32))
+        },
+        _ => None,
+    };
+
+    if let Some(lead) = lead {
+        out.push_str(&format!("RE[{}]", lead));
+    }
+
+    let start = Board::new();
+    let squares: String = start.field.iter().map(|cell| square_char(*cell, '*', 'O')).collect();
+    out.push_str(&format!("TY[8]BO[8 {} {}]", squares, side_char(start.player_turn, '*', 'O')));
+
+    for played in &history.moves {
+        let name = played.cell.map_or("PA".to_string(), |(x, y)| move_name(x, y));
+        out.push_str(&format!("{}[{}]", side_char(played.side, 'B', 'W'), name));
+    }
+
+    out.push_str(";)");
+    out
+}
+
+/// A GGF game without moves that starts from the position, enough to hand the position to
+/// engines speaking NBoard
+pub fn ggf_position(board: &Board) -> String {
+    let squares: String = board.field.iter().map(|cell| square_char(*cell, '*', 'O')).collect();
+    format!("(;GM[Othello]PC[reversi-druid]TY[8]BO[8 {} {}];)", squares, side_char(board.player_turn, '*', 'O'))
+}
+
+/// A game read from GGF with the names of its players
+#[derive(Clone, Debug, PartialEq)]
+pub struct GgfGame {
+    pub black: String,
+    pub white: String,
+    pub history: History,
+}
+
+/// Reads one GGF game, only games from the standard start position can be replayed
+pub fn parse_ggf(text: &str) -> Result<GgfGame, String> {
+    let raw = RawGgf::parse(text)?;
+
+    if raw.start != Board::new() {
+        return Err("only games from the standard start position are supported".to_string());
+    }
+
+    let (history, _) = replay(raw.start, raw.moves())?;
+
+    Ok(GgfGame {
+        black: raw.black,
+        white: raw.white,
+        history,
+    })
+}
+
+/// The position a GGF game has reached, the game may start from any position
+pub fn parse_ggf_position(text: &str) -> Result<Board, String> {
+    let raw = RawGgf::parse(text)?;
+    let (_, board) = replay(raw.start, raw.moves())?;
+    Ok(board)
+}
+
+// the tags of a GGF game this crate cares about
+struct RawGgf {
+    black: String,
+    white: String,
+    start: Board,
+    moves: Vec<String>,
+}
+
+impl RawGgf {
+    fn parse(text: &str) -> Result<Self, String> {
+        let text = text.trim();
+        let body = text.strip_prefix("(;")
+            .and_then(|body| body.strip_suffix(";)"))
+            .ok_or("a GGF game is enclosed in \"(;\" and \";)\"")?;
+
+        let mut raw = RawGgf {
+            black: String::new(),
+            white: String::new(),
+            start: Board::new(),
+            moves: Vec::new(),
+        };
+        let mut rest = body;
+
+        while let Some(open) = rest.find('[') {
+            let tag = rest[..open].trim();
+            let close = rest[open..].find(']').ok_or_else(|| format!("{} is never closed", tag))? + open;
+            let value = &rest[open + 1..close];
+
+            match tag {
+                "PB" => raw.black = value.to_string(),
+                "PW" => raw.white = value.to_string(),
+                "BO" => {
+                    let mut parts = value.split_whitespace();
+
+                    if parts.next() != Some("8") {
+                        return Err("only 8x8 boards are supported".to_string());
+                    }
+
+                    raw.start = parse_board_string(&parts.collect::<String>())?;
+                },
+                // the time spent and evaluation after the move are dropped
+                "B" | "W" => raw.moves.push(value.split('/').next().unwrap_or_default().to_string()),
+                _ => {},
+            }
+
+            rest = &rest[close + 1..];
+        }
+
+        Ok(raw)
+    }
+
+    // None for passes
+    fn moves(&self) -> impl Iterator<Item = Option<&str>> {
+        self.moves.iter().map(|name| {
+            if name.eq_ignore_ascii_case("PA") { None } else { Some(name.as_str()) }
+        })
+    }
+}
+
+// plays the moves from the start position, None stands for a pass and passes the moves leave
+// out are filled in
+fn replay<'a>(start: Board, moves: impl Iterator<Item = Option<&'a str>>) -> Result<(History, Board), String> {
+    let mut board = start;
+    let mut history = History::new();
+
+    for (idx, name) in moves.enumerate() {
+        let name = match name {
+            Some(name) => name,
+            None => {
+                if board.has_valid_move() {
+                    return Err(format!("move {}: {} passes while it can move", idx + 1, board.player_turn.name()));
+                }
+                history.push_pass(board.player_turn);
+                board.pass();
+                continue;
+            },
+        };
+
+        let (x, y) = parse_move(name).ok_or_else(|| format!("move {}: '{}' is no square", idx + 1, name))?;
+
+        if !board.has_valid_move() && !board.is_over() {
+            history.push_pass(board.player_turn);
+            board.pass();
+        }
+
+        let side = board.player_turn;
+        let flipped = board.play(x, y);
+
+        if flipped.is_empty() {
+            return Err(format!("move {}: {} can't play {}", idx + 1, side.name(), name));
+        }
+
+        history.push(side, (x, y), flipped);
+    }
+
+    Ok((history, board))
+}
+
+fn square_char(cell: Cell, black: char, white: char) -> char {
+    match cell {
+        Cell::Black(_) => black,
+        Cell::White(_) => white,
+        Cell::Free => '-',
+    }
+}
+
+fn side_char(side: PlayerTurn, black: char, white: char) -> char {
+    match side {
+        PlayerTurn::Black => black,
+        PlayerTurn::White => white,
+    }
+}
+
+fn ggf_escape(name: &str) -> String {
+    name.replace([']', '['], "")
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // a whole game, the first legal move every time, with the passes it takes
+    fn game() -> History {
+        let mut board = Board::new();
+        let mut history = History::new();
+
+        while !board.is_over() {
+            let side = board.player_turn;
+
+            match board.valid_moves().first() {
+                Some(&(x, y)) => {
+                    let flipped = board.play(x, y);
+                    history.push(side, (x, y), flipped);
+                },
+                None => {
+                    history.push_pass(side);
+                    board.pass();
+                },
+            }
+        }
+
+        history
+    }
+
+    #[test]
+    fn board_strings_round_trip() {
+        for board in game().positions() {
+            assert_eq!(parse_board_string(&board_string(&board)), Ok(board));
+        }
+    }
+
+    #[test]
+    fn board_strings_take_the_other_square_characters() {
+        let text = board_string(&Board::new()).replace('X', "*").replace('-', ".");
+        assert_eq!(parse_board_string(&text), Ok(Board::new()));
+    }
+
+    #[test]
+    fn bad_board_strings_are_rejected() {
+        assert!(parse_board_string("XO-").is_err());
+        assert!(parse_board_string(&board_string(&Board::new()).replace(' ', "?")).is_err());
+        assert!(parse_board_string(&board_string(&Board::new()).replacen('-', "#", 1)).is_err());
+    }
+
+    #[test]
+    fn transcripts_round_trip() {
+        let history = game();
+        let text = transcript(&history);
+
+        assert_eq!(text.len(), 2 * history.moves.iter().filter(|played| played.cell.is_some()).count());
+        assert_eq!(parse_transcript(&text), Ok(history));
+    }
+
+    #[test]
+    fn transcripts_may_separate_their_moves() {
+        assert_eq!(parse_transcript("f5 d6, c3"), parse_transcript("f5d6c3"));
+    }
+
+    #[test]
+    fn illegal_transcripts_are_rejected() {
+        // a1 flips nothing at the start
+        assert!(parse_transcript("a1").unwrap_err().contains("can't play"));
+        assert!(parse_transcript("f5z9").unwrap_err().contains("is no square"));
+        assert!(parse_transcript("f5d").is_err());
+    }
+
+    #[test]
+    fn ggf_games_round_trip() {
+        let history = game();
+        let text = ggf(&history, "Alice", "Bob");
+
+        assert!(text.contains("RE["));
+        assert_eq!(parse_ggf(&text), Ok(GgfGame {
+            black: "Alice".to_string(),
+            white: "Bob".to_string(),
+            history,
+        }));
+    }
+
+    #[test]
+    fn ggf_positions_round_trip() {
+        for board in game().positions() {
+            assert_eq!(parse_ggf_position(&ggf_position(&board)), Ok(board));
+        }
+    }
+
+    #[test]
+    fn truncated_ggf_is_rejected() {
+        let text = ggf(&game(), "Alice", "Bob");
+
+        assert!(parse_ggf(&text[..text.len() - 2]).unwrap_err().contains("enclosed"));
+        // cut inside a tag and closed again
+        let cut = &text[..text.find("PW[").unwrap() + 5];
+        assert!(parse_ggf(&format!("{};)", cut)).unwrap_err().contains("never closed"));
+    }
+
+    #[test]
+    fn illegal_ggf_moves_are_rejected() {
+        let text = ggf(&History::new(), "Alice", "Bob").replace(";)", "B[a1];)");
+        assert!(parse_ggf(&text).unwrap_err().contains("can't play"));
+
+        let text = ggf(&History::new(), "Alice", "Bob").replace(";)", "B[PA];)");
+        assert!(parse_ggf(&text).unwrap_err().contains("passes while it can move"));
+    }
+}
@@ -0,0 +1,91 @@
+use crate::board::{Board, PlayerTurn};
+
+
+/// Why a game ended
+#[derive(Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(feature = "gui", derive(druid::Data))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Reason {
+    // neither side could move, the discs were counted
+    #[cfg_attr(feature = "serde", serde(rename = "discs"))]
+    Discs,
+    // the loser's flag fell
+    #[cfg_attr(feature = "serde", serde(rename = "time"))]
+    Time,
+    // the loser gave up
+    #[cfg_attr(feature = "serde", serde(rename = "resign"))]
+    Resignation,
+    // both players agreed to a draw
+    #[cfg_attr(feature = "serde", serde(rename = "agreed"))]
+    Agreement,
+}
+
+impl Reason {
+    pub const ALL: [Reason; 4] = [Reason::Discs, Reason::Time, Reason::Resignation, Reason::Agreement];
+
+    /// the word saved games and the network protocol use for it
+    pub fn name(&self) -> &'static str {
+        match self {
+            Reason::Discs => "discs",
+            Reason::Time => "time",
+            Reason::Resignation => "resign",
+            Reason::Agreement => "agreed",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|reason| reason.name() == name)
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(feature = "gui", derive(druid::Data))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Outcome {
+    // None for a draw
+    pub winner: Option<PlayerTurn>,
+    pub reason: Reason,
+}
+
+impl Outcome {
+    /// result of a game nobody can move in anymore
+    pub fn from_board(board: &Board) -> Self {
+        Self {
+            winner: board.winner(),
+            reason: Reason::Discs,
+        }
+    }
+
+    /// the side ran out of time
+    pub fn on_time(loser: PlayerTurn) -> Self {
+        Self {
+            winner: Some(loser.opposite()),
+            reason: Reason::Time,
+        }
+    }
+
+    /// the side resigned
+    pub fn resigned(loser: PlayerTurn) -> Self {
+        Self {
+            winner: Some(loser.opposite()),
+            reason: Reason::Resignation,
+        }
+    }
+
+    pub fn agreed_draw() -> Self {
+        Self {
+            winner: None,
+            reason: Reason::Agreement,
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        match (self.winner, self.reason) {
+            (None, Reason::Agreement) => "Draw agreed!".to_string(),
+            (None, _) => "Draw!".to_string(),
+            (Some(winner), Reason::Time) => format!("{} wins on time!", winner.name()),
+            (Some(winner), Reason::Resignation) => format!("{} wins by resignation!", winner.name()),
+            (Some(winner), _) => format!("{} wins!", winner.name()),
+        }
+    }
+}
@@ -0,0 +1,301 @@
+use std::cmp::Reverse;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use crate::board::PlayerTurn;
+use crate::rating::GameEntry;
+use crate::settings::data_dir;
+
+
+/// Named players to pick from when a game starts, kept a name per line. Their games are the
+/// ones the rating store has under the name.
+pub struct Profiles {
+    path: PathBuf,
+    pub names: Vec<String>,
+}
+
+impl Profiles {
+    const FILE_NAME: &'static str = "profiles.txt";
+
+    /// `profiles.txt` in the data directory, see `settings::data_dir`
+    pub fn default_path() -> Option<PathBuf> {
+        Some(data_dir()?.join(Self::FILE_NAME))
+    }
+
+    /// reads the profiles, a missing file means there are none yet
+    pub fn open(path: &Path) -> io::Result<Self> {
+        let names = match fs::read_to_string(path) {
+            Ok(text) => text.lines().map(str::trim).filter(|name| !name.is_empty()).map(String::from).collect(),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Vec::new(),
+            Err(err) => return Err(err),
+        };
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            names,
+        })
+    }
+
+    /// the profiles at the default path, none kept in memory only when they can't be read
+    pub fn open_default() -> Self {
+        let path = Self::default_path().unwrap_or_default();
+
+        Self::open(&path).unwrap_or_else(|err| {
+            eprintln!("failed to read profiles from {}: {}", path.display(), err);
+            Self { path, names: Vec::new() }
+        })
+    }
+
+    /// keeps a new profile, false when there is one by the name already
+    pub fn add(&mut self, name: &str) -> io::Result<bool> {
+        let name = name.trim().replace(['\n', '\r', '\t'], " ");
+
+        if name.is_empty() || self.names.contains(&name) {
+            return Ok(false);
+        }
+
+        self.names.push(name);
+        self.write()?;
+        Ok(true)
+    }
+
+    /// the profile after `name`, the first one when `name` isn't a profile, to step through them
+    pub fn next(&self, name: &str) -> Option<&str> {
+        let next = match self.names.iter().position(|profile| profile == name) {
+            Some(idx) => (idx + 1) % self.names.len(),
+            None => 0,
+        };
+
+        self.names.get(next).map(String::as_str)
+    }
+
+    fn write(&self) -> io::Result<()> {
+        if self.path.as_os_str().is_empty() {
+            return Ok(());
+        }
+
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+
+        fs::write(&self.path, self.names.iter().map(|name| format!("{}\n", name)).collect::<String>())
+    }
+}
+
+
+/// Wins, draws and losses in one mode, see `GameMode::label`
+#[derive(Clone, Debug, PartialEq)]
+pub struct ModeRecord {
+    pub mode: String,
+    pub wins: u32,
+    pub draws: u32,
+    pub losses: u32,
+}
+
+/// What the recorded games tell about one player
+#[derive(Clone, Debug, PartialEq)]
+pub struct ProfileStats {
+    pub name: String,
+    pub games: u32,
+    // in the order the modes were first played
+    pub modes: Vec<ModeRecord>,
+    // own discs minus the opponent's at the end, over all games
+    pub average_margin: f64,
+    pub longest_streak: u32,
+    // the openings played most, with how often
+    pub openings: Vec<(String, u32)>,
+}
+
+impl ProfileStats {
+    const OPENINGS_SHOWN: usize = 3;
+
+    /// statistics from the rated games the name played, in the order they were recorded
+    pub fn from_games(name: &str, games: &[GameEntry]) -> Self {
+        let mut stats = Self {
+            name: name.to_string(),
+            games: 0,
+            modes: Vec::new(),
+            average_margin: 0.,
+            longest_streak: 0,
+            openings: Vec::new(),
+        };
+        let mut margins = 0_i64;
+        let mut streak = 0;
+
+        for game in games.iter().filter(|game| game.is_rated()) {
+            let side = if game.black == name {
+                PlayerTurn::Black
+            } else if game.white == name {
+                PlayerTurn::White
+            } else {
+                continue;
+            };
+
+            stats.games += 1;
+
+            let (black, white) = game.discs;
+            margins += match side {
+                PlayerTurn::Black => black as i64 - white as i64,
+                PlayerTurn::White => white as i64 - black as i64,
+            };
+
+            let record = match stats.modes.iter().position(|record| record.mode == game.mode) {
+                Some(idx) => &mut stats.modes[idx],
+                None => {
+                    stats.modes.push(ModeRecord { mode: game.mode.clone(), wins: 0, draws: 0, losses: 0 });
+                    stats.modes.last_mut().unwrap()
+                },
+            };
+
+            match game.outcome.winner {
+                Some(winner) if winner == side => {
+                    record.wins += 1;
+                    streak += 1;
+                    stats.longest_streak = stats.longest_streak.max(streak);
+                },
+                Some(_) => {
+                    record.losses += 1;
+                    streak = 0;
+                },
+                None => {
+                    record.draws += 1;
+                    streak = 0;
+                },
+            }
+
+            if !game.opening.is_empty() {
+                match stats.openings.iter_mut().find(|(opening, _)| *opening == game.opening) {
+                    Some((_, count)) => *count += 1,
+                    None => stats.openings.push((game.opening.clone(), 1)),
+                }
+            }
+        }
+
+        if stats.games > 0 {
+            stats.average_margin = margins as f64 / stats.games as f64;
+        }
+
+        // most played first, the first played of equally frequent ones
+        stats.openings.sort_by_key(|(_, count)| Reverse(*count));
+        stats.openings.truncate(Self::OPENINGS_SHOWN);
+        stats
+    }
+
+    pub fn wins(&self) -> u32 {
+        self.modes.iter().map(|record| record.wins).sum()
+    }
+
+    pub fn draws(&self) -> u32 {
+        self.modes.iter().map(|record| record.draws).sum()
+    }
+
+    pub fn losses(&self) -> u32 {
+        self.modes.iter().map(|record| record.losses).sum()
+    }
+
+    /// a few lines of text, wins/draws/losses per mode
+    pub fn report(&self) -> String {
+        if self.games == 0 {
+            return format!("{}: no games yet", self.name);
+        }
+
+        let mut lines = vec![
+            format!("{}: {} games, {}/{}/{}", self.name, self.games, self.wins(), self.draws(), self.losses()),
+            format!("  average margin {:+.1}, longest win streak {}", self.average_margin, self.longest_streak),
+        ];
+
+        for record in &self.modes {
+            lines.push(format!("  {:<18} {}/{}/{}", record.mode, record.wins, record.draws, record.losses));
+        }
+
+        if !self.openings.is_empty() {
+            let openings: Vec<String> = self.openings.iter()
+                .map(|(opening, count)| format!("{} ({})", opening, count))
+                .collect();
+            lines.push(format!("  openings: {}", openings.join(", ")));
+        }
+
+        lines.join("\n")
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notation::parse_transcript;
+    use crate::outcome::Outcome;
+
+    fn game(mode: &str, black: &str, white: &str, moves: &str, outcome: Outcome) -> GameEntry {
+        GameEntry::new(mode, black, white, &parse_transcript(moves).unwrap(), outcome)
+    }
+
+    fn games() -> Vec<GameEntry> {
+        vec![
+            game("PvP", "Alice", "Bob", "f5d6c3d3c4", Outcome::resigned(PlayerTurn::White)),
+            game("PvE mcts", "mcts", "Alice", "f5f6e6f4", Outcome::on_time(PlayerTurn::Black)),
+            game("PvP", "Bob", "Alice", "f5d6c3d3c4", Outcome::resigned(PlayerTurn::White)),
+            // not rated, Alice played both sides
+            game("PvP", "Alice", "Alice", "f5f6", Outcome::resigned(PlayerTurn::Black)),
+            game("PvP", "Alice", "Carol", "f5d6c3d3", Outcome::agreed_draw()),
+        ]
+    }
+
+    #[test]
+    fn records_are_kept_per_mode() {
+        let stats = ProfileStats::from_games("Alice", &games());
+
+        assert_eq!(stats.games, 4);
+        assert_eq!(stats.modes, vec![
+            ModeRecord { mode: "PvP".to_string(), wins: 1, draws: 1, losses: 1 },
+            ModeRecord { mode: "PvE mcts".to_string(), wins: 1, draws: 0, losses: 0 },
+        ]);
+        assert_eq!((stats.wins(), stats.draws(), stats.losses()), (2, 1, 1));
+    }
+
+    #[test]
+    fn streaks_margins_and_openings_are_counted() {
+        let stats = ProfileStats::from_games("Alice", &games());
+
+        assert_eq!(stats.longest_streak, 2);
+        // +3 as Black, +2 as White, -3 as White, 0 as Black
+        assert!((stats.average_margin - 0.5).abs() < 1e-9, "{}", stats.average_margin);
+        assert_eq!(stats.openings, vec![("f5d6c3d3".to_string(), 3), ("f5f6e6f4".to_string(), 1)]);
+    }
+
+    #[test]
+    fn a_name_without_games_has_no_stats() {
+        let stats = ProfileStats::from_games("Dave", &games());
+
+        assert_eq!(stats.games, 0);
+        assert_eq!(stats.report(), "Dave: no games yet");
+    }
+
+    #[test]
+    fn profiles_are_added_once_and_stepped_through() {
+        // kept in memory only
+        let mut profiles = Profiles { path: PathBuf::new(), names: Vec::new() };
+        assert_eq!(profiles.next("Alice"), None);
+
+        assert!(profiles.add("  Alice ").unwrap());
+        assert!(!profiles.add("Alice").unwrap());
+        assert!(!profiles.add("  ").unwrap());
+        assert!(profiles.add("Bob").unwrap());
+
+        assert_eq!(profiles.names, ["Alice", "Bob"]);
+        assert_eq!(profiles.next("Alice"), Some("Bob"));
+        assert_eq!(profiles.next("Bob"), Some("Alice"));
+        assert_eq!(profiles.next("Carol"), Some("Alice"));
+    }
+
+    #[test]
+    fn profiles_are_read_back() {
+        let path = std::env::temp_dir().join(format!("reversi-profiles-{}.txt", std::process::id()));
+        let mut profiles = Profiles::open(&path).unwrap();
+        profiles.add("Alice").unwrap();
+        profiles.add("Bob").unwrap();
+
+        assert_eq!(Profiles::open(&path).unwrap().names, ["Alice", "Bob"]);
+        fs::remove_file(path).unwrap();
+    }
+}
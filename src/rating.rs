@@ -0,0 +1,321 @@
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use crate::board::PlayerTurn;
+use crate::history::History;
+use crate::notation::transcript;
+use crate::outcome::{Outcome, Reason};
+use crate::settings::data_dir;
+
+
+/// A finished game as the rating store keeps it
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GameEntry {
+    // seconds since the Unix epoch
+    pub time: u64,
+    // label of the mode, see `GameMode::label`
+    pub mode: String,
+    pub black: String,
+    pub white: String,
+    pub outcome: Outcome,
+    // (black, white) disc counts at the end
+    pub discs: (u32, u32),
+    // first `OPENING_MOVES` moves as a transcript, empty for games recorded without them
+    pub opening: String,
+}
+
+impl GameEntry {
+    pub const OPENING_MOVES: usize = 4;
+
+    pub fn new(mode: &str, black: &str, white: &str, history: &History, outcome: Outcome) -> Self {
+        let board = history.positions().last().copied().unwrap_or_default();
+        let opening = History { moves: history.moves.iter().take(Self::OPENING_MOVES).cloned().collect() };
+
+        Self {
+            time: SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |time| time.as_secs()),
+            mode: field(mode),
+            black: field(black),
+            white: field(white),
+            outcome,
+            discs: board.score(),
+            opening: transcript(&opening),
+        }
+    }
+
+    /// 1 for a Black win, 0.5 for a draw and 0 for a White win
+    pub fn black_score(&self) -> f64 {
+        match self.outcome.winner {
+            Some(PlayerTurn::Black) => 1.,
+            Some(PlayerTurn::White) => 0.,
+            None => 0.5,
+        }
+    }
+
+    /// false when both sides were played by the same name, nothing to rate
+    pub fn is_rated(&self) -> bool {
+        self.black != self.white
+    }
+
+    // tab separated: time, mode, black, white, result, reason, black discs, white discs, opening
+    fn line(&self) -> String {
+        let result = match self.outcome.winner {
+            Some(PlayerTurn::Black) => "1-0",
+            Some(PlayerTurn::White) => "0-1",
+            None => "1/2",
+        };
+        [
+            self.time.to_string(),
+            self.mode.clone(),
+            self.black.clone(),
+            self.white.clone(),
+            result.to_string(),
+            self.outcome.reason.name().to_string(),
+            self.discs.0.to_string(),
+            self.discs.1.to_string(),
+            self.opening.clone(),
+        ].join("\t")
+    }
+
+    fn parse(line: &str) -> Option<Self> {
+        let parts: Vec<&str> = line.split('\t').collect();
+
+        // the opening came later, older lines go without it
+        let (parts, opening) = match parts.len() {
+            9 => (&parts[..8], parts[8]),
+            _ => (&parts[..], ""),
+        };
+
+        let [time, mode, black, white, result, reason, black_discs, white_discs] = parts[..] else {
+            return None;
+        };
+
+        let winner = match result {
+            "1-0" => Some(PlayerTurn::Black),
+            "0-1" => Some(PlayerTurn::White),
+            "1/2" => None,
+            _ => return None,
+        };
+        let reason = Reason::from_name(reason)?;
+
+        Some(Self {
+            time: time.parse().ok()?,
+            mode: mode.to_string(),
+            black: black.to_string(),
+            white: white.to_string(),
+            outcome: Outcome { winner, reason },
+            discs: (black_discs.parse().ok()?, white_discs.parse().ok()?),
+            opening: opening.to_string(),
+        })
+    }
+}
+
+// names have to fit into a single tab separated field
+fn field(text: &str) -> String {
+    text.replace(['\t', '\n', '\r'], " ")
+}
+
+/// Every recorded game, kept in a tab separated file with a line per game
+pub struct RatingStore {
+    path: PathBuf,
+    pub games: Vec<GameEntry>,
+}
+
+impl RatingStore {
+    const FILE_NAME: &'static str = "games.tsv";
+
+    /// `$XDG_DATA_HOME/reversi-druid/games.tsv`, under `~/.local/share` when the variable isn't set
+    pub fn default_path() -> Option<PathBuf> {
+        Some(data_dir()?.join(Self::FILE_NAME))
+    }
+
+    /// reads the store, a missing file is an empty store and unreadable lines are skipped
+    pub fn open(path: &Path) -> io::Result<Self> {
+        let games = match fs::read_to_string(path) {
+            Ok(text) => text.lines().filter_map(GameEntry::parse).collect(),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Vec::new(),
+            Err(err) => return Err(err),
+        };
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            games,
+        })
+    }
+
+    /// the store at the default path, an empty one kept in memory only when there is none
+    pub fn open_default() -> Self {
+        let path = Self::default_path().unwrap_or_default();
+
+        Self::open(&path).unwrap_or_else(|err| {
+            eprintln!("failed to read ratings from {}: {}", path.display(), err);
+            Self { path, games: Vec::new() }
+        })
+    }
+
+    /// keeps the game and appends it to the file
+    pub fn record(&mut self, entry: GameEntry) -> io::Result<()> {
+        let line = entry.line();
+        self.games.push(entry);
+
+        if self.path.as_os_str().is_empty() {
+            return Ok(());
+        }
+
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+
+        let mut file = OpenOptions::new().create(true).append(true).open(&self.path)?;
+        writeln!(file, "{}", line)
+    }
+
+    pub fn standings(&self) -> Vec<Standing> {
+        Standing::from_games(&self.games)
+    }
+}
+
+/// Rating and record of one player or engine
+#[derive(Clone, Debug, PartialEq)]
+pub struct Standing {
+    pub name: String,
+    pub rating: f64,
+    pub wins: u32,
+    pub draws: u32,
+    pub losses: u32,
+}
+
+impl Standing {
+    pub const INITIAL_RATING: f64 = 1500.;
+    // Elo K-factor, how far a single game moves a rating
+    const K: f64 = 32.;
+
+    pub fn games(&self) -> u32 {
+        self.wins + self.draws + self.losses
+    }
+
+    /// Elo ratings from playing through the games in order, best first
+    pub fn from_games(games: &[GameEntry]) -> Vec<Self> {
+        let mut standings: Vec<Self> = Vec::new();
+
+        for game in games.iter().filter(|game| game.is_rated()) {
+            let black = Self::index(&mut standings, &game.black);
+            let white = Self::index(&mut standings, &game.white);
+
+            let expected = 1. / (1. + 10_f64.powf((standings[white].rating - standings[black].rating) / 400.));
+            let change = Self::K * (game.black_score() - expected);
+
+            standings[black].rating += change;
+            standings[white].rating -= change;
+
+            match game.outcome.winner {
+                Some(PlayerTurn::Black) => {
+                    standings[black].wins += 1;
+                    standings[white].losses += 1;
+                },
+                Some(PlayerTurn::White) => {
+                    standings[white].wins += 1;
+                    standings[black].losses += 1;
+                },
+                None => {
+                    standings[black].draws += 1;
+                    standings[white].draws += 1;
+                },
+            }
+        }
+
+        standings.sort_by(|a, b| b.rating.total_cmp(&a.rating));
+        standings
+    }
+
+    // position of the name, added at the initial rating when it is new
+    fn index(standings: &mut Vec<Self>, name: &str) -> usize {
+        standings.iter().position(|standing| standing.name == name).unwrap_or_else(|| {
+            standings.push(Self {
+                name: name.to_string(),
+                rating: Self::INITIAL_RATING,
+                wins: 0,
+                draws: 0,
+                losses: 0,
+            });
+            standings.len() - 1
+        })
+    }
+}
+
+/// the first `limit` standings as a text table
+pub fn leaderboard(standings: &[Standing], limit: usize) -> String {
+    if standings.is_empty() {
+        return "no rated games yet".to_string();
+    }
+
+    standings.iter()
+        .take(limit)
+        .enumerate()
+        .map(|(idx, standing)| format!("{:>2}. {:<16} {:>5.0}  {}/{}/{}",
+            idx + 1, standing.name, standing.rating, standing.wins, standing.draws, standing.losses))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn game(black: &str, white: &str, winner: Option<PlayerTurn>) -> GameEntry {
+        GameEntry::new("PvP", black, white, &History::new(), Outcome { winner, reason: Reason::Discs })
+    }
+
+    fn rating(standings: &[Standing], name: &str) -> f64 {
+        standings.iter().find(|standing| standing.name == name).unwrap().rating
+    }
+
+    #[test]
+    fn a_win_between_equals_moves_half_the_k_factor() {
+        let standings = Standing::from_games(&[game("Ann", "Bob", Some(PlayerTurn::Black))]);
+
+        assert_eq!(rating(&standings, "Ann"), Standing::INITIAL_RATING + 16.);
+        assert_eq!(rating(&standings, "Bob"), Standing::INITIAL_RATING - 16.);
+        assert_eq!(standings[0].name, "Ann");
+        assert_eq!((standings[0].wins, standings[1].losses), (1, 1));
+    }
+
+    #[test]
+    fn the_favourite_gains_less_than_it_risks() {
+        let first = game("Ann", "Bob", Some(PlayerTurn::Black));
+        let before = rating(&Standing::from_games(std::slice::from_ref(&first)), "Ann");
+
+        let won = Standing::from_games(&[first.clone(), game("Ann", "Bob", Some(PlayerTurn::Black))]);
+        let lost = Standing::from_games(&[first, game("Ann", "Bob", Some(PlayerTurn::White))]);
+
+        assert!(rating(&won, "Ann") - before < 16.);
+        assert!(before - rating(&lost, "Ann") > 16.);
+        // the points only change hands
+        assert!((rating(&lost, "Ann") + rating(&lost, "Bob") - 2. * Standing::INITIAL_RATING).abs() < 1e-9);
+    }
+
+    #[test]
+    fn draws_between_equals_change_nothing() {
+        let standings = Standing::from_games(&[game("Ann", "Bob", None)]);
+
+        assert_eq!(rating(&standings, "Ann"), Standing::INITIAL_RATING);
+        assert_eq!((standings[0].draws, standings[1].draws), (1, 1));
+    }
+
+    #[test]
+    fn games_against_oneself_are_not_rated() {
+        assert!(Standing::from_games(&[game("Ann", "Ann", Some(PlayerTurn::Black))]).is_empty());
+    }
+
+    #[test]
+    fn entries_read_back_from_their_line() {
+        let entry = GameEntry::new("PvE (MCTS)", "Ann\tSmith", "MCTS", &crate::notation::parse_transcript("f5d6c3d3c4").unwrap(),
+                                   Outcome::resigned(PlayerTurn::White));
+
+        assert_eq!(entry.black, "Ann Smith");
+        assert_eq!(entry.opening, "f5d6c3d3");
+        assert_eq!(GameEntry::parse(&entry.line()), Some(entry));
+    }
+}
@@ -0,0 +1,103 @@
+use crate::board::{move_name, PlayerTurn};
+use crate::engine::analysis::{Classification, GameAnalysis};
+use crate::engine::eval::DISC;
+use crate::outcome::Outcome;
+
+
+const SIDES: [PlayerTurn; 2] = [PlayerTurn::Black, PlayerTurn::White];
+
+fn discs(score: i32) -> String {
+    format!("{:.2}", score as f64 / DISC as f64)
+}
+
+/// The analysis as a JSON document, scores and losses are in discs, see `Classification`
+pub fn json(analysis: &GameAnalysis, outcome: Option<&Outcome>) -> String {
+    let mut out = String::from("{\n");
+
+    if let Some(outcome) = outcome {
+        out.push_str(&format!("  \"result\": \"{}\",\n", outcome.describe()));
+    }
+
+    out.push_str("  \"summary\": {\n");
+    for (idx, side) in SIDES.iter().enumerate() {
+        let counts: Vec<String> = Classification::ALL.iter()
+            .map(|class| format!("\"{}\": {}", class.name(), analysis.count(*side, *class)))
+            .collect();
+
+        out.push_str(&format!("    \"{}\": {{ {} }}", side.name(), counts.join(", ")));
+        out.push_str(if idx + 1 < SIDES.len() { ",\n" } else { "\n" });
+    }
+    out.push_str("  },\n");
+
+    out.push_str("  \"moves\": [\n");
+    for (idx, analysed) in analysis.moves.iter().enumerate() {
+        out.push_str(&format!(
+            "    {{ \"ply\": {}, \"side\": \"{}\", \"move\": \"{}\", \"best\": \"{}\", \"score\": {}, \"best_score\": {}, \"loss\": {}, \"class\": \"{}\" }}",
+            analysed.ply + 1,
+            analysed.side.name(),
+            move_name(analysed.played.0, analysed.played.1),
+            move_name(analysed.best.0, analysed.best.1),
+            discs(analysed.played_score),
+            discs(analysed.best_score),
+            discs(analysed.loss()),
+            analysed.class.name(),
+        ));
+        out.push_str(if idx + 1 < analysis.moves.len() { ",\n" } else { "\n" });
+    }
+    out.push_str("  ]\n}\n");
+
+    out
+}
+
+/// The analysis as a standalone web page
+pub fn html(analysis: &GameAnalysis, outcome: Option<&Outcome>) -> String {
+    let mut out = String::from(concat!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>Reversi game analysis</title>\n",
+        "<style>\n",
+        "body { font-family: sans-serif; background: #009b77; color: #222; }\n",
+        "table { border-collapse: collapse; background: #fff; }\n",
+        "td, th { padding: 2px 10px; border: 1px solid #ccc; text-align: right; }\n",
+        ".inaccuracy { background: #fff3b0; }\n",
+        ".mistake { background: #ffc680; }\n",
+        ".blunder { background: #ff8080; }\n",
+        "</style>\n</head>\n<body>\n<h1>Game analysis</h1>\n",
+    ));
+
+    if let Some(outcome) = outcome {
+        out.push_str(&format!("<p>{}</p>\n", outcome.describe()));
+    }
+
+    out.push_str("<h2>Summary</h2>\n<table>\n<tr><th></th>");
+    for class in Classification::ALL {
+        out.push_str(&format!("<th>{}</th>", class.name()));
+    }
+    out.push_str("</tr>\n");
+
+    for side in SIDES {
+        out.push_str(&format!("<tr><th>{}</th>", side.name()));
+        for class in Classification::ALL {
+            out.push_str(&format!("<td>{}</td>", analysis.count(side, class)));
+        }
+        out.push_str("</tr>\n");
+    }
+    out.push_str("</table>\n");
+
+    out.push_str("<h2>Moves</h2>\n<table>\n<tr><th>ply</th><th>side</th><th>move</th><th>best</th><th>score</th><th>best score</th><th>loss</th><th>class</th></tr>\n");
+    for analysed in &analysis.moves {
+        out.push_str(&format!(
+            "<tr class=\"{}\"><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>\n",
+            analysed.class.name(),
+            analysed.ply + 1,
+            analysed.side.name(),
+            move_name(analysed.played.0, analysed.played.1),
+            move_name(analysed.best.0, analysed.best.1),
+            discs(analysed.played_score),
+            discs(analysed.best_score),
+            discs(analysed.loss()),
+            analysed.class.name(),
+        ));
+    }
+    out.push_str("</table>\n</body>\n</html>\n");
+
+    out
+}
@@ -0,0 +1,265 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use crate::board::Board;
+use crate::clock::{Clock, TimeControl};
+use crate::history::History;
+use crate::mode::GameMode;
+use crate::notation::{board_string, parse_board_string, parse_transcript, transcript};
+use crate::settings::data_dir;
+
+
+/// An unfinished game with everything needed to carry on with it
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SavedGame {
+    pub mode: GameMode,
+    // names the sides are played under
+    pub black: String,
+    pub white: String,
+    // the NBoard engine and the sides it plays, see `Settings::engine_command`
+    pub engine_command: String,
+    pub external_black: bool,
+    pub external_white: bool,
+    // both clocks as they stood after the last move, None for untimed games
+    pub clocks: Option<(Clock, Clock)>,
+    pub history: History,
+}
+
+impl SavedGame {
+    const FILE_NAME: &'static str = "autosave.txt";
+    // first line of every save, bumped when the format changes
+    const HEADER: &'static str = "reversi-druid save 1";
+
+    /// `autosave.txt` in the data directory, see `settings::data_dir`
+    pub fn default_path() -> Option<PathBuf> {
+        Some(data_dir()?.join(Self::FILE_NAME))
+    }
+
+    /// the position the game has reached
+    pub fn board(&self) -> Board {
+        self.history.positions().last().copied().unwrap_or_default()
+    }
+
+    /// "Ann vs Bob, 12 moves"
+    pub fn describe(&self) -> String {
+        format!("{} vs {}, {} moves", self.black, self.white, self.history.len())
+    }
+
+    /// a line per value as "key value", the board is written for readers and checked on loading
+    pub fn to_text(&self) -> String {
+        let mut lines = vec![
+            Self::HEADER.to_string(),
+            format!("mode {}", self.mode.label()),
+            format!("black {}", one_line(&self.black)),
+            format!("white {}", one_line(&self.white)),
+            format!("engine {}", one_line(&self.engine_command)),
+            format!("engine-black {}", on_off(self.external_black)),
+            format!("engine-white {}", on_off(self.external_white)),
+        ];
+
+        if let Some((black, white)) = &self.clocks {
+            lines.push(format!("clock {}", control_text(&black.control)));
+            lines.push(format!("clock-black {}", clock_text(black)));
+            lines.push(format!("clock-white {}", clock_text(white)));
+        }
+
+        lines.push(format!("moves {}", transcript(&self.history)));
+        lines.push(format!("board {}", board_string(&self.board())));
+        lines.join("\n") + "\n"
+    }
+
+    /// reads what `to_text` writes, unknown keys are skipped
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut lines = text.lines();
+
+        if lines.next().map(str::trim) != Some(Self::HEADER) {
+            return Err("not a saved game of this version".to_string());
+        }
+
+        let mut saved = SavedGame {
+            mode: GameMode::PvP,
+            black: String::new(),
+            white: String::new(),
+            engine_command: String::new(),
+            external_black: false,
+            external_white: false,
+            clocks: None,
+            history: History::new(),
+        };
+        let mut control = None;
+        let mut clocks = (None, None);
+        let mut board = None;
+
+        for line in lines {
+            let (key, value) = line.split_once(' ').unwrap_or((line, ""));
+
+            match key {
+                "mode" => {
+                    saved.mode = GameMode::PRESETS.iter()
+                        .find(|(label, _)| *label == value)
+                        .map(|(_, mode)| mode.clone())
+                        .ok_or_else(|| format!("unknown mode '{}'", value))?;
+                },
+                "black" => saved.black = value.to_string(),
+                "white" => saved.white = value.to_string(),
+                "engine" => saved.engine_command = value.to_string(),
+                "engine-black" => saved.external_black = value == "on",
+                "engine-white" => saved.external_white = value == "on",
+                "clock" => control = Some(parse_control(value)?),
+                "clock-black" => clocks.0 = Some(value.to_string()),
+                "clock-white" => clocks.1 = Some(value.to_string()),
+                "moves" => saved.history = parse_transcript(value)?,
+                "board" => board = Some(parse_board_string(value)?),
+                _ => {},
+            }
+        }
+
+        if let (Some(control), (Some(black), Some(white))) = (control, clocks) {
+            saved.clocks = Some((parse_clock(control, &black)?, parse_clock(control, &white)?));
+        }
+
+        if board.is_some_and(|board| board != saved.board()) {
+            return Err("the board doesn't match the moves".to_string());
+        }
+
+        Ok(saved)
+    }
+
+    /// the saved game at the path, None when there is none
+    pub fn load(path: &Path) -> io::Result<Option<Self>> {
+        match fs::read_to_string(path) {
+            Ok(text) => Self::parse(&text)
+                .map(Some)
+                .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err)),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// replaces the file at once, a crash while writing leaves the previous save behind
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+
+        let partial = path.with_extension("part");
+        fs::write(&partial, self.to_text())?;
+        fs::rename(&partial, path)
+    }
+
+    /// forgets the saved game, there being none is fine
+    pub fn remove(path: &Path) -> io::Result<()> {
+        match fs::remove_file(path) {
+            Err(err) if err.kind() != io::ErrorKind::NotFound => Err(err),
+            _ => Ok(()),
+        }
+    }
+}
+
+pub(crate) fn on_off(value: bool) -> &'static str {
+    if value { "on" } else { "off" }
+}
+
+// values must stay on their line
+pub(crate) fn one_line(text: &str) -> String {
+    text.replace(['\n', '\r'], " ")
+}
+
+// durations are kept in milliseconds: "sudden 300000", "fischer 180000 2000",
+// "byoyomi 300000 30000 5"
+pub(crate) fn control_text(control: &TimeControl) -> String {
+    match *control {
+        TimeControl::SuddenDeath { base } => format!("sudden {}", base.as_millis()),
+        TimeControl::Fischer { base, increment } => format!("fischer {} {}", base.as_millis(), increment.as_millis()),
+        TimeControl::ByoYomi { base, period, periods } => format!("byoyomi {} {} {}", base.as_millis(), period.as_millis(), periods),
+    }
+}
+
+pub(crate) fn parse_control(text: &str) -> Result<TimeControl, String> {
+    let parts: Vec<&str> = text.split_whitespace().collect();
+    let millis = |idx: usize| -> Result<Duration, String> {
+        parts.get(idx)
+            .and_then(|part| part.parse().ok())
+            .map(Duration::from_millis)
+            .ok_or_else(|| format!("bad clock '{}'", text))
+    };
+
+    match parts.first() {
+        Some(&"sudden") => Ok(TimeControl::SuddenDeath { base: millis(1)? }),
+        Some(&"fischer") => Ok(TimeControl::Fischer { base: millis(1)?, increment: millis(2)? }),
+        Some(&"byoyomi") => Ok(TimeControl::ByoYomi {
+            base: millis(1)?,
+            period: millis(2)?,
+            periods: parts.get(3).and_then(|part| part.parse().ok()).ok_or_else(|| format!("bad clock '{}'", text))?,
+        }),
+        _ => Err(format!("unknown clock '{}'", text)),
+    }
+}
+
+// remaining and period remaining in milliseconds, then the periods left: "170000 0 0"
+fn clock_text(clock: &Clock) -> String {
+    format!("{} {} {}", clock.remaining.as_millis(), clock.period_remaining.as_millis(), clock.periods)
+}
+
+fn parse_clock(control: TimeControl, text: &str) -> Result<Clock, String> {
+    let parts: Vec<u64> = text.split_whitespace().map(str::parse).collect::<Result<_, _>>()
+        .map_err(|_| format!("bad clock '{}'", text))?;
+
+    let [remaining, period_remaining, periods] = parts[..] else {
+        return Err(format!("bad clock '{}'", text));
+    };
+
+    Ok(Clock {
+        control,
+        remaining: Duration::from_millis(remaining),
+        period_remaining: Duration::from_millis(period_remaining),
+        periods: periods as u32,
+        flagged: false,
+    })
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mode::Opponent;
+
+    fn saved(clocks: Option<(Clock, Clock)>) -> SavedGame {
+        SavedGame {
+            mode: GameMode::PvE(Opponent::Mcts(20_000)),
+            black: "Ann".to_string(),
+            white: "Bob".to_string(),
+            engine_command: "edax -n 1".to_string(),
+            external_black: false,
+            external_white: true,
+            clocks,
+            history: parse_transcript("f5d6c3d3c4").unwrap(),
+        }
+    }
+
+    #[test]
+    fn games_read_back_from_their_text() {
+        let control = TimeControl::PRESETS[2];
+        let mut white = Clock::new(control);
+        white.tick(Duration::from_secs(301));
+
+        for game in [saved(None), saved(Some((Clock::new(control), white)))] {
+            assert_eq!(SavedGame::parse(&game.to_text()), Ok(game));
+        }
+    }
+
+    #[test]
+    fn the_board_has_to_match_the_moves() {
+        let text = saved(None).to_text().replace("moves f5d6c3d3c4", "moves f5d6c3");
+        assert!(SavedGame::parse(&text).is_err());
+    }
+
+    #[test]
+    fn other_files_are_turned_down() {
+        assert!(SavedGame::parse("reversi-druid save 0\nmoves f5\n").is_err());
+        assert!(SavedGame::parse("").is_err());
+        assert!(SavedGame::parse("reversi-druid save 1\nmode PvE (4)\n").is_err());
+    }
+}
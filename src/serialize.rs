@@ -0,0 +1,73 @@
+//! `serde` support, built with the "serde" feature. Most types derive it where they are
+//! defined; the ones here are written by hand for a representation that stays readable and
+//! stable: a board is its board string, a cell a single character and a history the transcript
+//! of its moves, see `notation`. Durations are whole milliseconds.
+
+use serde::de::Error;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use crate::board::{Board, Cell};
+use crate::history::History;
+use crate::notation::{board_string, parse_board_string, parse_transcript, transcript};
+
+
+/// "---------------------------OX------XO--------------------------- X"
+impl Serialize for Board {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&board_string(self))
+    }
+}
+
+impl<'de> Deserialize<'de> for Board {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        parse_board_string(&String::deserialize(deserializer)?).map_err(D::Error::custom)
+    }
+}
+
+/// "X" black, "O" white and "-" empty, the flip animation isn't kept
+impl Serialize for Cell {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_char(match self {
+            Cell::Black(_) => 'X',
+            Cell::White(_) => 'O',
+            Cell::Free => '-',
+        })
+    }
+}
+
+impl<'de> Deserialize<'de> for Cell {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        match char::deserialize(deserializer)? {
+            'X' => Ok(Cell::Black(0)),
+            'O' => Ok(Cell::White(0)),
+            '-' => Ok(Cell::Free),
+            chr => Err(D::Error::custom(format!("unknown square '{}'", chr))),
+        }
+    }
+}
+
+/// "f5d6c3", passes and flipped discs follow from the moves
+impl Serialize for History {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&transcript(self))
+    }
+}
+
+impl<'de> Deserialize<'de> for History {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        parse_transcript(&String::deserialize(deserializer)?).map_err(D::Error::custom)
+    }
+}
+
+/// `#[serde(with = "crate::serialize::millis")]` for `Duration` fields
+pub mod millis {
+    use std::time::Duration;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u64(duration.as_millis() as u64)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Duration, D::Error> {
+        u64::deserialize(deserializer).map(Duration::from_millis)
+    }
+}
@@ -0,0 +1,398 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use crate::board::PlayerTurn;
+use crate::clock::TimeControl;
+use crate::mode::GameMode;
+use crate::net::protocol::DEFAULT_PORT;
+use crate::save::{control_text, on_off, one_line, parse_control};
+
+/// Preferences that outlive a single game: restarting or switching the mode keeps them, and
+/// the game window keeps them in a settings file between runs.
+#[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "gui", derive(druid::Data, druid::Lens))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Settings {
+    // threads the alpha-beta engine searches with
+    pub threads: usize,
+    // clock every new game starts with, None for untimed games
+    #[cfg_attr(feature = "gui", data(same_fn = "PartialEq::eq"))]
+    pub time_control: Option<TimeControl>,
+    // hints show the variation after the best move as well
+    pub hint_pv: bool,
+    // every legal move of the side to move is marked on the board
+    pub show_legal_moves: bool,
+    // every legal move carries the engine's score for it
+    pub show_move_scores: bool,
+    // how long the last move and the discs it flipped stay marked, zero switches it off
+    #[cfg_attr(feature = "gui", data(same_fn = "PartialEq::eq"))]
+    #[cfg_attr(feature = "serde", serde(with = "crate::serialize::millis"))]
+    pub highlight_time: Duration,
+    // program and arguments of an NBoard engine, see `engine::external`
+    pub engine_command: String,
+    // sides the engine plays instead of the player or the PvE opponent
+    pub external_black: bool,
+    pub external_white: bool,
+    // names the players at the board are rated under
+    pub black_name: String,
+    pub white_name: String,
+    // host and port a networked game is joined at, the port is also the one hosted on
+    pub net_address: String,
+    // name of the theme, see `theme::Theme::load_all` in the game window
+    pub theme: String,
+    // how the board and the discs are drawn
+    pub renderer: Renderer,
+    // time between two frames of the flip animation
+    #[cfg_attr(feature = "gui", data(same_fn = "PartialEq::eq"))]
+    #[cfg_attr(feature = "serde", serde(with = "crate::serialize::millis"))]
+    pub animation_step: Duration,
+    // moves and the end of the game are heard
+    pub sounds: bool,
+    // mode, and so difficulty, the game window opens with and "Restart" starts
+    pub default_mode: GameMode,
+    // size the game window opens with
+    #[cfg_attr(feature = "gui", data(same_fn = "PartialEq::eq"))]
+    pub window_size: (f64, f64),
+}
+
+impl Settings {
+    pub const MAX_THREADS: usize = 64;
+
+    pub const HIGHLIGHT_TIMES: [Duration; 4] = [
+        Duration::ZERO,
+        Duration::from_secs(1),
+        Duration::from_secs(3),
+        Duration::from_secs(10),
+    ];
+
+    /// frame times of the flip animation by name, quickest first
+    pub const ANIMATION_STEPS: [(&'static str, Duration); 4] = [
+        ("off", Duration::ZERO),
+        ("fast", Duration::from_millis(40)),
+        ("normal", Duration::from_millis(80)),
+        ("slow", Duration::from_millis(160)),
+    ];
+
+    pub const WINDOW_SIZE: (f64, f64) = (800., 600.);
+    // the smallest window the board still fits in
+    pub const MIN_WINDOW_SIZE: (f64, f64) = (560., 420.);
+
+    // the built-in theme the game window starts with, themes themselves need the "gui" feature
+    pub const DEFAULT_THEME: &'static str = "green";
+
+    /// name of `animation_step` among `ANIMATION_STEPS`
+    pub fn animation_name(&self) -> &'static str {
+        Self::ANIMATION_STEPS.iter()
+            .find(|(_, step)| *step == self.animation_step)
+            .map_or("custom", |(name, _)| name)
+    }
+
+    pub fn is_external(&self, side: PlayerTurn) -> bool {
+        match side {
+            PlayerTurn::Black => self.external_black,
+            PlayerTurn::White => self.external_white,
+        }
+    }
+
+    /// port given with `net_address`, the default one without
+    pub fn net_port(&self) -> u16 {
+        self.net_address.rsplit_once(':')
+            .and_then(|(_, port)| port.parse().ok())
+            .unwrap_or(DEFAULT_PORT)
+    }
+
+    pub fn player_name(&self, side: PlayerTurn) -> &str {
+        match side {
+            PlayerTurn::Black => &self.black_name,
+            PlayerTurn::White => &self.white_name,
+        }
+    }
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            threads: std::thread::available_parallelism().map_or(1, |threads| threads.get()),
+            time_control: None,
+            hint_pv: false,
+            show_legal_moves: false,
+            show_move_scores: false,
+            highlight_time: Duration::from_secs(3),
+            engine_command: String::new(),
+            external_black: false,
+            external_white: false,
+            black_name: "Player 1".to_string(),
+            white_name: "Player 2".to_string(),
+            net_address: format!("127.0.0.1:{}", DEFAULT_PORT),
+            theme: Self::DEFAULT_THEME.to_string(),
+            renderer: Renderer::Sprites,
+            animation_step: Duration::from_millis(80),
+            sounds: false,
+            default_mode: GameMode::PvP,
+            window_size: Self::WINDOW_SIZE,
+        }
+    }
+}
+
+impl Settings {
+    const FILE_NAME: &'static str = "settings.txt";
+    // first line of the settings file, bumped when the format changes
+    const HEADER: &'static str = "reversi-druid settings 1";
+
+    /// `settings.txt` in the config directory, see `config_dir`
+    pub fn default_path() -> Option<PathBuf> {
+        Some(config_dir()?.join(Self::FILE_NAME))
+    }
+
+    /// a line per setting as "key value". The engine sides are left out, a new run starts
+    /// without engine processes.
+    pub fn to_text(&self) -> String {
+        let clock = match &self.time_control {
+            Some(control) => control_text(control),
+            None => "off".to_string(),
+        };
+
+        [
+            Self::HEADER.to_string(),
+            format!("theme {}", one_line(&self.theme)),
+            format!("renderer {}", self.renderer.name()),
+            format!("animation {}", self.animation_step.as_millis()),
+            format!("sounds {}", on_off(self.sounds)),
+            format!("mode {}", self.default_mode.label()),
+            format!("threads {}", self.threads),
+            format!("clock {}", clock),
+            format!("hint-pv {}", on_off(self.hint_pv)),
+            format!("legal-moves {}", on_off(self.show_legal_moves)),
+            format!("move-scores {}", on_off(self.show_move_scores)),
+            format!("highlight {}", self.highlight_time.as_millis()),
+            format!("engine {}", one_line(&self.engine_command)),
+            format!("black {}", one_line(&self.black_name)),
+            format!("white {}", one_line(&self.white_name)),
+            format!("address {}", one_line(&self.net_address)),
+            format!("window {} {}", self.window_size.0, self.window_size.1),
+        ].join("\n") + "\n"
+    }
+
+    /// reads what `to_text` writes. Missing settings keep their defaults, so do unknown keys
+    /// and values that don't read, a hand edited file shouldn't lose the rest.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut lines = text.lines();
+
+        if lines.next().map(str::trim) != Some(Self::HEADER) {
+            return Err("not a settings file of this version".to_string());
+        }
+
+        let mut settings = Self::default();
+
+        for line in lines {
+            let (key, value) = line.split_once(' ').unwrap_or((line, ""));
+            let on = value == "on";
+            let millis = || value.parse().ok().map(Duration::from_millis);
+
+            match key {
+                "theme" => settings.theme = value.to_string(),
+                "renderer" => settings.renderer = Renderer::from_name(value).unwrap_or(settings.renderer),
+                "animation" => settings.animation_step = millis().unwrap_or(settings.animation_step),
+                "sounds" => settings.sounds = on,
+                "mode" => {
+                    if let Some((_, mode)) = GameMode::PRESETS.iter().find(|(label, _)| *label == value) {
+                        settings.default_mode = mode.clone();
+                    }
+                },
+                "threads" => {
+                    if let Ok(threads) = value.parse::<usize>() {
+                        settings.threads = threads.clamp(1, Self::MAX_THREADS);
+                    }
+                },
+                "clock" => {
+                    settings.time_control = match value {
+                        "off" => None,
+                        _ => parse_control(value).ok().or(settings.time_control),
+                    };
+                },
+                "hint-pv" => settings.hint_pv = on,
+                "legal-moves" => settings.show_legal_moves = on,
+                "move-scores" => settings.show_move_scores = on,
+                "highlight" => settings.highlight_time = millis().unwrap_or(settings.highlight_time),
+                "engine" => settings.engine_command = value.to_string(),
+                "black" => settings.black_name = value.to_string(),
+                "white" => settings.white_name = value.to_string(),
+                "address" => settings.net_address = value.to_string(),
+                "window" => {
+                    let size: Vec<f64> = value.split_whitespace().filter_map(|part| part.parse().ok()).collect();
+                    // NaN and infinite sizes are dropped, small ones grow to the smallest window
+                    if let [width, height] = size[..] {
+                        if width.is_finite() && height.is_finite() {
+                            settings.window_size = (width.max(Self::MIN_WINDOW_SIZE.0), height.max(Self::MIN_WINDOW_SIZE.1));
+                        }
+                    }
+                },
+                _ => {},
+            }
+        }
+
+        Ok(settings)
+    }
+
+    /// the settings at the path, None when there is no file yet
+    pub fn load(path: &Path) -> io::Result<Option<Self>> {
+        match fs::read_to_string(path) {
+            Ok(text) => Self::parse(&text)
+                .map(Some)
+                .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err)),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// the settings at the default path, the defaults when there are none or they can't be read
+    pub fn load_default() -> Self {
+        let Some(path) = Self::default_path() else {
+            return Self::default();
+        };
+
+        Self::load(&path).unwrap_or_else(|err| {
+            eprintln!("failed to read the settings from {}: {}", path.display(), err);
+            None
+        }).unwrap_or_default()
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+
+        fs::write(path, self.to_text())
+    }
+}
+
+/// How the game window draws the board and the discs
+#[derive(Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(feature = "gui", derive(druid::Data))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum Renderer {
+    // the PNG images of the discs, resampled to the cell size
+    Sprites,
+    // discs and board drawn as shapes with gradients, with coordinates around the board
+    Vector,
+}
+
+impl Renderer {
+    pub const ALL: [Renderer; 2] = [Renderer::Sprites, Renderer::Vector];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Renderer::Sprites => "sprites",
+            Renderer::Vector => "vector",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|renderer| renderer.name() == name)
+    }
+}
+
+/// Where the settings file is kept: `$XDG_CONFIG_HOME/reversi-druid`, under `~/.config` when
+/// the variable isn't set
+pub fn config_dir() -> Option<PathBuf> {
+    let config = std::env::var_os("XDG_CONFIG_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| Path::new(&home).join(".config")))
+        .or_else(|| std::env::var_os("APPDATA").map(PathBuf::from))?;
+
+    Some(config.join("reversi-druid"))
+}
+
+/// Where the app keeps the files it writes: `$XDG_DATA_HOME/reversi-druid`, under
+/// `~/.local/share` when the variable isn't set
+pub fn data_dir() -> Option<PathBuf> {
+    let data = std::env::var_os("XDG_DATA_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| Path::new(&home).join(".local").join("share")))
+        .or_else(|| std::env::var_os("APPDATA").map(PathBuf::from))?;
+
+    Some(data.join("reversi-druid"))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mode::Opponent;
+
+    #[test]
+    fn settings_read_back_from_their_text() {
+        let settings = Settings {
+            threads: 3,
+            time_control: Some(TimeControl::PRESETS[1]),
+            hint_pv: true,
+            show_legal_moves: true,
+            show_move_scores: true,
+            highlight_time: Duration::from_secs(10),
+            engine_command: "edax -n 1".to_string(),
+            black_name: "Ann".to_string(),
+            white_name: "Bob Smith".to_string(),
+            net_address: "example.org:9000".to_string(),
+            theme: "blue".to_string(),
+            renderer: Renderer::Vector,
+            animation_step: Duration::from_millis(40),
+            sounds: true,
+            default_mode: GameMode::PvE(Opponent::AlphaBeta(8)),
+            window_size: (1024., 768.),
+            ..Settings::default()
+        };
+
+        assert_eq!(Settings::parse(&settings.to_text()), Ok(settings));
+    }
+
+    #[test]
+    fn the_engine_sides_are_not_kept() {
+        let settings = Settings { external_black: true, external_white: true, ..Settings::default() };
+        let read = Settings::parse(&settings.to_text()).unwrap();
+        assert!(!read.external_black && !read.external_white);
+    }
+
+    #[test]
+    fn values_that_dont_read_keep_the_defaults() {
+        let text = "reversi-druid settings 1\nthreads many\nrenderer crayons\nclock forever\nmode PvE (9)\n\
+                    animation soon\nwindow wide\nfrobnicate on\nblack Ann\n";
+        let settings = Settings::parse(text).unwrap();
+        let defaults = Settings::default();
+
+        assert_eq!(settings.threads, defaults.threads);
+        assert_eq!(settings.renderer, defaults.renderer);
+        assert_eq!(settings.time_control, defaults.time_control);
+        assert_eq!(settings.default_mode, defaults.default_mode);
+        assert_eq!(settings.animation_step, defaults.animation_step);
+        assert_eq!(settings.window_size, defaults.window_size);
+        // the rest of the file is still read
+        assert_eq!(settings.black_name, "Ann");
+    }
+
+    #[test]
+    fn windows_are_at_least_the_smallest_size() {
+        let window = |value: &str| Settings::parse(&format!("reversi-druid settings 1\nwindow {}\n", value)).unwrap().window_size;
+
+        assert_eq!(window("-800 10"), Settings::MIN_WINDOW_SIZE);
+        assert_eq!(window("1024 100"), (1024., Settings::MIN_WINDOW_SIZE.1));
+        for broken in ["NaN 600", "800 inf", "-inf -inf"] {
+            assert_eq!(window(broken), Settings::WINDOW_SIZE, "{}", broken);
+        }
+    }
+
+    #[test]
+    fn threads_stay_in_range() {
+        assert_eq!(Settings::parse("reversi-druid settings 1\nthreads 0\n").unwrap().threads, 1);
+        assert_eq!(Settings::parse("reversi-druid settings 1\nthreads 1000\n").unwrap().threads, Settings::MAX_THREADS);
+    }
+
+    #[test]
+    fn other_files_are_turned_down() {
+        assert!(Settings::parse("reversi-druid theme 1\n").is_err());
+        assert!(Settings::parse("").is_err());
+    }
+}
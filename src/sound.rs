@@ -0,0 +1,69 @@
+#[cfg(feature = "sound")]
+use std::time::Duration;
+
+
+/// What the game window plays a sound for
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Sound {
+    // a disc was placed
+    Move,
+    // a side had to pass
+    Pass,
+    GameOver,
+}
+
+impl Sound {
+    // pitch in Hz and length of the tone
+    #[cfg(feature = "sound")]
+    fn tone(self) -> (f32, Duration) {
+        match self {
+            Sound::Move => (660., Duration::from_millis(40)),
+            Sound::Pass => (440., Duration::from_millis(120)),
+            Sound::GameOver => (880., Duration::from_millis(300)),
+        }
+    }
+}
+
+/// The sound output, there is none without the "sound" feature
+#[cfg(feature = "sound")]
+pub struct Sounds {
+    // the output stops when the stream is dropped
+    _stream: rodio::OutputStream,
+    handle: rodio::OutputStreamHandle,
+}
+
+#[cfg(not(feature = "sound"))]
+pub struct Sounds;
+
+impl Sounds {
+    /// the default output device, None when there is none to play on
+    #[cfg(feature = "sound")]
+    pub fn open() -> Option<Self> {
+        let (stream, handle) = rodio::OutputStream::try_default()
+            .map_err(|err| eprintln!("no sound output: {}", err))
+            .ok()?;
+
+        Some(Self { _stream: stream, handle })
+    }
+
+    #[cfg(not(feature = "sound"))]
+    pub fn open() -> Option<Self> {
+        None
+    }
+
+    /// plays the sound without waiting for it to end
+    #[cfg(feature = "sound")]
+    pub fn play(&self, sound: Sound) {
+        use rodio::Source;
+
+        let (pitch, length) = sound.tone();
+        let tone = rodio::source::SineWave::new(pitch).take_duration(length).amplify(0.2);
+
+        if let Err(err) = self.handle.play_raw(tone) {
+            eprintln!("failed to play a sound: {}", err);
+        }
+    }
+
+    #[cfg(not(feature = "sound"))]
+    pub fn play(&self, _sound: Sound) {}
+}
@@ -0,0 +1,396 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use druid::Color;
+use crate::board::PlayerTurn;
+use crate::save::one_line;
+use crate::settings::{config_dir, Settings};
+
+
+/// Colors, fonts and disc images the game window is drawn with
+#[derive(Clone, Debug, PartialEq)]
+pub struct Theme {
+    pub name: String,
+    // the window, and the charts of the victory screen
+    pub background: Color,
+    // felt of the vector board, lit at the top left and shaded at the bottom right
+    pub board: Color,
+    pub board_shade: Color,
+    // grid lines and the frame of the vector board
+    pub grid: Color,
+    // the coordinates around the vector board
+    pub label: Color,
+    // vector discs, lit and shaded, the shaded color is their rim as well
+    pub black_disc: (Color, Color),
+    pub white_disc: (Color, Color),
+    // PNG images replacing the built-in sprites of the discs
+    pub black_sprite: Option<PathBuf>,
+    pub white_sprite: Option<PathBuf>,
+    // font family of the board's text and the side panel, None for the defaults
+    pub font: Option<String>,
+    // marks on the board
+    pub hint: Color,
+    pub last_move: Color,
+    pub flipped: Color,
+    pub best_score: Color,
+    pub invalid: Color,
+    // legal moves of either side and their scores
+    pub legal_black: Color,
+    pub legal_white: Color,
+    // scores and clocks of either side, and their disc counts in the charts
+    pub score_black: Color,
+    pub score_white: Color,
+    // frames and middle lines of the charts and the evaluation bar
+    pub chart: Color,
+    // the evaluation in the charts
+    pub eval: Color,
+    // either side's share of the evaluation bar
+    pub eval_black: Color,
+    pub eval_white: Color,
+}
+
+impl Theme {
+    pub const DEFAULT_NAME: &'static str = Settings::DEFAULT_THEME;
+
+    const EXTENSION: &'static str = "theme";
+    // first line of every theme file, bumped when the format changes
+    const HEADER: &'static str = "reversi-druid theme 1";
+
+    /// the themes that come with the game, the first one is the default
+    pub fn built_in() -> Vec<Theme> {
+        let green = Theme::default();
+
+        let blue = Theme {
+            name: "blue".to_string(),
+            background: Color::rgba8(40, 90, 150, 255),
+            board: Color::rgba8(52, 112, 182, 255),
+            board_shade: Color::rgba8(30, 72, 124, 255),
+            ..green.clone()
+        };
+
+        let dark = Theme {
+            name: "dark".to_string(),
+            background: Color::rgba8(40, 44, 52, 255),
+            board: Color::rgba8(62, 68, 80, 255),
+            board_shade: Color::rgba8(34, 38, 46, 255),
+            grid: Color::rgba8(120, 128, 140, 255),
+            label: Color::rgba8(180, 186, 196, 255),
+            ..green.clone()
+        };
+
+        // strong colors that tell every mark apart
+        let high_contrast = Theme {
+            name: "high-contrast".to_string(),
+            background: Color::BLACK,
+            board: Color::rgba8(0, 90, 0, 255),
+            board_shade: Color::rgba8(0, 60, 0, 255),
+            grid: Color::rgba8(255, 255, 0, 255),
+            label: Color::WHITE,
+            black_disc: (Color::rgba8(70, 70, 70, 255), Color::BLACK),
+            white_disc: (Color::WHITE, Color::rgba8(225, 225, 225, 255)),
+            hint: Color::rgba8(0, 255, 255, 255),
+            last_move: Color::rgba8(255, 0, 255, 255),
+            flipped: Color::rgba8(255, 0, 255, 255),
+            best_score: Color::rgba8(0, 255, 0, 255),
+            invalid: Color::rgba8(255, 0, 0, 255),
+            legal_black: Color::rgba8(255, 140, 0, 255),
+            legal_white: Color::rgba8(0, 255, 255, 255),
+            score_black: Color::rgba8(255, 140, 0, 255),
+            score_white: Color::WHITE,
+            chart: Color::WHITE,
+            eval: Color::rgba8(255, 255, 0, 255),
+            eval_black: Color::rgba8(70, 70, 70, 255),
+            eval_white: Color::WHITE,
+            ..green.clone()
+        };
+
+        vec![green, blue, dark, high_contrast]
+    }
+
+    /// `themes` in the config directory, see `settings::config_dir`
+    pub fn dir() -> Option<PathBuf> {
+        Some(config_dir()?.join("themes"))
+    }
+
+    /// the built-in themes and the `.theme` files of the directory, a file replaces the
+    /// built-in theme of its name. Files that don't read are reported and skipped.
+    pub fn load_all(dir: Option<&Path>) -> Vec<Theme> {
+        let mut themes = Self::built_in();
+
+        let Some(entries) = dir.and_then(|dir| fs::read_dir(dir).ok()) else {
+            return themes;
+        };
+
+        let mut paths: Vec<PathBuf> = entries
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.extension().is_some_and(|ext| ext == Self::EXTENSION))
+            .collect();
+        paths.sort();
+
+        for path in paths {
+            match Self::load(&path) {
+                Ok(theme) => match themes.iter_mut().find(|known| known.name == theme.name) {
+                    Some(known) => *known = theme,
+                    None => themes.push(theme),
+                },
+                Err(err) => eprintln!("failed to read the theme {}: {}", path.display(), err),
+            }
+        }
+
+        themes
+    }
+
+    /// the theme of a file, named after the file unless it names itself. Sprite paths are
+    /// taken relative to the file.
+    pub fn load(path: &Path) -> io::Result<Theme> {
+        let text = fs::read_to_string(path)?;
+        let mut theme = Self::parse(&text).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+
+        if !text.lines().any(|line| line.starts_with("name ")) {
+            if let Some(stem) = path.file_stem() {
+                theme.name = stem.to_string_lossy().into_owned();
+            }
+        }
+
+        if let Some(dir) = path.parent() {
+            for sprite in [&mut theme.black_sprite, &mut theme.white_sprite].into_iter().flatten() {
+                *sprite = dir.join(&*sprite);
+            }
+        }
+
+        Ok(theme)
+    }
+
+    /// reads what `to_text` writes. Settings left out keep the default theme's, or those of the
+    /// built-in theme named by a "base" line, which goes before the settings it keeps.
+    pub fn parse(text: &str) -> Result<Theme, String> {
+        let mut lines = text.lines();
+
+        if lines.next().map(str::trim) != Some(Self::HEADER) {
+            return Err("not a theme of this version".to_string());
+        }
+
+        let mut theme = Theme::default();
+
+        for line in lines.map(str::trim).filter(|line| !line.is_empty() && !line.starts_with('#')) {
+            let (key, value) = line.split_once(' ').unwrap_or((line, ""));
+            let value = value.trim();
+            let color = || parse_color(value);
+            let pair = || {
+                let (lit, shaded) = value.split_once(' ').ok_or_else(|| format!("'{}' needs two colors", key))?;
+                Ok::<_, String>((parse_color(lit)?, parse_color(shaded.trim())?))
+            };
+
+            match key {
+                "name" => theme.name = value.to_string(),
+                "base" => {
+                    let base = Self::built_in().into_iter()
+                        .find(|base| base.name == value)
+                        .ok_or_else(|| format!("no built-in theme '{}'", value))?;
+                    theme = Theme { name: theme.name, ..base };
+                },
+                "background" => theme.background = color()?,
+                "board" => theme.board = color()?,
+                "board-shade" => theme.board_shade = color()?,
+                "grid" => theme.grid = color()?,
+                "label" => theme.label = color()?,
+                "black-disc" => theme.black_disc = pair()?,
+                "white-disc" => theme.white_disc = pair()?,
+                "black-sprite" => theme.black_sprite = Some(PathBuf::from(value)),
+                "white-sprite" => theme.white_sprite = Some(PathBuf::from(value)),
+                "font" => theme.font = Some(value.to_string()).filter(|font| !font.is_empty()),
+                "hint" => theme.hint = color()?,
+                "last-move" => theme.last_move = color()?,
+                "flipped" => theme.flipped = color()?,
+                "best-score" => theme.best_score = color()?,
+                "invalid" => theme.invalid = color()?,
+                "legal-black" => theme.legal_black = color()?,
+                "legal-white" => theme.legal_white = color()?,
+                "score-black" => theme.score_black = color()?,
+                "score-white" => theme.score_white = color()?,
+                "chart" => theme.chart = color()?,
+                "eval" => theme.eval = color()?,
+                "eval-black" => theme.eval_black = color()?,
+                "eval-white" => theme.eval_white = color()?,
+                _ => return Err(format!("unknown setting '{}'", key)),
+            }
+        }
+
+        Ok(theme)
+    }
+
+    /// a line per setting as "key value", colors as #rrggbbaa
+    pub fn to_text(&self) -> String {
+        let mut lines = vec![
+            Self::HEADER.to_string(),
+            format!("name {}", one_line(&self.name)),
+            format!("background {}", color_text(&self.background)),
+            format!("board {}", color_text(&self.board)),
+            format!("board-shade {}", color_text(&self.board_shade)),
+            format!("grid {}", color_text(&self.grid)),
+            format!("label {}", color_text(&self.label)),
+            format!("black-disc {} {}", color_text(&self.black_disc.0), color_text(&self.black_disc.1)),
+            format!("white-disc {} {}", color_text(&self.white_disc.0), color_text(&self.white_disc.1)),
+        ];
+
+        if let Some(path) = &self.black_sprite {
+            lines.push(format!("black-sprite {}", path.display()));
+        }
+        if let Some(path) = &self.white_sprite {
+            lines.push(format!("white-sprite {}", path.display()));
+        }
+        if let Some(font) = &self.font {
+            lines.push(format!("font {}", one_line(font)));
+        }
+
+        lines.extend([
+            format!("hint {}", color_text(&self.hint)),
+            format!("last-move {}", color_text(&self.last_move)),
+            format!("flipped {}", color_text(&self.flipped)),
+            format!("best-score {}", color_text(&self.best_score)),
+            format!("invalid {}", color_text(&self.invalid)),
+            format!("legal-black {}", color_text(&self.legal_black)),
+            format!("legal-white {}", color_text(&self.legal_white)),
+            format!("score-black {}", color_text(&self.score_black)),
+            format!("score-white {}", color_text(&self.score_white)),
+            format!("chart {}", color_text(&self.chart)),
+            format!("eval {}", color_text(&self.eval)),
+            format!("eval-black {}", color_text(&self.eval_black)),
+            format!("eval-white {}", color_text(&self.eval_white)),
+        ]);
+        lines.join("\n") + "\n"
+    }
+
+    /// the color legal moves of the side are marked with
+    pub fn legal(&self, side: PlayerTurn) -> &Color {
+        match side {
+            PlayerTurn::Black => &self.legal_black,
+            PlayerTurn::White => &self.legal_white,
+        }
+    }
+
+    /// the color the side's score, clock and disc count are drawn in
+    pub fn score(&self, side: PlayerTurn) -> &Color {
+        match side {
+            PlayerTurn::Black => &self.score_black,
+            PlayerTurn::White => &self.score_white,
+        }
+    }
+
+    /// the color of the side's share of the evaluation bar
+    pub fn eval_bar(&self, side: PlayerTurn) -> &Color {
+        match side {
+            PlayerTurn::Black => &self.eval_black,
+            PlayerTurn::White => &self.eval_white,
+        }
+    }
+}
+
+impl Default for Theme {
+    // the green board the game has always had
+    fn default() -> Self {
+        Self {
+            name: Self::DEFAULT_NAME.to_string(),
+            background: Color::rgba8(0, 155, 119, 255),
+            board: Color::rgba8(0, 172, 132, 255),
+            board_shade: Color::rgba8(0, 118, 90, 255),
+            grid: Color::rgba8(192, 192, 192, 255),
+            label: Color::rgba8(235, 235, 235, 255),
+            black_disc: (Color::rgba8(96, 96, 96, 255), Color::rgba8(8, 8, 8, 255)),
+            white_disc: (Color::WHITE, Color::rgba8(186, 186, 186, 255)),
+            black_sprite: None,
+            white_sprite: None,
+            font: None,
+            hint: Color::rgba8(255, 215, 0, 255),
+            last_move: Color::rgba8(255, 69, 0, 255),
+            flipped: Color::rgba8(255, 69, 0, 255 / 2),
+            best_score: Color::rgba8(127, 255, 0, 255),
+            invalid: Color::rgba8(255, 0, 0, 255 / 2),
+            legal_black: Color::rgba8(0, 0, 0, 255 / 2),
+            legal_white: Color::rgba8(255, 255, 255, 255 / 2),
+            score_black: Color::BLACK,
+            score_white: Color::WHITE,
+            chart: Color::rgba8(192, 192, 192, 255),
+            eval: Color::rgba8(255, 215, 0, 255),
+            eval_black: Color::BLACK,
+            eval_white: Color::WHITE,
+        }
+    }
+}
+
+fn color_text(color: &Color) -> String {
+    let (r, g, b, a) = color.as_rgba8();
+    format!("#{:02x}{:02x}{:02x}{:02x}", r, g, b, a)
+}
+
+// "#rrggbb" or "#rrggbbaa"
+fn parse_color(text: &str) -> Result<Color, String> {
+    let hex = text.strip_prefix('#').filter(|hex| hex.len() == 6 || hex.len() == 8)
+        .ok_or_else(|| format!("bad color '{}'", text))?;
+
+    let channels: Vec<u8> = (0..hex.len()).step_by(2)
+        .map(|idx| hex.get(idx..idx + 2).and_then(|channel| u8::from_str_radix(channel, 16).ok()))
+        .collect::<Option<_>>()
+        .ok_or_else(|| format!("bad color '{}'", text))?;
+
+    Ok(Color::rgba8(channels[0], channels[1], channels[2], channels.get(3).copied().unwrap_or(255)))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn themes_read_back_from_their_text() {
+        for theme in Theme::built_in() {
+            assert_eq!(Theme::parse(&theme.to_text()), Ok(theme));
+        }
+
+        let custom = Theme {
+            name: "night".to_string(),
+            black_sprite: Some(PathBuf::from("black.png")),
+            font: Some("Consolas".to_string()),
+            ..Theme::default()
+        };
+        assert_eq!(Theme::parse(&custom.to_text()), Ok(custom));
+    }
+
+    #[test]
+    fn a_base_gives_what_the_file_leaves_out() {
+        let blue = Theme::built_in().into_iter().find(|theme| theme.name == "blue").unwrap();
+        let theme = Theme::parse("reversi-druid theme 1\nname mine\nbase blue\n# a comment\ngrid #102030\n").unwrap();
+
+        assert_eq!(theme.name, "mine");
+        assert_eq!(theme.board, blue.board);
+        assert_eq!(theme.grid, Color::rgba8(16, 32, 48, 255));
+    }
+
+    #[test]
+    fn scores_stand_out_from_the_background() {
+        for theme in Theme::built_in() {
+            for side in [PlayerTurn::Black, PlayerTurn::White] {
+                assert_ne!(theme.score(side), &theme.background, "{} {:?}", theme.name, side);
+            }
+        }
+    }
+
+    #[test]
+    fn colors_have_six_or_eight_digits() {
+        assert_eq!(parse_color("#ff8000"), Ok(Color::rgba8(255, 128, 0, 255)));
+        assert_eq!(parse_color("#ff800080"), Ok(Color::rgba8(255, 128, 0, 128)));
+
+        for text in ["ff8000", "#ff80", "#ff80000", "#gg8000", "#ff8000801", ""] {
+            assert!(parse_color(text).is_err(), "'{}' reads", text);
+        }
+    }
+
+    #[test]
+    fn mistakes_are_reported() {
+        assert!(Theme::parse("reversi-druid theme 1\nboard green\n").is_err());
+        assert!(Theme::parse("reversi-druid theme 1\nblack-disc #000000\n").is_err());
+        assert!(Theme::parse("reversi-druid theme 1\nbase plaid\n").is_err());
+        assert!(Theme::parse("reversi-druid theme 1\nsparkle on\n").is_err());
+        assert!(Theme::parse("reversi-druid settings 1\n").is_err());
+    }
+}
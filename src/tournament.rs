@@ -0,0 +1,306 @@
+use std::cell::RefCell;
+use std::fmt::Write;
+use std::rc::Rc;
+use crate::board::{Board, PlayerTurn};
+use crate::engine::external::ExternalEngine;
+use crate::engine::Player;
+use crate::history::History;
+use crate::mode::Opponent;
+use crate::notation::{ggf_with_outcome, parse_transcript};
+use crate::outcome::Outcome;
+use crate::settings::Settings;
+
+
+/// Short openings the engine scores within about half a disc either way, so neither color
+/// starts from a won position
+pub const OPENINGS: [&str; 8] = [
+    "f5d6c3d3c4f4",
+    "f5f6e6f4c3",
+    "f5f6e6f4e3",
+    "f5d6c3d3c4f4c5b3c2",
+    "f5f6e6f4g5",
+    "f5d6c5f4d3",
+    "f5f6e6f4e3c5",
+    "f5d6c3d3c4b3",
+];
+
+/// One participant of a tournament
+#[derive(Clone, Debug, PartialEq)]
+pub enum Entrant {
+    // built-in opponent by its name, see `Opponent::NAMES`
+    Builtin(String),
+    // NBoard engine started from the command, under the given name
+    External { name: String, command: String },
+}
+
+impl Entrant {
+    pub fn name(&self) -> &str {
+        match self {
+            Entrant::Builtin(name) => name,
+            Entrant::External { name, .. } => name,
+        }
+    }
+
+    /// a fresh player for one game
+    pub fn player(&self, settings: &Settings) -> Result<Rc<RefCell<dyn Player>>, String> {
+        match self {
+            Entrant::Builtin(name) => Opponent::from_name(name)
+                .map(|opponent| opponent.player(settings))
+                .ok_or_else(|| format!("unknown engine '{}', pick one of {}", name, Opponent::NAMES.join(", "))),
+            Entrant::External { command, .. } => ExternalEngine::launch(command, ExternalEngine::DEFAULT_DEPTH)
+                .map(|engine| Rc::new(RefCell::new(engine)) as Rc<RefCell<dyn Player>>)
+                .map_err(|err| format!("failed to start '{}': {}", command, err)),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Format {
+    // everybody plays everybody
+    RoundRobin,
+    // the first entrant plays each of the others
+    Gauntlet,
+}
+
+/// A finished game of the tournament
+#[derive(Clone, Debug, PartialEq)]
+pub struct GameResult {
+    // indices into the entrants
+    pub black: usize,
+    pub white: usize,
+    pub opening: usize,
+    pub history: History,
+    // Black's discs minus White's
+    pub disc_difference: i32,
+    pub outcome: Outcome,
+}
+
+impl GameResult {
+    /// 1 for a win, 0.5 for a draw and 0 for a loss of the entrant, None when it didn't play
+    pub fn score_of(&self, entrant: usize) -> Option<f64> {
+        let black_score = match self.disc_difference.signum() {
+            1 => 1.,
+            0 => 0.5,
+            _ => 0.,
+        };
+
+        if entrant == self.black {
+            Some(black_score)
+        } else if entrant == self.white {
+            Some(1. - black_score)
+        } else {
+            None
+        }
+    }
+}
+
+/// Elo difference a score fraction stands for with its 95% confidence margin
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct EloEstimate {
+    pub difference: f64,
+    pub margin: f64,
+}
+
+impl EloEstimate {
+    // a perfect or zero score says the difference is at least this big
+    const CAP: f64 = 800.;
+
+    /// from the scores of the games, 1 for a win, 0.5 for a draw and 0 for a loss
+    pub fn from_scores(scores: &[f64]) -> Option<Self> {
+        if scores.is_empty() {
+            return None;
+        }
+
+        let games = scores.len() as f64;
+        let mean = scores.iter().sum::<f64>() / games;
+        let variance = scores.iter().map(|score| (score - mean).powi(2)).sum::<f64>() / games;
+        let error = (variance / games).sqrt() * 1.96;
+
+        let low = elo_difference(mean - error);
+        let high = elo_difference(mean + error);
+
+        Some(Self {
+            difference: elo_difference(mean),
+            margin: (high - low) / 2.,
+        })
+    }
+}
+
+/// Elo difference at which the expected score is `fraction`
+pub fn elo_difference(fraction: f64) -> f64 {
+    if fraction <= 0. {
+        return -EloEstimate::CAP;
+    }
+    if fraction >= 1. {
+        return EloEstimate::CAP;
+    }
+
+    (400. * (fraction / (1. - fraction)).log10()).clamp(-EloEstimate::CAP, EloEstimate::CAP)
+}
+
+/// Plays every pairing of the format from every opening with both colors
+pub struct Tournament {
+    pub entrants: Vec<Entrant>,
+    pub format: Format,
+    // how many of `OPENINGS` each pairing starts from
+    pub openings: usize,
+    pub settings: Settings,
+    pub results: Vec<GameResult>,
+}
+
+impl Tournament {
+    pub fn new(entrants: Vec<Entrant>, format: Format, openings: usize, settings: Settings) -> Self {
+        Self {
+            entrants,
+            format,
+            openings: openings.clamp(1, OPENINGS.len()),
+            settings,
+            results: Vec::new(),
+        }
+    }
+
+    /// pairs of entrant indices that meet
+    pub fn pairings(&self) -> Vec<(usize, usize)> {
+        let count = self.entrants.len();
+
+        match self.format {
+            Format::RoundRobin => (0..count)
+                .flat_map(|first| (first + 1..count).map(move |second| (first, second)))
+                .collect(),
+            Format::Gauntlet => (1..count).map(|other| (0, other)).collect(),
+        }
+    }
+
+    /// plays all games, `on_game` hears of every finished one
+    pub fn run(&mut self, mut on_game: impl FnMut(&Tournament, &GameResult)) -> Result<(), String> {
+        for (first, second) in self.pairings() {
+            for opening in 0..self.openings {
+                for (black, white) in [(first, second), (second, first)] {
+                    let result = self.play(black, white, opening)?;
+                    on_game(self, &result);
+                    self.results.push(result);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn play(&self, black: usize, white: usize, opening: usize) -> Result<GameResult, String> {
+        let players = [
+            self.entrants[black].player(&self.settings)?,
+            self.entrants[white].player(&self.settings)?,
+        ];
+
+        let mut history = parse_transcript(OPENINGS[opening])?;
+        let mut board: Board = history.positions().last().copied().unwrap_or_default();
+
+        while !board.is_over() {
+            let side = board.player_turn;
+            let player = &players[if side == PlayerTurn::Black { 0 } else { 1 }];
+
+            if !board.has_valid_move() {
+                history.push_pass(side);
+                board.pass();
+                continue;
+            }
+
+            let (x, y) = player.borrow_mut().choose_move(&board)
+                .filter(|(x, y)| board.is_valid_cell(*x, *y))
+                .ok_or_else(|| format!("{} made no legal move", self.entrants[if side == PlayerTurn::Black { black } else { white }].name()))?;
+
+            let flipped = board.play(x, y);
+            history.push(side, (x, y), flipped);
+        }
+
+        let (black_score, white_score) = board.score();
+
+        Ok(GameResult {
+            black,
+            white,
+            opening,
+            history,
+            disc_difference: black_score as i32 - white_score as i32,
+            outcome: Outcome::from_board(&board),
+        })
+    }
+
+    fn scores(&self, entrant: usize, opponent: Option<usize>) -> Vec<f64> {
+        self.results.iter()
+            .filter(|result| opponent.is_none_or(|opponent| result.black == opponent || result.white == opponent))
+            .filter_map(|result| result.score_of(entrant))
+            .collect()
+    }
+
+    /// standings and the result of every pairing as text tables
+    pub fn report(&self) -> String {
+        let mut out = String::new();
+        let width = self.entrants.iter().map(|entrant| entrant.name().len()).max().unwrap_or(0).max(8);
+
+        let _ = writeln!(out, "{:<width$}  games   wins  draws losses   score  elo", "entrant", width = width);
+
+        let mut standings: Vec<usize> = (0..self.entrants.len()).collect();
+        standings.sort_by(|a, b| {
+            let score = |entrant: usize| self.scores(entrant, None).iter().sum::<f64>();
+            score(*b).total_cmp(&score(*a))
+        });
+
+        for entrant in standings {
+            let scores = self.scores(entrant, None);
+            let _ = writeln!(out, "{}", Self::row(self.entrants[entrant].name(), &scores, width));
+        }
+
+        let _ = writeln!(out);
+        let _ = writeln!(out, "{:<width$}  games   wins  draws losses   score  elo", "pairing", width = width * 2 + 4);
+
+        for (first, second) in self.pairings() {
+            let scores = self.scores(first, Some(second));
+            let name = format!("{} vs {}", self.entrants[first].name(), self.entrants[second].name());
+            let _ = writeln!(out, "{}", Self::row(&name, &scores, width * 2 + 4));
+        }
+
+        out
+    }
+
+    // games, wins, draws, losses, score percentage and the Elo estimate
+    fn row(name: &str, scores: &[f64], width: usize) -> String {
+        let count = |value: f64| scores.iter().filter(|score| **score == value).count();
+        let percent = if scores.is_empty() { 0. } else { 100. * scores.iter().sum::<f64>() / scores.len() as f64 };
+        let elo = EloEstimate::from_scores(scores)
+            .map_or("-".to_string(), |elo| format!("{:+.0} ± {:.0}", elo.difference, elo.margin));
+
+        format!("{:<width$}  {:>5}  {:>5}  {:>5}  {:>5}  {:>5.1}%  {}", name, scores.len(), count(1.), count(0.5), count(0.), percent, elo, width = width)
+    }
+
+    /// every game in GGF, one per line
+    pub fn archive(&self) -> String {
+        self.results.iter()
+            .map(|result| {
+                let (black, white) = (self.entrants[result.black].name(), self.entrants[result.white].name());
+                ggf_with_outcome(&result.history, black, white, Some(&result.outcome)) + "\n"
+            })
+            .collect()
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::analysis::ASSESS_DEPTH;
+    use crate::engine::eval::DISC;
+    use crate::engine::search::{SearchLimits, Searcher};
+
+    #[test]
+    fn openings_are_balanced() {
+        // the depth of `assess` without its time limit, so the scores don't depend on the machine
+        let limits = SearchLimits { depth: ASSESS_DEPTH, time: None, soft_time: None };
+
+        for opening in OPENINGS {
+            let history = parse_transcript(opening).unwrap();
+            let board = history.positions().last().copied().unwrap();
+            let score = Searcher::new(1).search(&board, &limits).score;
+            assert!(score.abs() <= DISC / 2, "{} scores {}", opening, score);
+        }
+    }
+}
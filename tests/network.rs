@@ -0,0 +1,215 @@
+//! Sessions and the server over 127.0.0.1, each test on ports of its own.
+
+use std::io::{Read, Write};
+use std::net::TcpStream;
+use std::thread;
+use std::time::{Duration, Instant};
+use reversi::board::{parse_move, PlayerTurn};
+use reversi::net::protocol::{Connection, Message, VERSION};
+use reversi::net::server::Server;
+use reversi::net::session::{NetEvent, Session};
+use reversi::notation::transcript;
+
+
+// how long a test waits for a message before it fails
+const WAIT: Duration = Duration::from_secs(5);
+
+fn connect(server: &Server) -> Connection {
+    Connection::new(TcpStream::connect(("127.0.0.1", server.port())).unwrap()).unwrap()
+}
+
+fn hello(token: Option<&str>, name: &str) -> Message {
+    Message::Hello { version: VERSION, token: token.map(str::to_string), name: name.to_string() }
+}
+
+// polls the server until the client receives a message the test is after
+fn expect(server: &mut Server, client: &mut Connection, wanted: impl Fn(&Message) -> bool) -> Message {
+    let started = Instant::now();
+
+    while started.elapsed() < WAIT {
+        server.poll();
+
+        for message in client.receive().unwrap() {
+            let message = message.unwrap();
+            if wanted(&message) {
+                return message;
+            }
+        }
+
+        thread::sleep(Duration::from_millis(5));
+    }
+
+    panic!("the message never came");
+}
+
+// seats Ann and Bob in a game, returns their connections and Ann's token
+fn pair(server: &mut Server) -> (Connection, Connection, String) {
+    let mut ann = connect(server);
+    ann.send(&hello(None, "Ann")).unwrap();
+    ann.send(&Message::Seek).unwrap();
+    expect(server, &mut ann, |message| matches!(message, Message::Info(_)));
+
+    let mut bob = connect(server);
+    bob.send(&hello(None, "Bob")).unwrap();
+    bob.send(&Message::Seek).unwrap();
+    expect(server, &mut bob, |message| matches!(message, Message::Welcome { .. }));
+
+    let Message::Welcome { token, .. } = expect(server, &mut ann, |message| matches!(message, Message::Welcome { .. })) else {
+        unreachable!();
+    };
+
+    (ann, bob, token)
+}
+
+#[test]
+fn a_seat_is_reclaimed_with_its_token_only() {
+    let mut server = Server::bind(0, None).unwrap();
+    let (ann, _bob, token) = pair(&mut server);
+
+    drop(ann);
+    // the server notices Ann left
+    for _ in 0..20 {
+        server.poll();
+        thread::sleep(Duration::from_millis(5));
+    }
+
+    let mut impostor = connect(&server);
+    impostor.send(&hello(None, "Ann")).unwrap();
+    expect(&mut server, &mut impostor, |message| matches!(message, Message::Error(_)));
+
+    let mut ann = connect(&server);
+    ann.send(&hello(Some(&token), "Ann")).unwrap();
+    let welcome = expect(&mut server, &mut ann, |message| matches!(message, Message::Welcome { .. }));
+    assert!(matches!(welcome, Message::Welcome { name, .. } if name == "Bob"));
+}
+
+#[test]
+fn hello_is_said_once() {
+    let mut server = Server::bind(0, None).unwrap();
+    let mut ann = connect(&server);
+
+    ann.send(&hello(None, "Ann")).unwrap();
+    ann.send(&hello(None, "Bob")).unwrap();
+    let error = expect(&mut server, &mut ann, |message| matches!(message, Message::Error(_)));
+    assert_eq!(error, Message::Error("HELLO was said already".to_string()));
+}
+
+#[test]
+fn an_endless_line_drops_the_client() {
+    let mut server = Server::bind(0, None).unwrap();
+    let mut stream = TcpStream::connect(("127.0.0.1", server.port())).unwrap();
+    stream.write_all(&[b'a'; 8 * 1024]).unwrap();
+    stream.set_read_timeout(Some(Duration::from_millis(10))).unwrap();
+
+    let started = Instant::now();
+    let mut byte = [0_u8; 1];
+
+    while started.elapsed() < WAIT {
+        server.poll();
+        if matches!(stream.read(&mut byte), Ok(0)) {
+            return;
+        }
+    }
+
+    panic!("the client is still connected");
+}
+
+#[test]
+fn a_seat_is_taken_over_from_a_stale_connection() {
+    let mut server = Server::bind(0, None).unwrap();
+    let (mut stale, _bob, token) = pair(&mut server);
+
+    // the old connection is still open as far as the server knows
+    let mut ann = connect(&server);
+    ann.send(&hello(Some(&token), "Ann")).unwrap();
+    expect(&mut server, &mut ann, |message| matches!(message, Message::Welcome { .. }));
+
+    let started = Instant::now();
+    while stale.receive().is_ok() {
+        assert!(started.elapsed() < WAIT, "the stale connection is still open");
+        server.poll();
+        thread::sleep(Duration::from_millis(5));
+    }
+}
+
+// polls both sessions until the condition holds
+fn settle(host: &mut Session, guest: &mut Session, done: impl Fn(&Session, &Session) -> bool) {
+    let started = Instant::now();
+
+    while !done(host, guest) {
+        assert!(started.elapsed() < WAIT, "host: {}, guest: {}", host.status(), guest.status());
+        host.poll();
+        guest.poll();
+        thread::sleep(Duration::from_millis(5));
+    }
+}
+
+#[test]
+fn host_and_guest_play_over_loopback() {
+    let mut host = Session::host(0, "Ann").unwrap();
+    let address = format!("127.0.0.1:{}", host.port().unwrap());
+
+    // a connection that never says HELLO doesn't keep the guest out
+    let _silent = TcpStream::connect(&address).unwrap();
+
+    let mut guest = Session::join(&address, "Bob").unwrap();
+    settle(&mut host, &mut guest, |host, guest| host.is_connected() && guest.is_connected());
+
+    assert_eq!(host.peer_name.as_deref(), Some("Bob"));
+    assert_eq!(guest.peer_name.as_deref(), Some("Ann"));
+    assert_eq!(guest.side, PlayerTurn::White);
+
+    let (f5, d6) = (parse_move("f5").unwrap(), parse_move("d6").unwrap());
+    host.play(f5.0, f5.1).unwrap();
+    settle(&mut host, &mut guest, |_, guest| guest.is_my_turn());
+    guest.play(d6.0, d6.1).unwrap();
+    settle(&mut host, &mut guest, |host, guest| host.is_my_turn() && guest.game.history.len() == 2);
+
+    assert_eq!(transcript(&host.game.history), "f5d6");
+    assert_eq!(transcript(&guest.game.history), "f5d6");
+
+    // somebody else is turned away while Bob plays
+    let mut intruder = Session::join(&address, "Eve").unwrap();
+    let started = Instant::now();
+    while !intruder.poll().iter().any(|event| matches!(event, NetEvent::Rejected(text) if text == "the game is full")) {
+        assert!(started.elapsed() < WAIT, "Eve was never turned away");
+        host.poll();
+        thread::sleep(Duration::from_millis(5));
+    }
+    assert_eq!(host.peer_name.as_deref(), Some("Bob"));
+}
+
+// the token of the WELCOME the host sends over the connection
+fn wait_for_welcome(host: &mut Session, client: &mut Connection) -> String {
+    let started = Instant::now();
+
+    while started.elapsed() < WAIT {
+        host.poll();
+
+        for message in client.receive().unwrap() {
+            if let Ok(Message::Welcome { token, .. }) = message {
+                return token;
+            }
+        }
+
+        thread::sleep(Duration::from_millis(5));
+    }
+
+    panic!("no WELCOME");
+}
+
+#[test]
+fn a_returning_guest_replaces_its_stale_connection() {
+    let mut host = Session::host(0, "Ann").unwrap();
+    let address = ("127.0.0.1", host.port().unwrap());
+
+    let mut first = Connection::new(TcpStream::connect(address).unwrap()).unwrap();
+    first.send(&hello(None, "Bob")).unwrap();
+    let token = wait_for_welcome(&mut host, &mut first);
+
+    // Bob's first connection still looks open to the host when he is back
+    let mut second = Connection::new(TcpStream::connect(address).unwrap()).unwrap();
+    second.send(&hello(Some(&token), "Bob")).unwrap();
+    assert_eq!(wait_for_welcome(&mut host, &mut second), token);
+    assert!(host.is_connected());
+}
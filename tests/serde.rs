@@ -0,0 +1,119 @@
+//! The JSON the "serde" feature produces, run with `cargo test --features serde`. Every type
+//! goes through JSON and back, and the JSON is spelled out for the types whose representation
+//! other tools may read.
+#![cfg(feature = "serde")]
+
+use std::fmt::Debug;
+use std::time::Duration;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use reversi::board::{Board, Cell, PlayerTurn};
+use reversi::clock::{Clock, TimeControl};
+use reversi::history::GameRecord;
+use reversi::mode::{GameMode, Opponent};
+use reversi::notation::parse_transcript;
+use reversi::outcome::Outcome;
+use reversi::rating::GameEntry;
+use reversi::save::SavedGame;
+use reversi::settings::Settings;
+
+
+// the value as JSON, checked to read back as the same value
+fn round_trip<T: Serialize + DeserializeOwned + PartialEq + Debug>(value: &T) -> String {
+    let json = serde_json::to_string(value).unwrap();
+    let back: T = serde_json::from_str(&json).unwrap();
+    assert_eq!(&back, value, "{} reads back differently", json);
+    json
+}
+
+#[test]
+fn board_is_its_board_string() {
+    let board = Board::new();
+    assert_eq!(round_trip(&board), r#""---------------------------OX------XO--------------------------- X""#);
+
+    let after = parse_transcript("f5d6").unwrap().positions().last().copied().unwrap();
+    assert_eq!(round_trip(&after), r#""---------------------------OX------OXX-----O-------------------- X""#);
+}
+
+#[test]
+fn cells_and_sides() {
+    assert_eq!(round_trip(&Cell::Black(0)), r#""X""#);
+    assert_eq!(round_trip(&Cell::White(0)), r#""O""#);
+    assert_eq!(round_trip(&Cell::Free), r#""-""#);
+    // the flip animation is left out
+    assert_eq!(serde_json::to_string(&Cell::White(2)).unwrap(), r#""O""#);
+
+    assert_eq!(round_trip(&PlayerTurn::Black), r#""black""#);
+    assert_eq!(round_trip(&PlayerTurn::White), r#""white""#);
+}
+
+#[test]
+fn history_is_its_transcript() {
+    // Black has to pass before e3, the pass comes back from the moves
+    let history = parse_transcript("d3c3b3b2f5a3a1c1e3").unwrap();
+    assert_eq!(history.len(), 10);
+    assert_eq!(round_trip(&history), r#""d3c3b3b2f5a3a1c1e3""#);
+    assert!(serde_json::from_str::<reversi::history::History>(r#""f5f5""#).is_err());
+
+    let record = GameRecord { history: parse_transcript("f5d6").unwrap(), evals: vec![0, -4, 10] };
+    assert_eq!(round_trip(&record), r#"{"history":"f5d6","evals":[0,-4,10]}"#);
+}
+
+#[test]
+fn modes() {
+    assert_eq!(round_trip(&GameMode::PvP), r#""pvp""#);
+    assert_eq!(round_trip(&GameMode::PvE(Opponent::AlphaBeta(8))), r#"{"pve":{"alphabeta":8}}"#);
+    assert_eq!(round_trip(&GameMode::PvE(Opponent::Greedy(0.2))), r#"{"pve":{"greedy":0.2}}"#);
+
+    for (_, mode) in GameMode::PRESETS {
+        round_trip(&mode);
+    }
+}
+
+#[test]
+fn outcomes() {
+    assert_eq!(round_trip(&Outcome::resigned(PlayerTurn::White)), r#"{"winner":"black","reason":"resign"}"#);
+    assert_eq!(round_trip(&Outcome::agreed_draw()), r#"{"winner":null,"reason":"agreed"}"#);
+    round_trip(&Outcome::from_board(&Board::new()));
+    round_trip(&Outcome::on_time(PlayerTurn::Black));
+}
+
+#[test]
+fn clocks_count_milliseconds() {
+    let control = TimeControl::Fischer { base: Duration::from_secs(180), increment: Duration::from_secs(2) };
+    assert_eq!(round_trip(&control), r#"{"fischer":{"base":180000,"increment":2000}}"#);
+
+    for control in TimeControl::PRESETS {
+        let mut clock = Clock::new(control);
+        clock.tick(Duration::from_millis(1234));
+        round_trip(&clock);
+    }
+}
+
+#[test]
+fn settings_and_records() {
+    round_trip(&Settings::default());
+
+    let history = parse_transcript("f5d6c3d3c4").unwrap();
+    let entry = GameEntry::new("PvP", "Ann", "Bob", &history, Outcome::resigned(PlayerTurn::Black));
+    assert_eq!(entry.opening, "f5d6c3d3");
+    round_trip(&entry);
+}
+
+#[test]
+fn saved_game() {
+    let control = TimeControl::PRESETS[2];
+    let saved = SavedGame {
+        mode: GameMode::PvE(Opponent::Mcts(20_000)),
+        black: "Ann".to_string(),
+        white: "Bob".to_string(),
+        engine_command: String::new(),
+        external_black: false,
+        external_white: false,
+        clocks: Some((Clock::new(control), Clock::new(control))),
+        history: parse_transcript("f5d6c3d3c4").unwrap(),
+    };
+
+    let json = round_trip(&saved);
+    assert!(json.contains(r#""history":"f5d6c3d3c4""#), "{}", json);
+}